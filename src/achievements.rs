@@ -0,0 +1,154 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::{daylight, Event, State};
+
+/// Farthest, in m, from the middle of the goal zone a stop counts as
+/// perfectly centred.
+pub const PERFECT_STOP_DISTANCE: f64 = 0.5;
+
+/// Daylight below which a win counts as a night win: before 4 and after 20
+/// o'clock.
+pub const NIGHT_DAYLIGHT: f64 = 0.25;
+
+/// Something a player can unlock.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Achievement {
+    /// Winning any run.
+    FirstWin,
+    /// Winning within [`PERFECT_STOP_DISTANCE`] of the middle of the goal
+    /// zone.
+    PerfectCentreStop,
+    /// Winning a run without touching the brake.
+    NoBrakeWin,
+    /// Winning a run at night.
+    NightWin
+}
+
+/// Achievements a player unlocked, unlocking more as they watch the runs
+/// go by tick after tick.
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Achievements {
+    /// In the order they were unlocked.
+    unlocked: Vec<Achievement>,
+    /// Set once the brake was pressed in the run being watched.
+    #[serde(skip)]
+    braked: bool,
+    /// Ticks of the last state watched, to tell when a run restarts.
+    #[serde(skip)]
+    ticks: u32
+}
+
+#[wasm_bindgen]
+impl Achievements {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Achievements {
+        Achievements::default()
+    }
+
+    /// Watches the tick that led to `state`, and returns the achievements
+    /// it unlocked.
+    pub fn observe(&mut self, state: &State) -> Vec<Achievement> {
+        if state.ticks <= self.ticks {
+            self.braked = false;
+        }
+        self.ticks = state.ticks;
+        self.braked |= state.pedals.brake > 0.0;
+        if !state.events.contains(&Event::Won) {
+            return Vec::new();
+        }
+        let centre = (state.position_goal_start + state.position_goal_end) / 2.0;
+        let earned = [
+            (Achievement::FirstWin, true),
+            (Achievement::PerfectCentreStop, (state.trailer_position() - centre).abs() <= PERFECT_STOP_DISTANCE),
+            (Achievement::NoBrakeWin, !self.braked),
+            (Achievement::NightWin, daylight(state.time_of_day) < NIGHT_DAYLIGHT)
+        ];
+        let mut unlocked = Vec::new();
+        for (achievement, earned) in earned {
+            if earned && !self.is_unlocked(achievement) {
+                self.unlocked.push(achievement);
+                unlocked.push(achievement);
+            }
+        }
+        unlocked
+    }
+
+    pub fn is_unlocked(&self, achievement: Achievement) -> bool {
+        self.unlocked.contains(&achievement)
+    }
+
+    /// Achievements unlocked, in the order they were.
+    #[wasm_bindgen(getter)]
+    pub fn unlocked(&self) -> Vec<Achievement> {
+        self.unlocked.clone()
+    }
+
+    /// The unlocked achievements as JSON, for saving them.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("achievements should serialize to JSON")
+    }
+
+    /// Achievements saved with [`Achievements::to_json`], or why `json`
+    /// isn't them.
+    pub fn from_json(json: &str) -> Result<Achievements, String> {
+        serde_json::from_str(json).map_err(|error| error.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::Input;
+
+    fn won(position: f64) -> State {
+        State { position, ticks: 10, events: vec![Event::Won], won: true, ..State::new() }
+    }
+
+    #[test]
+    fn a_win_should_unlock_first_win_once(){
+        let mut achievements = Achievements::new();
+        achievements.observe(&State { ticks: 1, pedals: Input::new(0.0, 1.0), ..State::new() });
+
+        assert_eq!(vec![Achievement::FirstWin], achievements.observe(&won(9200.0)));
+        assert_eq!(Vec::<Achievement>::new(), achievements.observe(&State { ticks: 11, ..won(9200.0) }));
+    }
+
+    #[test]
+    fn a_centred_win_without_braking_should_unlock_both(){
+        let mut achievements = Achievements::new();
+
+        let unlocked = achievements.observe(&won(9500.0));
+        assert!(unlocked.contains(&Achievement::PerfectCentreStop));
+        assert!(unlocked.contains(&Achievement::NoBrakeWin));
+        assert!(!unlocked.contains(&Achievement::NightWin));
+    }
+
+    #[test]
+    fn braking_should_be_forgotten_when_the_run_restarts(){
+        let mut achievements = Achievements::new();
+        achievements.observe(&State { ticks: 5, pedals: Input::new(0.0, 1.0), ..State::new() });
+        achievements.observe(&State { ticks: 1, ..State::new() });
+
+        assert!(achievements.observe(&won(9200.0)).contains(&Achievement::NoBrakeWin));
+    }
+
+    #[test]
+    fn a_win_at_midnight_should_unlock_night_win(){
+        let mut achievements = Achievements::new();
+
+        assert!(achievements.observe(&State { time_of_day: 0.0, ..won(9200.0) }).contains(&Achievement::NightWin));
+    }
+
+    #[test]
+    fn achievements_should_round_trip_through_json(){
+        let mut achievements = Achievements::new();
+        achievements.observe(&won(9500.0));
+
+        let loaded = Achievements::from_json(&achievements.to_json()).unwrap();
+        assert_eq!(achievements.unlocked(), loaded.unlocked());
+    }
+}
@@ -0,0 +1,110 @@
+use wasm_bindgen::prelude::*;
+
+use crate::track::slope_deceleration;
+use crate::{damaged_performance, Input, State};
+
+/// Share of the full braking the autopilot plans its stop with. The margin
+/// lets it brake harder when the stop comes up short.
+const PLANNED_BRAKING: f64 = 0.5;
+
+/// Share of the level's max entry speed the autopilot aims to enter the
+/// goal zone at, as its braking only takes effect a tick later.
+const PLANNED_ENTRY: f64 = 0.9;
+
+/// Inputs an AI driver gives in `state` to stop in the middle of the goal
+/// zone, for demo mode and as a scoring baseline.
+///
+/// It accelerates at half the braking the car can manage, on top of what
+/// holds it on an uphill slope, until stopping at that rate would just
+/// reach the target, then brakes just hard enough to stop there. On levels with a max entry speed it also slows down to
+/// enter the goal zone below it and never speeds up past it.
+#[wasm_bindgen]
+pub fn autopilot(state: &State) -> Input {
+    let vehicle = &state.vehicle;
+    let target = (state.position_goal_start + state.position_goal_end) / 2.0;
+    let distance = target - state.position;
+    let friction = state.friction();
+    let (full_braking, _) = vehicle.braking(1.0, friction, state.speed, state.brake_temperature);
+    let full_braking = full_braking * state.trailer.load_share(vehicle.mass) * damaged_performance(state.damage);
+
+    if distance <= 0.0 {
+        return Input::new(0.0, 1.0);
+    }
+    let stopping = state.speed * state.speed / (2.0 * distance);
+    // Levels with a max entry speed need the car slowed down by the start
+    // of the goal zone already.
+    let entry = state.position_goal_start - state.position;
+    let entry_speed = PLANNED_ENTRY * state.max_entry_speed;
+    let stopping = if entry > 0.0 && state.speed > entry_speed {
+        let slowing = (state.speed * state.speed - entry_speed * entry_speed) / (2.0 * entry);
+        stopping.max(slowing)
+    } else {
+        stopping
+    };
+    if stopping >= PLANNED_BRAKING * full_braking {
+        // Brake deceleration grows with the square of the pedal.
+        let pedal = (stopping / full_braking).sqrt().min(1.0);
+        return Input::new(0.0, pedal);
+    }
+    if state.speed >= entry_speed {
+        return Input::new(0.0, 0.0);
+    }
+    // Uphill, the throttle also has to hold the car against the slope.
+    let climb = slope_deceleration(state.track.gradient_at(state.position)).max(0.0);
+    let throttle = vehicle.throttle_for(PLANNED_BRAKING * full_braking + climb, state.rpm, state.gear);
+    Input::new(throttle, 0.0)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::{update, Level, Powertrain, Vehicle};
+
+    fn drive(mut state: State) -> State {
+        for _ in 0..100_000 {
+            let input = autopilot(&state);
+            state = update(state, input, 0.1);
+            if state.won || state.lost {
+                break;
+            }
+        }
+        state
+    }
+
+    #[test]
+    fn autopilot_should_stop_the_arcade_car_in_the_goal_zone(){
+        let state = drive(State::new());
+
+        assert!(state.won);
+    }
+
+    #[test]
+    fn autopilot_should_stop_an_electric_car_in_the_goal_zone(){
+        let vehicle = Vehicle {
+            mass: 1200.0,
+            brake_force: 9000.0,
+            powertrain: Powertrain::Electric,
+            motor_force: 4000.0,
+            ..Default::default()
+        };
+        let state = drive(State { vehicle, ..State::new() });
+
+        assert!(state.won);
+    }
+
+    #[test]
+    fn autopilot_should_win_every_level(){
+        for index in 0..Level::count() {
+            let state = drive(State::from_level(&Level::get(index).unwrap()));
+            assert!(state.won, "level {}: {:?}", index, state.lose_reason);
+        }
+    }
+
+    #[test]
+    fn autopilot_should_brake_fully_past_the_goal_target(){
+        let state = State { position: 9600.0, speed: 3.0, ..State::new() };
+
+        assert_eq!(Input::new(0.0, 1.0), autopilot(&state));
+    }
+}
@@ -0,0 +1,269 @@
+use wasm_bindgen::prelude::*;
+
+use crate::{BoundsError, Difficulty, Endless, Forecast, GoalMotion, Level, LoseConditions, OvershootPolicy, State, Track, Trailer, Vehicle, WinCondition, Wind};
+
+/// Composes a [`State`] one setting at a time, for scenarios that don't
+/// fit a built-in level, so the JS side can chain
+/// `new StateBuilder().position(0).goal(100, 120).build()`.
+///
+/// Settings left out keep the value of the level the builder starts from,
+/// the first level for [`StateBuilder::new`].
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct StateBuilder {
+    level: Level,
+    speed: f64,
+    fuel: Option<f64>,
+    battery: Option<f64>,
+    damage: f64,
+    difficulty: Difficulty
+}
+
+#[wasm_bindgen]
+impl StateBuilder {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> StateBuilder {
+        StateBuilder::from_level(&Level::default())
+    }
+
+    pub fn from_level(level: &Level) -> StateBuilder {
+        StateBuilder {
+            level: level.clone(),
+            speed: 0.0,
+            fuel: None,
+            battery: None,
+            damage: 0.0,
+            difficulty: Difficulty::Normal
+        }
+    }
+
+    pub fn position(mut self, position: f64) -> StateBuilder {
+        self.level.start_position = position;
+        self
+    }
+
+    pub fn goal(mut self, start: f64, end: f64) -> StateBuilder {
+        self.level.goal_start = start;
+        self.level.goal_end = end;
+        self
+    }
+
+    pub fn speed(mut self, speed: f64) -> StateBuilder {
+        self.speed = speed;
+        self
+    }
+
+    pub fn vehicle(mut self, vehicle: Vehicle) -> StateBuilder {
+        self.level.vehicle = vehicle;
+        self
+    }
+
+    pub fn track(mut self, track: Track) -> StateBuilder {
+        self.level.track = track;
+        self
+    }
+
+    pub fn wind(mut self, wind: Wind) -> StateBuilder {
+        self.level.wind = wind;
+        self
+    }
+
+    pub fn forecast(mut self, forecast: Forecast) -> StateBuilder {
+        self.level.forecast = forecast;
+        self
+    }
+
+    pub fn time_of_day(mut self, time_of_day: f64) -> StateBuilder {
+        self.level.time_of_day = time_of_day;
+        self
+    }
+
+    /// In-game hours going by per simulated second, 0 stopping the clock.
+    pub fn time_scale(mut self, time_scale: f64) -> StateBuilder {
+        self.level.time_scale = time_scale;
+        self
+    }
+
+    pub fn speed_limit(mut self, speed_limit: f64) -> StateBuilder {
+        self.level.speed_limit = speed_limit;
+        self
+    }
+
+    pub fn max_entry_speed(mut self, max_entry_speed: f64) -> StateBuilder {
+        self.level.max_entry_speed = max_entry_speed;
+        self
+    }
+
+    pub fn nitrous_charges(mut self, charges: u32) -> StateBuilder {
+        self.level.nitrous_charges = charges;
+        self
+    }
+
+    /// Makes the run a time trial to be won within `target_time` seconds.
+    pub fn target_time(mut self, target_time: f64) -> StateBuilder {
+        self.level.target_time = target_time;
+        self
+    }
+
+    pub fn win_condition(mut self, win_condition: WinCondition) -> StateBuilder {
+        self.level.win_condition = win_condition;
+        self
+    }
+
+    pub fn energy_reserve(mut self, energy_reserve: f64) -> StateBuilder {
+        self.level.energy_reserve = energy_reserve;
+        self
+    }
+
+    pub fn position_tolerance(mut self, position_tolerance: f64) -> StateBuilder {
+        self.level.position_tolerance = position_tolerance;
+        self
+    }
+
+    pub fn lose_conditions(mut self, lose_conditions: LoseConditions) -> StateBuilder {
+        self.level.lose_conditions = lose_conditions;
+        self
+    }
+
+    /// What driving past the goal zone does, with the ticks the car may
+    /// stay past it under [`OvershootPolicy::GraceWindow`].
+    pub fn overshoot_policy(mut self, policy: OvershootPolicy, grace_ticks: u32) -> StateBuilder {
+        self.level.overshoot_policy = policy;
+        self.level.overshoot_grace_ticks = grace_ticks;
+        self
+    }
+
+    pub fn endless(mut self, endless: Endless) -> StateBuilder {
+        self.level.endless = endless;
+        self
+    }
+
+    /// Scores a win needs for two and three stars.
+    pub fn star_scores(mut self, two_star_score: f64, three_star_score: f64) -> StateBuilder {
+        self.level.two_star_score = two_star_score;
+        self.level.three_star_score = three_star_score;
+        self
+    }
+
+    /// Fuel in the tank. Defaults to a full tank.
+    pub fn fuel(mut self, fuel: f64) -> StateBuilder {
+        self.fuel = Some(fuel);
+        self
+    }
+
+    /// Battery charge. Defaults to a full battery.
+    pub fn battery(mut self, battery: f64) -> StateBuilder {
+        self.battery = Some(battery);
+        self
+    }
+
+    pub fn damage(mut self, damage: f64) -> StateBuilder {
+        self.damage = damage;
+        self
+    }
+
+    /// Preset the level is played at, applied on top of every other
+    /// setting.
+    pub fn difficulty(mut self, difficulty: Difficulty) -> StateBuilder {
+        self.difficulty = difficulty;
+        self
+    }
+
+    pub fn trailer(mut self, trailer: Trailer) -> StateBuilder {
+        self.level.trailer = trailer;
+        self
+    }
+
+    /// Adds a checkpoint at `position` to be crossed by `split_time`,
+    /// `f64::MAX` for none.
+    pub fn checkpoint(mut self, position: f64, split_time: f64) -> StateBuilder {
+        self.level.add_checkpoint(position, split_time);
+        self
+    }
+
+    /// Adds a zone from `start` to `end` worth `value` times the precision
+    /// points of the main goal zone.
+    pub fn goal_zone(mut self, start: f64, end: f64, value: f64) -> StateBuilder {
+        self.level.add_goal_zone(start, end, value);
+        self
+    }
+
+    pub fn goal_motion(mut self, goal_motion: GoalMotion) -> StateBuilder {
+        self.level.goal_motion = goal_motion;
+        self
+    }
+
+    /// Adds a traffic car at `position` driving at `cruise_speed`.
+    pub fn traffic(mut self, position: f64, cruise_speed: f64) -> StateBuilder {
+        self.level.add_traffic(position, cruise_speed);
+        self
+    }
+
+    /// The composed state, or why its start and goal bounds don't make
+    /// sense.
+    pub fn build(self) -> Result<State, BoundsError> {
+        self.level.validate()?;
+        let mut state = State::from_level_at(&self.level, self.difficulty);
+        state.speed = self.speed;
+        state.fuel = self.fuel.unwrap_or(state.fuel);
+        state.battery = self.battery.unwrap_or(state.battery);
+        state.damage = self.damage;
+        Ok(state)
+    }
+}
+
+impl Default for StateBuilder {
+    fn default() -> StateBuilder {
+        StateBuilder::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn builder_without_settings_should_build_the_first_level(){
+        let state = StateBuilder::new().build().unwrap();
+        let first = State::new();
+
+        assert_eq!(first.position, state.position);
+        assert_eq!(first.position_goal_start, state.position_goal_start);
+        assert_eq!(first.fuel, state.fuel);
+    }
+
+    #[test]
+    fn builder_should_apply_every_setting(){
+        let vehicle = Vehicle { mass: 2.0, tank_capacity: 40.0, ..Default::default() };
+        let state = StateBuilder::new()
+            .position(10.0)
+            .goal(100.0, 120.0)
+            .vehicle(vehicle.clone())
+            .fuel(5.0)
+            .damage(0.25)
+            .traffic(50.0, 3.0)
+            .build()
+            .unwrap();
+
+        assert_eq!(10.0, state.position);
+        assert_eq!(100.0, state.position_goal_start);
+        assert_eq!(120.0, state.position_goal_end);
+        assert_eq!(vehicle, state.vehicle);
+        assert_eq!(5.0, state.fuel);
+        assert_eq!(0.25, state.damage);
+        assert_eq!(50.0, state.traffic[0].position);
+    }
+
+    #[test]
+    fn builder_should_start_with_a_full_tank_of_the_chosen_vehicle(){
+        let vehicle = Vehicle { tank_capacity: 40.0, ..Default::default() };
+
+        assert_eq!(40.0, StateBuilder::new().vehicle(vehicle).build().unwrap().fuel);
+    }
+
+    #[test]
+    fn builder_should_reject_insane_bounds(){
+        assert_eq!(Some(BoundsError::EmptyGoal), StateBuilder::new().goal(20.0, 10.0).build().err());
+    }
+}
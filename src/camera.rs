@@ -0,0 +1,586 @@
+use wasm_bindgen::prelude::*;
+
+use crate::Event;
+
+/// Pixels the camera shakes by when the car crashes.
+pub const COLLISION_SHAKE: f64 = 20.0;
+
+/// Pixels the camera shakes by on a harsh landing.
+pub const LANDING_SHAKE: f64 = 8.0;
+
+/// Seconds it takes a shake to die down to half.
+pub const SHAKE_HALF_LIFE: f64 = 0.15;
+
+/// Back and forth swings per second of a shake.
+const SHAKE_FREQUENCY: f64 = 12.0;
+
+/// Slowest the camera closes the gap to its target, in m per follow, so
+/// the easing ends on the target instead of creeping towards it forever.
+const SETTLE_STEP: f64 = 0.05;
+
+/// What a camera can be attached to, to follow it.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CameraTarget {
+    /// The player's car.
+    Player,
+    /// The ghost car of a raced replay.
+    Ghost,
+    /// A traffic car, by its index from the rearmost.
+    Traffic
+}
+
+/// How the camera moves up and down.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum VerticalPolicy {
+    /// The camera stays at its height, for flat levels.
+    #[default]
+    Fixed,
+    /// The camera rises and sinks with what it follows, over hills.
+    Follow
+}
+
+/// A point on the screen, in fractional pixels from the top left corner.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScreenPoint {
+    pub x: f64,
+    pub y: f64
+}
+
+/// A stretch of the screen, in fractional pixels from the left.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScreenSpan {
+    pub left: f64,
+    pub right: f64
+}
+
+/// Maps world positions to screen pixels around a point of the road.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct Camera {
+    pub screen_size: i32,
+    /// Stretch of road, in m, the screen shows at zoom 1.
+    pub world_size: i32,
+    /// Point of the road at the centre of the view, in fractional metres
+    /// so following and zooming stay smooth.
+    pub world_position: f64,
+    /// Magnification: 2 shows half the road, 0.5 twice as much.
+    pub zoom: f64,
+    /// Half-width, in m, of the window around the view's centre the
+    /// followed car moves freely in before the camera moves, 0 keeping
+    /// it centred.
+    pub dead_zone: f64,
+    /// Seconds of travel the view leads the followed car by, to show
+    /// the road ahead sooner at speed. 0 keeps it on the car.
+    pub look_ahead: f64,
+    /// Distance, in m, the view currently leads the car by.
+    lead: f64,
+    /// Zoom a [`Camera::zoom_to`] is heading for.
+    zoom_target: f64,
+    /// Change of zoom per second until the target is reached.
+    zoom_rate: f64,
+    pub screen_height: i32,
+    /// Left edge of the camera's viewport on the screen, for split
+    /// screens.
+    pub screen_x: i32,
+    /// Top edge of the camera's viewport on the screen.
+    pub screen_y: i32,
+    /// Road height, in m, the camera looks at.
+    pub height: f64,
+    /// Pixels below the middle of the screen the looked-at height is
+    /// drawn at, to leave more sky than ground.
+    pub vertical_offset: f64,
+    pub vertical_policy: VerticalPolicy,
+    /// Pixels the view currently shakes by, dying down tick after tick.
+    pub shake: f64,
+    /// Seconds since the shake started, to swing it back and forth.
+    shake_time: f64,
+    /// Stretch of world the view has to stay within, if any.
+    bounds: Option<(f64, f64)>
+}
+
+#[wasm_bindgen]
+impl Camera {
+    #[wasm_bindgen(constructor)]
+    pub fn new(screen_size: i32, world_size: i32) -> Camera {
+        Camera {
+            screen_size,
+            world_size,
+            world_position: 0.0,
+            zoom: 1.0,
+            dead_zone: 0.0,
+            look_ahead: 0.0,
+            lead: 0.0,
+            zoom_target: 1.0,
+            zoom_rate: 0.0,
+            screen_height: screen_size,
+            screen_x: 0,
+            screen_y: 0,
+            height: 0.0,
+            vertical_offset: 0.0,
+            vertical_policy: VerticalPolicy::Fixed,
+            shake: 0.0,
+            shake_time: 0.0,
+            bounds: None
+        }
+    }
+    pub fn project(&self, world_position: i32) -> i32{
+        self.project_precise(world_position as f64).round() as i32
+    }
+
+    /// Screen position of `world_position` in fractional pixels, for
+    /// sub-pixel smooth drawing where [`Camera::project`] steps from one
+    /// whole pixel to the next.
+    pub fn project_precise(&self, world_position: f64) -> f64 {
+        let world_size = self.world_size as f64 / self.zoom;
+        self.screen_size as f64 * (self.world_position + world_size / 2.0 - world_position) / world_size
+            + self.horizontal_offset()
+    }
+
+    /// World position drawn at `screen_x`, the inverse of
+    /// [`Camera::project_precise`], for mapping clicks and taps back onto
+    /// the road.
+    pub fn unproject(&self, screen_x: f64) -> f64 {
+        let world_size = self.world_size as f64 / self.zoom;
+        self.world_position + world_size / 2.0 - (screen_x - self.horizontal_offset()) * world_size / self.screen_size as f64
+    }
+
+    /// Screen position, in fractional pixels, of `world_position` on a
+    /// background layer scrolling at `depth_factor` of the road's pace: 1
+    /// scrolls with the road, 0.2 is far hills, 0 a sky that never moves.
+    pub fn project_layer(&self, world_position: f64, depth_factor: f64) -> f64 {
+        let world_size = self.world_size as f64 / self.zoom;
+        self.screen_size as f64 * (self.world_position * depth_factor + world_size / 2.0 - world_position) / world_size
+            + self.horizontal_offset()
+    }
+
+    /// Screen span of the stretch of road from `world_start` to
+    /// `world_end`, such as a goal zone or an obstacle, cut to the
+    /// viewport. None when none of it is in view.
+    pub fn project_range(&self, world_start: f64, world_end: f64) -> Option<ScreenSpan> {
+        let (first, second) = (self.project_precise(world_start), self.project_precise(world_end));
+        let viewport_left = self.screen_x as f64;
+        let viewport_right = viewport_left + self.screen_size as f64;
+        let left = first.min(second).max(viewport_left);
+        let right = first.max(second).min(viewport_right);
+        if left < right {
+            Some(ScreenSpan { left, right })
+        } else {
+            None
+        }
+    }
+
+    /// Screen height of a point `height` m up, in fractional pixels from
+    /// the top, on the same scale as the road.
+    pub fn project_height(&self, height: f64) -> f64 {
+        let scale = self.screen_size as f64 * self.zoom / self.world_size as f64;
+        self.screen_y as f64 + self.screen_height as f64 / 2.0 + self.vertical_offset - (height - self.height) * scale
+    }
+
+    /// Screen point of the world point `position` along the road and
+    /// `height` up.
+    pub fn project_point(&self, position: f64, height: f64) -> ScreenPoint {
+        ScreenPoint {
+            x: self.project_precise(position),
+            y: self.project_height(height)
+        }
+    }
+
+    /// Eases the camera towards `height` like [`Camera::follow`] does along
+    /// the road, when its vertical policy follows.
+    pub fn follow_height(&mut self, height: f64, smoothing: f64) {
+        if self.vertical_policy == VerticalPolicy::Follow {
+            self.height += (height - self.height) * smoothing.clamp(0.0, 1.0);
+        }
+    }
+
+    /// Eases the lead of the view over the car towards what `speed` and
+    /// the look-ahead call for, covering the `smoothing` share of the gap
+    /// so it grows and shrinks gently as the car speeds up and brakes.
+    /// Called once per tick, before [`Camera::follow`].
+    pub fn lead_for(&mut self, speed: f64, smoothing: f64) {
+        self.lead += (speed * self.look_ahead - self.lead) * smoothing.clamp(0.0, 1.0);
+    }
+
+    /// Distance, in m, the view leads the followed car by.
+    pub fn lead(&self) -> f64 {
+        self.lead
+    }
+
+    /// Stretch of road the screen shows at the current zoom.
+    pub fn visible_world_size(&self) -> i32 {
+        ((self.world_size as f64 / self.zoom).round() as i32).max(1)
+    }
+
+    /// Eases the camera towards `target`, covering the `smoothing` share of
+    /// the gap, from 0 (still) to 1 (snapping), and always at least a few
+    /// centimetres so it does settle on the target. With a dead zone it only
+    /// moves to bring the target back to the edge of the window. Called
+    /// once per tick.
+    pub fn follow(&mut self, target: f64, smoothing: f64) {
+        let target = target + self.lead;
+        let offset = target - self.world_position;
+        let target = target - offset.clamp(-self.dead_zone, self.dead_zone);
+        let gap = target - self.world_position;
+        let step = (gap * smoothing.clamp(0.0, 1.0)).abs().max(SETTLE_STEP);
+        self.world_position += step.min(gap.abs()).copysign(gap);
+        self.clamp_to_bounds();
+    }
+
+    /// Keeps the view between `start` and `end`, say from 0 to the end of
+    /// the track, while still following as far as it can. A view wider
+    /// than the bounds is centred on them.
+    pub fn set_bounds(&mut self, start: f64, end: f64) {
+        self.bounds = Some((start, end));
+        self.clamp_to_bounds();
+    }
+
+    pub fn clear_bounds(&mut self) {
+        self.bounds = None;
+    }
+
+    /// Zooms smoothly to `zoom` over `duration` seconds, say pulling out at
+    /// high speed and in near the goal zone. 0 zooms at once. A zoom that
+    /// isn't a positive number is ignored.
+    pub fn zoom_to(&mut self, zoom: f64, duration: f64) {
+        if !zoom.is_finite() || zoom <= 0.0 {
+            return;
+        }
+        self.zoom_target = zoom;
+        if duration > 0.0 {
+            self.zoom_rate = (zoom - self.zoom).abs() / duration;
+        } else {
+            self.zoom = zoom;
+            self.zoom_rate = 0.0;
+        }
+    }
+
+    /// Moves the camera's animations on by `dt` seconds. Called once per
+    /// tick.
+    pub fn step(&mut self, dt: f64) {
+        if !dt.is_finite() || dt <= 0.0 {
+            return;
+        }
+        let gap = self.zoom_target - self.zoom;
+        self.zoom += gap.abs().min(self.zoom_rate * dt).copysign(gap);
+        self.clamp_to_bounds();
+        self.shake *= 0.5_f64.powf(dt / SHAKE_HALF_LIFE);
+        self.shake_time += dt;
+    }
+
+    /// Shakes the view by `amplitude` pixels, unless it already shakes
+    /// harder.
+    pub fn start_shake(&mut self, amplitude: f64) {
+        if amplitude > self.shake {
+            self.shake = amplitude;
+            self.shake_time = 0.0;
+        }
+    }
+
+    /// Pixels the shake moves the view by along the road this tick.
+    pub fn shake_offset(&self) -> f64 {
+        self.shake * (std::f64::consts::TAU * SHAKE_FREQUENCY * self.shake_time).cos()
+    }
+}
+
+impl Camera {
+    /// Pixels the viewport and the shake move the view by along the road.
+    fn horizontal_offset(&self) -> f64 {
+        self.screen_x as f64 + self.shake_offset()
+    }
+
+    fn clamp_to_bounds(&mut self) {
+        if let Some((start, end)) = self.bounds {
+            let half_view = self.world_size as f64 / self.zoom / 2.0;
+            let lowest = start + half_view;
+            let highest = end - half_view;
+            self.world_position = if lowest <= highest {
+                self.world_position.clamp(lowest, highest)
+            } else {
+                (start + end) / 2.0
+            };
+        }
+    }
+
+    /// Shakes the view for the crashes and harsh landings among `events`.
+    pub fn shake_for(&mut self, events: &[Event]) {
+        for event in events {
+            match event {
+                Event::Collision => self.start_shake(COLLISION_SHAKE),
+                Event::HarshLanding => self.start_shake(LANDING_SHAKE),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// A camera on a 1000 m stretch of road and a 1000 px screen.
+impl Default for Camera {
+    fn default() -> Camera {
+        Camera::new(1000, 1000)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    #[test]
+    fn camera_should_project_900_when_car_world_position_is_100_and_camera_size_is_1000_and_world_and_screen_size_are_the_same(){
+        let camera = Camera {
+            screen_size: 1000,
+            world_size: 1000,
+            world_position: 500.0,
+            ..Camera::default()
+        };
+        let screen_position = camera.project(100);
+        assert_eq!(900, screen_position);
+    }
+
+    #[test]
+    fn camera_should_project_900_when_world_position_is_1000_and_camera_world_size_is_10x_bigger_than_screen_size(){
+        let camera= Camera {
+            screen_size: 1000,
+            world_size: 10000,
+            world_position: 5000.0,
+            ..Camera::default()
+        };
+        let screen_position = camera.project(1000);
+        assert_eq!(900, screen_position);
+    }
+
+    #[test]
+    fn camera_should_project_900_when_camera_is_at_5000_in_world_position(){
+        let camera = Camera {
+            screen_size: 1000,
+            world_size: 10000,
+            world_position: 5000.0,
+            ..Camera::default()
+        };
+        let screen_position = camera.project(1000);
+        assert_eq!(900, screen_position);
+    }
+
+    #[test]
+    fn camera_should_ease_towards_the_target(){
+        let mut camera = Camera::new(1000, 1000);
+        camera.follow(100.0, 0.25);
+        assert_eq!(25.0, camera.world_position);
+        camera.follow(100.0, 0.25);
+        assert_eq!(43.75, camera.world_position);
+        camera.follow(-56.0, 1.0);
+        assert_eq!(-56.0, camera.world_position);
+    }
+
+    #[test]
+    fn camera_should_settle_on_the_target(){
+        let mut camera = Camera::new(1000, 1000);
+        for _ in 0..200 {
+            camera.follow(10.0, 0.01);
+        }
+        assert_eq!(10.0, camera.world_position);
+    }
+
+    #[test]
+    fn camera_should_stay_still_while_the_car_is_in_the_dead_zone(){
+        let mut camera = Camera { dead_zone: 50.0, ..Camera::default() };
+        camera.follow(40.0, 1.0);
+        assert_eq!(0.0, camera.world_position);
+
+        camera.follow(80.0, 1.0);
+        assert_eq!(30.0, camera.world_position);
+        camera.follow(-30.0, 1.0);
+        assert_eq!(20.0, camera.world_position);
+    }
+
+    #[test]
+    fn camera_should_look_further_ahead_the_faster_the_car(){
+        let mut camera = Camera { look_ahead: 2.0, ..Camera::default() };
+        camera.lead_for(40.0, 0.5);
+        camera.follow(100.0, 1.0);
+        assert_eq!(40.0, camera.lead());
+        assert_eq!(140.0, camera.world_position);
+
+        camera.lead_for(0.0, 0.5);
+        camera.lead_for(0.0, 0.5);
+        assert_eq!(10.0, camera.lead());
+    }
+
+    #[test]
+    fn camera_should_project_500_when_camera_is_at_same_world_position_than_target(){
+        let camera = Camera {
+            screen_size: 1000,
+            world_size: 10000,
+            world_position: 1000.0,
+            ..Camera::default()
+        };
+        let screen_position = camera.project(1000);
+        assert_eq!(500, screen_position);
+    }
+
+    #[test]
+    fn zooming_in_should_spread_the_road_over_the_screen(){
+        let camera = Camera { zoom: 2.0, world_position: 1000.0, ..Camera::new(1000, 10000) };
+
+        assert_eq!(400, camera.project(1500));
+    }
+
+    #[test]
+    fn precise_projection_should_keep_fractional_pixels(){
+        let camera = Camera { world_position: 5000.0, ..Camera::new(1000, 10000) };
+
+        assert_eq!(900.0, camera.project_precise(1000.0));
+        assert_eq!(899.95, camera.project_precise(1000.5));
+        assert_eq!(900, camera.project(1001));
+    }
+
+    #[test]
+    fn unproject_should_undo_the_projection(){
+        let camera = Camera { world_position: 5000.0, zoom: 2.0, shake: 3.0, ..Camera::new(800, 10000) };
+
+        for position in [2500.0, 5000.0, 6123.5] {
+            assert!((position - camera.unproject(camera.project_precise(position))).abs() < 1e-9);
+        }
+        assert_eq!(5000.0, Camera { world_position: 5000.0, ..Camera::new(800, 10000) }.unproject(400.0));
+    }
+
+    #[test]
+    fn bounded_camera_should_not_show_past_its_bounds(){
+        let mut camera = Camera::new(1000, 1000);
+        camera.set_bounds(0.0, 5000.0);
+        assert_eq!(500.0, camera.world_position);
+
+        camera.follow(3000.0, 1.0);
+        assert_eq!(3000.0, camera.world_position);
+        camera.follow(4900.0, 1.0);
+        assert_eq!(4500.0, camera.world_position);
+
+        camera.clear_bounds();
+        camera.follow(4900.0, 1.0);
+        assert_eq!(4900.0, camera.world_position);
+    }
+
+    #[test]
+    fn bounds_narrower_than_the_view_should_centre_it(){
+        let mut camera = Camera::new(1000, 1000);
+        camera.set_bounds(0.0, 600.0);
+        camera.follow(0.0, 1.0);
+
+        assert_eq!(300.0, camera.world_position);
+    }
+
+    #[test]
+    fn bounds_less_than_a_metre_wider_than_the_view_should_still_clamp_it(){
+        let mut camera = Camera { zoom: 20.0 / 20.6, ..Camera::new(100, 20) };
+        camera.set_bounds(0.0, 20.9);
+
+        assert!((camera.world_position - 10.3).abs() < 1e-9);
+    }
+
+    #[test]
+    fn viewport_should_shift_the_projection(){
+        let camera = Camera { screen_x: 100, screen_y: 50, ..Camera::default() };
+
+        assert_eq!(600, camera.project(0));
+        assert_eq!(600.0, camera.project_precise(0.0));
+        assert_eq!(550.0, camera.project_height(0.0));
+        assert_eq!(0.0, camera.unproject(600.0));
+    }
+
+    #[test]
+    fn ranges_should_be_cut_to_the_viewport(){
+        let camera = Camera { screen_x: 100, ..Camera::default() };
+
+        assert_eq!(Some(ScreenSpan { left: 500.0, right: 600.0 }), camera.project_range(0.0, 100.0));
+        assert_eq!(Some(ScreenSpan { left: 100.0, right: 300.0 }), camera.project_range(300.0, 800.0));
+        assert_eq!(Some(ScreenSpan { left: 1000.0, right: 1100.0 }), camera.project_range(-900.0, -400.0));
+        assert_eq!(None, camera.project_range(600.0, 800.0));
+    }
+
+    #[test]
+    fn far_layers_should_scroll_slower_than_the_road(){
+        let camera = Camera { world_position: 100.0, ..Camera::default() };
+
+        assert_eq!(camera.project_precise(50.0), camera.project_layer(50.0, 1.0));
+        assert_eq!(530.0, camera.project_layer(-10.0, 0.2));
+        assert_eq!(510.0, camera.project_layer(-10.0, 0.0));
+    }
+
+    #[test]
+    fn heights_should_be_drawn_upwards_on_the_road_scale(){
+        let camera = Camera { vertical_offset: 100.0, ..Camera::new(1000, 500) };
+
+        assert_eq!(600.0, camera.project_height(0.0));
+        assert_eq!(580.0, camera.project_height(10.0));
+        assert_eq!(ScreenPoint { x: 480.0, y: 580.0 }, camera.project_point(10.0, 10.0));
+    }
+
+    #[test]
+    fn camera_should_only_follow_heights_when_its_policy_says_so(){
+        let mut camera = Camera::default();
+        camera.follow_height(10.0, 0.5);
+        assert_eq!(0.0, camera.height);
+
+        camera.vertical_policy = VerticalPolicy::Follow;
+        camera.follow_height(10.0, 0.5);
+        assert_eq!(5.0, camera.height);
+    }
+
+    #[test]
+    fn collisions_should_shake_the_view_until_it_dies_down(){
+        let mut camera = Camera::default();
+        camera.shake_for(&[Event::HarshLanding, Event::Collision]);
+
+        assert_eq!(COLLISION_SHAKE, camera.shake);
+        assert_eq!(500 + COLLISION_SHAKE as i32, camera.project(0));
+        camera.step(SHAKE_HALF_LIFE);
+        assert!((camera.shake - COLLISION_SHAKE / 2.0).abs() < 1e-9);
+        for _ in 0..100 {
+            camera.step(0.1);
+        }
+        assert_eq!(500, camera.project(0));
+    }
+
+    #[test]
+    fn weaker_shakes_should_not_cut_a_stronger_one_short(){
+        let mut camera = Camera::default();
+        camera.start_shake(COLLISION_SHAKE);
+        camera.start_shake(LANDING_SHAKE);
+
+        assert_eq!(COLLISION_SHAKE, camera.shake);
+    }
+
+    #[test]
+    fn zoom_to_should_reach_the_zoom_over_the_duration(){
+        let mut camera = Camera::default();
+        camera.zoom_to(2.0, 1.0);
+        camera.step(0.5);
+        assert_eq!(1.5, camera.zoom);
+        camera.step(0.75);
+        assert_eq!(2.0, camera.zoom);
+    }
+
+    #[test]
+    fn bad_zooms_and_ticks_should_leave_the_zoom_alone(){
+        let mut camera = Camera::default();
+        camera.zoom_to(f64::NAN, 1.0);
+        camera.zoom_to(-2.0, 1.0);
+        camera.step(0.5);
+        assert_eq!(1.0, camera.zoom);
+
+        camera.zoom_to(2.0, 1.0);
+        camera.step(-0.5);
+        assert_eq!(1.0, camera.zoom);
+    }
+
+    #[test]
+    fn zoom_to_without_duration_should_zoom_at_once(){
+        let mut camera = Camera::default();
+        camera.zoom_to(0.5, 0.0);
+
+        assert_eq!(0.5, camera.zoom);
+    }
+}
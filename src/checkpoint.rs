@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+/// A line across the road the car has to cross before stopping in the goal
+/// zone, on long tracks.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Checkpoint {
+    pub position: f64,
+    /// Run time, in seconds, the checkpoint must be crossed by. `f64::MAX`
+    /// when there is no split time.
+    pub split_time: f64
+}
+
+#[wasm_bindgen]
+impl Checkpoint {
+    #[wasm_bindgen(constructor)]
+    pub fn new(position: f64, split_time: f64) -> Checkpoint {
+        Checkpoint { position, split_time }
+    }
+}
+
+impl Checkpoint {
+    /// Whether a car moving from `from` to `to` crosses the line forwards.
+    pub fn crossed_between(&self, from: f64, to: f64) -> bool {
+        from < self.position && to >= self.position
+    }
+}
+
+/// Checkpoints of `checkpoints`, in road order, passed once a car that had
+/// passed `passed` of them moves from `from` to `to`.
+pub fn passed_checkpoints(checkpoints: &[Checkpoint], passed: u32, from: f64, to: f64) -> u32 {
+    let crossed = checkpoints.iter()
+        .skip(passed as usize)
+        .take_while(|checkpoint| checkpoint.crossed_between(from, to))
+        .count();
+    passed + crossed as u32
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn checkpoint_should_only_be_crossed_forwards(){
+        let checkpoint = Checkpoint::new(10.0, f64::MAX);
+
+        assert!(checkpoint.crossed_between(9.0, 10.0));
+        assert!(!checkpoint.crossed_between(10.0, 11.0));
+        assert!(!checkpoint.crossed_between(11.0, 9.0));
+    }
+
+    #[test]
+    fn passed_checkpoints_should_count_every_checkpoint_crossed_in_order(){
+        let checkpoints = [Checkpoint::new(10.0, f64::MAX), Checkpoint::new(20.0, f64::MAX), Checkpoint::new(30.0, f64::MAX)];
+
+        assert_eq!(0, passed_checkpoints(&checkpoints, 0, 0.0, 5.0));
+        assert_eq!(2, passed_checkpoints(&checkpoints, 0, 5.0, 25.0));
+        assert_eq!(3, passed_checkpoints(&checkpoints, 2, 25.0, 35.0));
+    }
+}
@@ -0,0 +1,143 @@
+use wasm_bindgen::prelude::*;
+
+use crate::{Input, State};
+
+/// Textbook PID controller turning an error into a correction.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Pid {
+    pub kp: f64,
+    pub ki: f64,
+    pub kd: f64,
+    integral: f64,
+    previous_error: Option<f64>
+}
+
+#[wasm_bindgen]
+impl Pid {
+    #[wasm_bindgen(constructor)]
+    pub fn new(kp: f64, ki: f64, kd: f64) -> Pid {
+        Pid {
+            kp,
+            ki,
+            kd,
+            ..Pid::default()
+        }
+    }
+
+    /// Correction for `error` measured `dt` seconds after the previous one.
+    /// The derivative term is 0 on the first update.
+    pub fn update(&mut self, error: f64, dt: f64) -> f64 {
+        self.integral += error * dt;
+        let derivative = self.previous_error.map_or(0.0, |previous| (error - previous) / dt);
+        self.previous_error = Some(error);
+        self.kp * error + self.ki * self.integral + self.kd * derivative
+    }
+
+    /// Forgets the accumulated integral and previous error.
+    pub fn reset(&mut self) {
+        self.integral = 0.0;
+        self.previous_error = None;
+    }
+}
+
+/// Assist that brings the car to a stop at `target`.
+///
+/// It follows the speed profile of a stop at a constant `braking`
+/// deceleration, with a [`Pid`] closing the gap between that profile and
+/// the actual speed.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StopAssist {
+    pub target: f64,
+    /// Deceleration the planned stop uses, as a positive value. Keep it
+    /// below what the brakes can do so the assist has margin to correct.
+    pub braking: f64,
+    pub pid: Pid
+}
+
+#[wasm_bindgen]
+impl StopAssist {
+    #[wasm_bindgen(constructor)]
+    pub fn new(target: f64, braking: f64) -> StopAssist {
+        StopAssist {
+            target,
+            braking,
+            pid: Pid::new(2.0, 0.0, 0.0)
+        }
+    }
+
+    /// Speed the car should have at `position` to stop at the target.
+    pub fn target_speed(&self, position: f64) -> f64 {
+        (2.0 * self.braking * (self.target - position).max(0.0)).sqrt()
+    }
+
+    /// Inputs for the next `dt` seconds in `state`. Past the target it
+    /// brakes fully, as easing off would only creep towards a standstill.
+    pub fn input(&mut self, state: &State, dt: f64) -> Input {
+        if state.position >= self.target {
+            return Input::new(0.0, 1.0);
+        }
+        let vehicle = &state.vehicle;
+        let demand = self.pid.update(self.target_speed(state.position) - state.speed, dt);
+        if demand >= 0.0 {
+            return Input::new(vehicle.throttle_for(demand, state.rpm, state.gear), 0.0);
+        }
+        let friction = state.friction();
+        let (full_braking, _) = vehicle.braking(1.0, friction, state.speed, state.brake_temperature);
+        if full_braking <= 0.0 {
+            return Input::new(0.0, 1.0);
+        }
+        // Brake deceleration grows with the square of the pedal.
+        Input::new(0.0, (-demand / full_braking).sqrt().min(1.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::update;
+
+    #[test]
+    fn pid_should_sum_proportional_integral_and_derivative_terms(){
+        let mut pid = Pid::new(2.0, 0.5, 1.0);
+
+        assert_eq!(2.0 * 4.0 + 0.5 * 4.0, pid.update(4.0, 1.0));
+        assert_eq!(2.0 * 2.0 + 0.5 * 6.0 - 2.0, pid.update(2.0, 1.0));
+    }
+
+    #[test]
+    fn reset_pid_should_forget_integral_and_derivative(){
+        let mut pid = Pid::new(0.0, 1.0, 1.0);
+        pid.update(4.0, 1.0);
+        pid.reset();
+
+        assert_eq!(2.0, pid.update(2.0, 1.0));
+    }
+
+    #[test]
+    fn stop_assist_target_speed_should_follow_a_constant_deceleration_stop(){
+        let assist = StopAssist::new(100.0, 0.5);
+
+        assert_eq!(10.0, assist.target_speed(0.0));
+        assert_eq!(0.0, assist.target_speed(100.0));
+        assert_eq!(0.0, assist.target_speed(120.0));
+    }
+
+    #[test]
+    fn stop_assist_should_stop_the_car_near_its_target(){
+        let mut state = State::new();
+        let mut assist = StopAssist::new(9500.0, 0.5);
+
+        for _ in 0..100_000 {
+            let input = assist.input(&state, 0.1);
+            state = update(state, input, 0.1);
+            if state.won || state.lost {
+                break;
+            }
+        }
+        assert!(state.won);
+        assert!((state.position - 9500.0).abs() < 5.0);
+    }
+}
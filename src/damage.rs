@@ -0,0 +1,32 @@
+/// Damage, as a share of a wreck, from knocking over a cone.
+pub const CONE_DAMAGE: f64 = 0.05;
+
+/// Damage for each m/s of speed the suspension bottoms out with.
+pub const LANDING_DAMAGE: f64 = 0.05;
+
+/// Share of the brakes and drive lost on a car one hit away from a wreck.
+pub const DAMAGED_PERFORMANCE_LOSS: f64 = 0.5;
+
+/// Share of the brakes and drive a car with `damage`, from 0 (intact) to 1
+/// (wrecked), still has.
+pub fn damaged_performance(damage: f64) -> f64 {
+    1.0 - DAMAGED_PERFORMANCE_LOSS * damage.clamp(0.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn intact_car_should_keep_full_performance(){
+        assert_eq!(1.0, damaged_performance(0.0));
+    }
+
+    #[test]
+    fn damage_should_degrade_performance_linearly(){
+        assert_eq!(0.75, damaged_performance(0.5));
+        assert_eq!(0.5, damaged_performance(1.0));
+        assert_eq!(0.5, damaged_performance(3.0));
+    }
+}
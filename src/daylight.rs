@@ -0,0 +1,51 @@
+/// How far, in metres, the driver sees in full daylight.
+pub const DAY_VISIBILITY: f64 = 1000.0;
+/// How far the driver sees at midnight without headlights.
+pub const NIGHT_VISIBILITY: f64 = 30.0;
+/// How far headlights light the road ahead.
+pub const HEADLIGHT_VISIBILITY: f64 = 150.0;
+/// In-game hours going by per simulated second unless a level says
+/// otherwise: about an hour over a long run.
+pub const DEFAULT_TIME_SCALE: f64 = 0.01;
+
+/// Share of full daylight at `time_of_day`, in hours: 0 at midnight, 1 at
+/// noon, half-light at 6 and 18.
+pub fn daylight(time_of_day: f64) -> f64 {
+    0.5 - 0.5 * (time_of_day / 24.0 * std::f64::consts::TAU).cos()
+}
+
+/// How far the driver sees at `time_of_day`. Headlights only help once it
+/// is darker than they light.
+pub fn visibility(time_of_day: f64, headlights: bool) -> f64 {
+    let natural = NIGHT_VISIBILITY + (DAY_VISIBILITY - NIGHT_VISIBILITY) * daylight(time_of_day);
+    if headlights {
+        natural.max(HEADLIGHT_VISIBILITY)
+    } else {
+        natural
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn daylight_should_peak_at_noon_and_vanish_at_midnight(){
+        assert_eq!(0.0, daylight(0.0));
+        assert_eq!(1.0, daylight(12.0));
+        assert!((daylight(6.0) - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn visibility_should_be_shorter_at_night(){
+        assert_eq!(DAY_VISIBILITY, visibility(12.0, false));
+        assert_eq!(NIGHT_VISIBILITY, visibility(0.0, false));
+    }
+
+    #[test]
+    fn headlights_should_only_help_in_the_dark(){
+        assert_eq!(HEADLIGHT_VISIBILITY, visibility(0.0, true));
+        assert_eq!(DAY_VISIBILITY, visibility(12.0, true));
+    }
+}
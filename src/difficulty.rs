@@ -0,0 +1,105 @@
+use wasm_bindgen::prelude::*;
+
+use crate::Level;
+
+/// Difficulty preset a level is played at, so the front end doesn't have to
+/// tune physics numbers itself.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Difficulty {
+    /// Wider goal zone, stronger brakes and ABS and traction control on.
+    Easy,
+    /// The level as designed.
+    #[default]
+    Normal,
+    /// Narrower goal zone, weaker brakes and no ABS or traction control.
+    Hard
+}
+
+impl Difficulty {
+    /// Factor the width of the goal zone is scaled by, around its centre.
+    pub fn goal_width_factor(self) -> f64 {
+        match self {
+            Difficulty::Easy => 1.5,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 0.5
+        }
+    }
+
+    /// Factor the brake force is scaled by.
+    pub fn braking_factor(self) -> f64 {
+        match self {
+            Difficulty::Easy => 1.25,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 0.8
+        }
+    }
+
+    /// Whether ABS and traction control are forced on or off, or left as
+    /// the level has them.
+    pub fn assists(self) -> Option<bool> {
+        match self {
+            Difficulty::Easy => Some(true),
+            Difficulty::Normal => None,
+            Difficulty::Hard => Some(false)
+        }
+    }
+
+    /// `level` as played at this difficulty.
+    pub fn apply(self, level: &Level) -> Level {
+        let centre = (level.goal_start + level.goal_end) / 2.0;
+        let half_width = (level.goal_end - level.goal_start) / 2.0 * self.goal_width_factor();
+        let mut level = level.clone();
+        level.goal_start = centre - half_width;
+        level.goal_end = centre + half_width;
+        level.vehicle.brake_force *= self.braking_factor();
+        if let Some(assists) = self.assists() {
+            level.vehicle.abs = assists;
+            level.vehicle.traction_control = assists;
+        }
+        level
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn normal_should_leave_the_level_as_designed(){
+        let level = Level::get(2).unwrap();
+
+        assert_eq!(level, Difficulty::Normal.apply(&level));
+    }
+
+    #[test]
+    fn easy_should_widen_the_goal_and_strengthen_the_brakes(){
+        let level = Level::default();
+        let easy = Difficulty::Easy.apply(&level);
+
+        assert_eq!(8750.0, easy.goal_start);
+        assert_eq!(10250.0, easy.goal_end);
+        assert_eq!(level.vehicle.brake_force * 1.25, easy.vehicle.brake_force);
+        assert!(easy.vehicle.abs && easy.vehicle.traction_control);
+    }
+
+    #[test]
+    fn hard_should_narrow_the_goal_and_take_the_assists_away(){
+        let level = Level { vehicle: crate::Vehicle { abs: true, ..Default::default() }, ..Level::default() };
+        let hard = Difficulty::Hard.apply(&level);
+
+        assert_eq!(9250.0, hard.goal_start);
+        assert_eq!(9750.0, hard.goal_end);
+        assert!(!hard.vehicle.abs);
+    }
+
+    #[test]
+    fn every_level_should_stay_valid_at_every_difficulty(){
+        for index in 0..Level::count() {
+            for difficulty in [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard] {
+                assert_eq!(Ok(()), difficulty.apply(&Level::get(index).unwrap()).validate(), "level {}", index);
+            }
+        }
+    }
+}
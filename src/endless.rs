@@ -0,0 +1,85 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::rng::Rng;
+
+/// Endless mode: stopping in a goal zone spawns the next one further down
+/// the road instead of winning, and the run goes on until it is lost.
+///
+/// Gaps between zones are seeded, so a seed always lays out the same
+/// succession of zones.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Endless {
+    pub enabled: bool,
+    /// Shortest road, in m, between the end of a goal zone and the start of
+    /// the next one.
+    pub min_gap: f64,
+    /// Longest road, in m, between the end of a goal zone and the start of
+    /// the next one.
+    pub max_gap: f64,
+    pub seed: u64,
+    /// Goal zones stopped in so far.
+    pub streak: u32
+}
+
+#[wasm_bindgen]
+impl Endless {
+    #[wasm_bindgen(constructor)]
+    pub fn new(min_gap: f64, max_gap: f64, seed: u64) -> Endless {
+        Endless {
+            enabled: true,
+            min_gap,
+            max_gap,
+            seed,
+            streak: 0
+        }
+    }
+}
+
+impl Endless {
+    /// Bounds of the zone following the one from `goal_start` to
+    /// `goal_end`, as wide as it, once `streak` zones were stopped in.
+    pub fn next_goal(&self, goal_start: f64, goal_end: f64) -> (f64, f64) {
+        let mut rng = Rng::keyed(self.seed, self.streak as u64);
+        let gap = self.min_gap + (self.max_gap - self.min_gap) * rng.next_f64();
+        let start = goal_end + gap;
+        (start, start + goal_end - goal_start)
+    }
+}
+
+impl Default for Endless {
+    fn default() -> Endless {
+        Endless {
+            enabled: false,
+            min_gap: 1000.0,
+            max_gap: 3000.0,
+            seed: 0,
+            streak: 0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn next_goal_should_keep_the_zone_width_and_a_gap_in_range(){
+        let endless = Endless::new(100.0, 200.0, 3);
+
+        let (start, end) = endless.next_goal(1000.0, 1050.0);
+        assert!((1150.0..1250.0).contains(&start));
+        assert_eq!(50.0, end - start);
+    }
+
+    #[test]
+    fn same_seed_and_streak_should_give_the_same_next_goal(){
+        let endless = Endless::new(100.0, 200.0, 3);
+
+        assert_eq!(Endless::new(100.0, 200.0, 3).next_goal(0.0, 10.0), endless.next_goal(0.0, 10.0));
+        assert_ne!(endless.next_goal(0.0, 10.0), Endless { streak: 1, ..endless }.next_goal(0.0, 10.0));
+    }
+}
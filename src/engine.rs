@@ -0,0 +1,192 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+/// What turns throttle into acceleration.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Powertrain {
+    /// Arcade model: the throttle value is the driving force.
+    #[default]
+    Direct,
+    /// Combustion engine following the [`Engine`] torque curve.
+    Combustion,
+    /// Electric motor running on the battery, with regenerative braking.
+    Electric,
+    /// Combustion engine and electric motor: the motor alone drives at low
+    /// speed and light throttle, the engine takes over above.
+    Hybrid
+}
+
+/// Combustion engine with an RPM-dependent torque curve.
+///
+/// The curve is piecewise linear: it rises from `idle_torque` at idle to
+/// full torque at `peak_torque_rpm`, falls to `redline_torque` at the
+/// redline and the rev limiter cuts it entirely above. Torque values are
+/// fractions of the peak.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Engine {
+    pub idle_rpm: f64,
+    /// Below this RPM with a gear engaged the engine stalls.
+    pub stall_rpm: f64,
+    pub peak_torque_rpm: f64,
+    pub redline_rpm: f64,
+    pub idle_torque: f64,
+    pub redline_torque: f64,
+    /// Driving force at the wheels at full throttle and peak torque, before
+    /// gearing.
+    pub peak_force: f64,
+    /// Force holding the wheels back at closed throttle at the redline,
+    /// before gearing. 0 disables engine braking.
+    pub braking_force: f64,
+    /// Engine revolutions per minute for each unit of road speed.
+    pub rpm_per_speed: f64
+}
+
+#[wasm_bindgen]
+impl Engine {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Engine {
+        Engine::default()
+    }
+
+    /// Engine speed when the car moves at `speed`. The engine never drops
+    /// below idle.
+    pub fn rpm(&self, speed: f64) -> f64 {
+        (speed * self.rpm_per_speed).max(self.idle_rpm)
+    }
+
+    /// Fraction of peak torque available at `rpm`.
+    pub fn torque(&self, rpm: f64) -> f64 {
+        if rpm > self.redline_rpm {
+            0.0
+        } else if rpm >= self.peak_torque_rpm {
+            lerp(1.0, self.redline_torque, (rpm - self.peak_torque_rpm) / (self.redline_rpm - self.peak_torque_rpm))
+        } else if rpm >= self.idle_rpm {
+            lerp(self.idle_torque, 1.0, (rpm - self.idle_rpm) / (self.peak_torque_rpm - self.idle_rpm))
+        } else {
+            self.idle_torque
+        }
+    }
+
+    /// Driving force produced at `rpm` for a throttle between 0 and 1.
+    pub fn force(&self, throttle: f64, rpm: f64) -> f64 {
+        throttle.clamp(0.0, 1.0) * self.peak_force * self.torque(rpm)
+    }
+
+    /// Engine speed with no load, revving up from idle towards peak torque
+    /// with `throttle`.
+    pub fn free_rpm(&self, throttle: f64) -> f64 {
+        self.idle_rpm + throttle.clamp(0.0, 1.0) * (self.peak_torque_rpm - self.idle_rpm)
+    }
+
+    /// Force the engine holds the wheels back with at closed throttle,
+    /// growing with `rpm`.
+    pub fn braking(&self, rpm: f64) -> f64 {
+        self.braking_force * rpm / self.redline_rpm
+    }
+}
+
+impl Default for Engine {
+    fn default() -> Engine {
+        Engine {
+            idle_rpm: 800.0,
+            stall_rpm: 500.0,
+            peak_torque_rpm: 4000.0,
+            redline_rpm: 6500.0,
+            idle_torque: 0.6,
+            redline_torque: 0.7,
+            peak_force: 4.0,
+            braking_force: 0.0,
+            rpm_per_speed: 130.0
+        }
+    }
+}
+
+fn lerp(from: f64, to: f64, ratio: f64) -> f64 {
+    from + (to - from) * ratio
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn rpm_should_follow_speed(){
+        let engine = Engine { rpm_per_speed: 100.0, ..Default::default() };
+
+        assert_eq!(2000.0, engine.rpm(20.0));
+    }
+
+    #[test]
+    fn rpm_should_not_drop_below_idle(){
+        let engine = Engine { idle_rpm: 800.0, ..Default::default() };
+
+        assert_eq!(800.0, engine.rpm(0.0));
+    }
+
+    #[test]
+    fn torque_should_peak_at_peak_torque_rpm(){
+        let engine = Engine::default();
+
+        assert_eq!(1.0, engine.torque(engine.peak_torque_rpm));
+    }
+
+    #[test]
+    fn torque_should_rise_linearly_from_idle_to_peak(){
+        let engine = Engine {
+            idle_rpm: 1000.0,
+            peak_torque_rpm: 3000.0,
+            idle_torque: 0.5,
+            ..Default::default()
+        };
+
+        assert_eq!(0.5, engine.torque(1000.0));
+        assert_eq!(0.75, engine.torque(2000.0));
+    }
+
+    #[test]
+    fn torque_should_fall_towards_redline_and_be_cut_above(){
+        let engine = Engine {
+            peak_torque_rpm: 4000.0,
+            redline_rpm: 6000.0,
+            redline_torque: 0.5,
+            ..Default::default()
+        };
+
+        assert_eq!(0.75, engine.torque(5000.0));
+        assert_eq!(0.5, engine.torque(6000.0));
+        assert_eq!(0.0, engine.torque(6001.0));
+    }
+
+    #[test]
+    fn force_should_scale_with_throttle_and_torque(){
+        let engine = Engine {
+            peak_force: 4.0,
+            idle_torque: 0.5,
+            ..Default::default()
+        };
+
+        assert_eq!(4.0, engine.force(1.0, engine.peak_torque_rpm));
+        assert_eq!(1.0, engine.force(0.5, engine.idle_rpm));
+    }
+
+    #[test]
+    fn engine_braking_should_grow_with_rpm(){
+        let engine = Engine { braking_force: 2.0, redline_rpm: 6000.0, ..Default::default() };
+
+        assert_eq!(0.5, engine.braking(1500.0));
+        assert_eq!(2.0, engine.braking(6000.0));
+    }
+
+    #[test]
+    fn free_engine_should_rev_from_idle_to_peak_torque_with_throttle(){
+        let engine = Engine { idle_rpm: 1000.0, peak_torque_rpm: 4000.0, ..Default::default() };
+
+        assert_eq!(1000.0, engine.free_rpm(0.0));
+        assert_eq!(2500.0, engine.free_rpm(0.5));
+        assert_eq!(4000.0, engine.free_rpm(2.0));
+    }
+}
@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+/// Something that happened during a tick, for the front end to react to
+/// with a sound or a message instead of diffing the state.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Event {
+    /// The car reached the speed limiter and it started cutting throttle.
+    LimiterHit,
+    /// The car ran into an obstacle or another car.
+    Collision,
+    /// The suspension bottomed out landing from a bump.
+    HarshLanding,
+    /// The car stopped in a goal zone in endless mode and the next zone
+    /// spawned.
+    GoalCleared,
+    /// The car crossed a checkpoint.
+    CheckpointPassed,
+    /// The car, or its trailer when towing, drove into the goal zone.
+    GoalEntered,
+    /// The car, or its trailer when towing, drove past the end of the goal
+    /// zone.
+    GoalOvershot,
+    /// The run was just won.
+    Won,
+    /// The run was just lost; `lose_reason` tells why.
+    Lost
+}
@@ -0,0 +1,177 @@
+use wasm_bindgen::prelude::*;
+
+use crate::{Camera, Input, Level, LoseReason, State, Weather};
+
+/// Where a run stands.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameStatus {
+    Playing,
+    Won,
+    Lost
+}
+
+/// Everything the front end needs to draw a frame, already projected to
+/// the screen.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RenderInfo {
+    /// Screen position of the car.
+    pub car: i32,
+    /// Screen position of the trailer, the car's when it tows none.
+    pub trailer: i32,
+    pub goal_start: i32,
+    pub goal_end: i32,
+    /// Height of the car body, for drawing it over hills.
+    pub body_height: f64,
+    pub speed: f64,
+    pub rpm: f64,
+    pub gear: i32,
+    pub headlights: bool,
+    pub visibility: f64,
+    pub weather: Weather
+}
+
+/// One object for the JS side to drive a whole run with: it owns the
+/// state and the camera and steps the physics itself, so callers don't
+/// juggle the state [`update`](crate::update) consumes.
+#[wasm_bindgen]
+pub struct Game {
+    state: State,
+    camera: Camera,
+    /// Share of the gap to the car the camera closes each tick, 1 keeping
+    /// the car centred.
+    camera_smoothing: f64,
+    tick_duration: f64
+}
+
+#[wasm_bindgen]
+impl Game {
+    /// Game of `level` stepped `tick_rate` times per second and seen
+    /// through `camera`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(level: &Level, tick_rate: f64, camera: Camera) -> Game {
+        let mut game = Game {
+            state: State::from_level(level),
+            camera,
+            camera_smoothing: 1.0,
+            tick_duration: 1.0 / tick_rate
+        };
+        game.camera.follow(game.state.position, 1.0);
+        game
+    }
+
+    /// Runs one tick with `input` and returns where the run stands.
+    pub fn tick(&mut self, input: Input) -> GameStatus {
+        self.state.step(input, self.tick_duration);
+        self.camera.lead_for(self.state.speed, self.camera_smoothing);
+        self.camera.follow(self.state.position, self.camera_smoothing);
+        self.camera.follow_height(self.state.body_height(), self.camera_smoothing);
+        self.camera.shake_for(&self.state.events);
+        self.camera.step(self.tick_duration);
+        self.status()
+    }
+
+    pub fn render_info(&self) -> RenderInfo {
+        let project = |position: f64| self.camera.project(position.round() as i32);
+        RenderInfo {
+            car: project(self.state.position),
+            trailer: project(self.state.trailer_position()),
+            goal_start: project(self.state.position_goal_start),
+            goal_end: project(self.state.position_goal_end),
+            body_height: self.state.body_height(),
+            speed: self.state.speed,
+            rpm: self.state.rpm,
+            gear: self.state.gear,
+            headlights: self.state.headlights,
+            visibility: self.state.visibility(),
+            weather: self.state.weather()
+        }
+    }
+
+    pub fn status(&self) -> GameStatus {
+        if self.state.won {
+            GameStatus::Won
+        } else if self.state.lost {
+            GameStatus::Lost
+        } else {
+            GameStatus::Playing
+        }
+    }
+
+    pub fn lose_reason(&self) -> LoseReason {
+        self.state.lose_reason
+    }
+
+    /// Copy of the state, for anything [`Game::render_info`] leaves out.
+    pub fn state(&self) -> State {
+        self.state.clone()
+    }
+
+    /// Starts the level over, counting the run that ends in the session.
+    pub fn restart(&mut self) {
+        self.state.restart_level();
+        self.camera.follow(self.state.position, 1.0);
+    }
+
+    /// Zooms the camera to `zoom` over `duration` seconds of ticks.
+    pub fn zoom_to(&mut self, zoom: f64, duration: f64) {
+        self.camera.zoom_to(zoom, duration);
+    }
+
+    /// Lets the camera trail the car, closing `smoothing` of the gap each
+    /// tick, from 0 to 1.
+    pub fn set_camera_smoothing(&mut self, smoothing: f64) {
+        self.camera_smoothing = smoothing;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn game() -> Game {
+        Game::new(&Level::default(), 10.0, Camera::new(800, 400))
+    }
+
+    #[test]
+    fn game_should_keep_the_car_in_the_middle_of_the_screen(){
+        let mut game = game();
+        game.state.speed = 10.0;
+        game.tick(Input::new(0.0, 0.0));
+
+        assert_eq!(501.0, game.state().position);
+        assert_eq!(400, game.render_info().car);
+    }
+
+    #[test]
+    fn smoothed_camera_should_trail_the_car(){
+        let mut game = game();
+        game.set_camera_smoothing(0.5);
+        game.state.speed = 20.0;
+        game.tick(Input::new(0.0, 0.0));
+
+        assert_eq!(501.0, game.camera.world_position);
+    }
+
+    #[test]
+    fn tick_should_report_the_run_status(){
+        let mut game = game();
+        assert_eq!(GameStatus::Playing, game.tick(Input::new(0.0, 0.0)));
+
+        game.state.position = 9500.0;
+        assert_eq!(GameStatus::Won, game.tick(Input::new(0.0, 0.0)));
+    }
+
+    #[test]
+    fn restart_should_put_the_car_back_at_the_start(){
+        let mut game = game();
+        game.tick(Input::new(1.0, 0.0));
+        game.tick(Input::new(1.0, 0.0));
+        game.restart();
+
+        assert_eq!(500.0, game.state().position);
+        assert_eq!(2, game.state().session.attempts);
+    }
+}
@@ -0,0 +1,157 @@
+use wasm_bindgen::prelude::*;
+
+/// Longest wall-clock gap, in seconds, that is fed into the accumulator in
+/// one call. Anything above (a backgrounded tab, a debugger pause) is dropped
+/// instead of being replayed as hundreds of catch-up ticks.
+const MAX_FRAME_TIME: f64 = 0.25;
+
+/// Range tick rates are held within: a rate that isn't positive would never
+/// let the accumulator drain, and a huge one runs countless ticks a frame.
+const MIN_TICK_RATE: f64 = 1.0;
+const MAX_TICK_RATE: f64 = 1000.0;
+
+/// Fixed-timestep clock: fed the wall-clock time every animation frame, it
+/// tells how many ticks of `tick_rate` per second are due, whatever the
+/// frame rate.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GameLoop {
+    tick_rate: f64,
+    /// Simulated seconds per wall-clock second: 0.5 is slow motion.
+    time_scale: f64,
+    /// Simulated time fed in but not yet run as ticks.
+    accumulator: f64,
+    last_time: Option<f64>,
+    /// When set, wall-clock time passes without any tick falling due.
+    paused: bool
+}
+
+#[wasm_bindgen]
+impl GameLoop {
+    /// Clock ticking `tick_rate` times per second, held between 1 and
+    /// 1000; a rate that isn't a number ticks once per second.
+    #[wasm_bindgen(constructor)]
+    pub fn new(tick_rate: f64) -> GameLoop {
+        let tick_rate = if tick_rate.is_nan() { MIN_TICK_RATE } else { tick_rate.clamp(MIN_TICK_RATE, MAX_TICK_RATE) };
+        GameLoop {
+            tick_rate,
+            time_scale: 1.0,
+            accumulator: 0.0,
+            last_time: None,
+            paused: false
+        }
+    }
+
+    pub fn tick_rate(&self) -> f64 {
+        self.tick_rate
+    }
+
+    /// Duration of one tick, in seconds.
+    pub fn tick_duration(&self) -> f64 {
+        1.0 / self.tick_rate
+    }
+
+    pub fn time_scale(&self) -> f64 {
+        self.time_scale
+    }
+
+    pub fn set_time_scale(&mut self, time_scale: f64) {
+        self.time_scale = time_scale.max(0.0);
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Feeds the wall-clock time in milliseconds (as given by
+    /// `performance.now()` or `requestAnimationFrame`) and returns the
+    /// number of ticks to run for it.
+    pub fn advance(&mut self, now_ms: f64) -> u32 {
+        let frame_time = match self.last_time {
+            Some(last_time) => ((now_ms - last_time) / 1000.0).clamp(0.0, MAX_FRAME_TIME),
+            None => 0.0
+        };
+        self.last_time = Some(now_ms);
+        if self.paused {
+            return 0;
+        }
+        self.accumulator += frame_time * self.time_scale;
+
+        let dt = self.tick_duration();
+        let mut ticks = 0;
+        while self.accumulator >= dt {
+            self.accumulator -= dt;
+            ticks += 1;
+        }
+        ticks
+    }
+
+    /// Wall-clock time in milliseconds at which tick `tick`, from 0, of
+    /// the `ticks` the last [`GameLoop::advance`] to `now_ms` made due
+    /// started.
+    pub fn tick_start(&self, now_ms: f64, tick: u32, ticks: u32) -> f64 {
+        let behind = ticks.saturating_sub(tick) as f64 * self.tick_duration() + self.accumulator;
+        now_ms - behind / self.time_scale * 1000.0
+    }
+
+    /// How far, from 0 to 1, the frame is between the last tick run and
+    /// the next, for drawing positions interpolated between the two.
+    pub fn alpha(&self) -> f64 {
+        self.accumulator / self.tick_duration()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn time_scale_should_slow_the_ticks_down(){
+        let mut game_loop = GameLoop::new(10.0);
+        game_loop.set_time_scale(0.5);
+        game_loop.advance(0.0);
+
+        assert_eq!(1, game_loop.advance(200.0));
+    }
+
+    #[test]
+    fn tick_start_should_spread_the_ticks_over_the_frame(){
+        let mut game_loop = GameLoop::new(10.0);
+        game_loop.advance(0.0);
+        let ticks = game_loop.advance(250.0);
+
+        assert_eq!(2, ticks);
+        assert!((game_loop.tick_start(250.0, 0, ticks) - 0.0).abs() < 1e-9);
+        assert!((game_loop.tick_start(250.0, 1, ticks) - 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn insane_tick_rates_should_be_held_in_range(){
+        assert_eq!(MIN_TICK_RATE, GameLoop::new(-10.0).tick_rate());
+        assert_eq!(MIN_TICK_RATE, GameLoop::new(f64::NAN).tick_rate());
+        assert_eq!(MAX_TICK_RATE, GameLoop::new(f64::INFINITY).tick_rate());
+
+        let mut game_loop = GameLoop::new(-10.0);
+        game_loop.advance(0.0);
+        assert_eq!(0, game_loop.advance(100.0));
+        assert_eq!(0.0, game_loop.tick_start(100.0, 3, 1));
+    }
+
+    #[test]
+    fn alpha_should_tell_how_far_the_frame_is_into_the_next_tick(){
+        let mut game_loop = GameLoop::new(10.0);
+        game_loop.advance(0.0);
+        game_loop.advance(125.0);
+
+        assert!((game_loop.alpha() - 0.25).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,176 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+/// Who picks the gear.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Transmission {
+    /// The player shifts with `shift_up` / `shift_down`.
+    #[default]
+    Manual,
+    /// Gears are picked from the engine RPM using the gearbox shift points.
+    Automatic
+}
+
+/// Gear ratio table of the gearbox.
+///
+/// Gears are numbered from 1; gear 0 is neutral and gear -1 reverse. Ratios
+/// multiply both the engine RPM and the acceleration it produces at the
+/// wheels.
+#[wasm_bindgen]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Gearbox {
+    #[wasm_bindgen(skip)]
+    pub ratios: Vec<f64>,
+    pub final_drive: f64,
+    /// Ratio of the reverse gear, as a positive value.
+    pub reverse_ratio: f64,
+    pub transmission: Transmission,
+    /// Automatic transmission shifts up above this RPM.
+    pub upshift_rpm: f64,
+    /// Automatic transmission shifts down below this RPM.
+    pub downshift_rpm: f64
+}
+
+#[wasm_bindgen]
+impl Gearbox {
+    #[wasm_bindgen(constructor)]
+    pub fn new(ratios: Vec<f64>, final_drive: f64) -> Gearbox {
+        Gearbox {
+            reverse_ratio: ratios.first().copied().unwrap_or(0.0),
+            ratios,
+            final_drive,
+            transmission: Transmission::Manual,
+            upshift_rpm: 5500.0,
+            downshift_rpm: 2000.0
+        }
+    }
+
+    /// Typical road car gearbox.
+    pub fn five_speed() -> Gearbox {
+        Gearbox::new(vec![3.0, 2.0, 1.4, 1.0, 0.8], 1.0)
+    }
+
+    /// Same ratios as [`Gearbox::five_speed`], shifted automatically.
+    pub fn five_speed_automatic() -> Gearbox {
+        Gearbox {
+            transmission: Transmission::Automatic,
+            ..Gearbox::five_speed()
+        }
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn ratios(&self) -> Vec<f64> {
+        self.ratios.clone()
+    }
+
+    pub fn gear_count(&self) -> i32 {
+        self.ratios.len() as i32
+    }
+
+    /// Gear an automatic transmission engages when the engine turns at
+    /// `rpm` in `gear`. It moves at most one gear at a time, never leaves
+    /// neutral on its own and leaves manual gearboxes alone.
+    pub fn automatic_gear(&self, gear: i32, rpm: f64) -> i32 {
+        if self.transmission != Transmission::Automatic || gear < 1 {
+            gear
+        } else if rpm > self.upshift_rpm && gear < self.gear_count() {
+            gear + 1
+        } else if rpm < self.downshift_rpm && gear > 1 {
+            gear - 1
+        } else {
+            gear
+        }
+    }
+
+    /// Overall ratio between engine and wheels in `gear`, 0 in neutral or
+    /// for gears the box doesn't have. Reverse has a negative ratio, so the
+    /// engine pushes the car backwards.
+    pub fn ratio(&self, gear: i32) -> f64 {
+        if gear == -1 {
+            return -self.reverse_ratio * self.final_drive;
+        }
+        if gear < 1 {
+            return 0.0;
+        }
+        self.ratios
+            .get(gear as usize - 1)
+            .map_or(0.0, |ratio| ratio * self.final_drive)
+    }
+}
+
+/// A single-speed box with a 1:1 ratio.
+impl Default for Gearbox {
+    fn default() -> Gearbox {
+        Gearbox::new(vec![1.0], 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn neutral_should_have_no_ratio(){
+        let gearbox = Gearbox::five_speed();
+
+        assert_eq!(0.0, gearbox.ratio(0));
+    }
+
+    #[test]
+    fn ratio_should_include_final_drive(){
+        let gearbox = Gearbox::new(vec![3.0, 2.0], 2.0);
+
+        assert_eq!(6.0, gearbox.ratio(1));
+        assert_eq!(4.0, gearbox.ratio(2));
+    }
+
+    #[test]
+    fn gears_beyond_the_table_should_have_no_ratio(){
+        let gearbox = Gearbox::new(vec![3.0, 2.0], 1.0);
+
+        assert_eq!(2, gearbox.gear_count());
+        assert_eq!(0.0, gearbox.ratio(3));
+    }
+
+    #[test]
+    fn automatic_should_shift_up_above_upshift_rpm(){
+        let gearbox = Gearbox::five_speed_automatic();
+
+        assert_eq!(3, gearbox.automatic_gear(2, gearbox.upshift_rpm + 1.0));
+        assert_eq!(5, gearbox.automatic_gear(5, gearbox.upshift_rpm + 1.0));
+    }
+
+    #[test]
+    fn automatic_should_shift_down_below_downshift_rpm(){
+        let gearbox = Gearbox::five_speed_automatic();
+
+        assert_eq!(1, gearbox.automatic_gear(2, gearbox.downshift_rpm - 1.0));
+        assert_eq!(1, gearbox.automatic_gear(1, gearbox.downshift_rpm - 1.0));
+    }
+
+    #[test]
+    fn automatic_should_hold_gear_between_shift_points_and_in_neutral(){
+        let gearbox = Gearbox::five_speed_automatic();
+
+        assert_eq!(3, gearbox.automatic_gear(3, 3000.0));
+        assert_eq!(0, gearbox.automatic_gear(0, 7000.0));
+    }
+
+    #[test]
+    fn manual_gearbox_should_never_shift_by_itself(){
+        let gearbox = Gearbox::five_speed();
+
+        assert_eq!(2, gearbox.automatic_gear(2, 7000.0));
+    }
+
+    #[test]
+    fn reverse_should_have_a_negative_ratio_like_first_gear(){
+        let gearbox = Gearbox::five_speed();
+
+        assert_eq!(-3.0, gearbox.ratio(-1));
+        assert_eq!(0.0, gearbox.ratio(-2));
+    }
+}
@@ -0,0 +1,126 @@
+use crate::rng::Rng;
+use crate::{Level, ObstacleKind, Surface, Track};
+
+/// Road, in m, from the start to the goal zone at difficulty 0.
+const BASE_LENGTH: f64 = 2000.0;
+/// Road added to the approach at difficulty 1.
+const EXTRA_LENGTH: f64 = 6000.0;
+/// Goal zone width at difficulty 0.
+const EASIEST_GOAL: f64 = 400.0;
+/// Goal zone width at difficulty 1.
+const HARDEST_GOAL: f64 = 100.0;
+/// Length of every generated slope.
+const SLOPE_LENGTH: f64 = 500.0;
+/// Steepest gradient at difficulty 0.
+const BASE_GRADIENT: f64 = 0.005;
+/// Gradient added to the steepest at difficulty 1. Steeper hills would
+/// outclimb or outbrake the arcade car.
+const EXTRA_GRADIENT: f64 = 0.015;
+/// Road before the goal zone kept flat, dry and clear, so the car can
+/// always brake into it.
+const CLEAR_APPROACH: f64 = 1000.0;
+/// Road after the start kept clear of cones.
+const CLEAR_START: f64 = 200.0;
+/// Most friction zones, at difficulty 1.
+const MAX_FRICTION_ZONES: f64 = 4.0;
+/// Most cones, at difficulty 1. Even all hit leave the car short of a
+/// wreck.
+const MAX_CONES: f64 = 8.0;
+
+/// Level generated from `seed` at `difficulty`, from 0 (easiest) to 1. A
+/// difficulty that isn't a finite number counts as 0.
+///
+/// The same seed and difficulty always give the same level. It stays
+/// solvable: the last stretch before the goal zone is flat, dry and clear,
+/// and only cones, never parked cars, stand on the road.
+pub fn generate(seed: u64, difficulty: f64) -> Level {
+    let difficulty = if difficulty.is_finite() { difficulty.clamp(0.0, 1.0) } else { 0.0 };
+    let mut rng = Rng::new(seed);
+    let mut between = |from: f64, to: f64| from + (to - from) * rng.next_f64();
+
+    let start_position = 0.0;
+    let goal_start = start_position + BASE_LENGTH + EXTRA_LENGTH * between(difficulty / 2.0, difficulty);
+    let goal_end = goal_start + EASIEST_GOAL + (HARDEST_GOAL - EASIEST_GOAL) * difficulty;
+    let open_road = goal_start - CLEAR_APPROACH;
+
+    let mut track = Track::new();
+    let steepest = BASE_GRADIENT + EXTRA_GRADIENT * difficulty;
+    let mut slope_start = start_position;
+    while slope_start + SLOPE_LENGTH <= open_road {
+        track.add_slope(slope_start, slope_start + SLOPE_LENGTH, between(-steepest, steepest));
+        slope_start += SLOPE_LENGTH;
+    }
+
+    let surfaces = if difficulty > 0.5 {
+        &[Surface::WetAsphalt, Surface::Gravel, Surface::Ice][..]
+    } else {
+        &[Surface::WetAsphalt, Surface::Gravel][..]
+    };
+    for _ in 0..(MAX_FRICTION_ZONES * difficulty).round() as u32 {
+        let surface = surfaces[(between(0.0, surfaces.len() as f64) as usize).min(surfaces.len() - 1)];
+        let start = between(start_position, open_road - 300.0);
+        track.add_surface(start, start + between(100.0, 300.0), surface);
+    }
+
+    for _ in 0..(MAX_CONES * difficulty).round() as u32 {
+        track.add_obstacle(ObstacleKind::Cone, between(start_position + CLEAR_START, open_road));
+    }
+
+    let mut level = Level {
+        start_position,
+        goal_start,
+        goal_end,
+        track,
+        ..Level::default()
+    };
+    level.set_seed(seed);
+    level
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::{autopilot, update, State};
+
+    #[test]
+    fn same_seed_should_generate_the_same_level(){
+        assert_eq!(generate(42, 0.7), generate(42, 0.7));
+        assert_ne!(generate(42, 0.7), generate(43, 0.7));
+    }
+
+    #[test]
+    fn non_finite_difficulty_should_generate_the_easiest_level(){
+        for difficulty in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            assert_eq!(generate(42, 0.0), generate(42, difficulty));
+        }
+    }
+
+    #[test]
+    fn harder_levels_should_have_narrower_goal_zones(){
+        let easy = generate(1, 0.0);
+        let hard = generate(1, 1.0);
+
+        assert!(hard.goal_end - hard.goal_start < easy.goal_end - easy.goal_start);
+    }
+
+    #[test]
+    fn generated_levels_should_be_valid_and_solvable(){
+        for seed in 0..20 {
+            for difficulty in [0.0, 0.5, 1.0] {
+                let level = generate(seed, difficulty);
+                assert_eq!(Ok(()), level.validate());
+
+                let mut state = State::from_level(&level);
+                for _ in 0..100_000 {
+                    let input = autopilot(&state);
+                    state = update(state, input, 0.1);
+                    if state.won || state.lost {
+                        break;
+                    }
+                }
+                assert!(state.won, "seed {} at {}: {:?}", seed, difficulty, state.lose_reason);
+            }
+        }
+    }
+}
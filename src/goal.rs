@@ -0,0 +1,104 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+/// A stretch of road the car may stop in to win, worth `value` times the
+/// precision points of the level's main goal zone.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GoalZone {
+    pub start: f64,
+    pub end: f64,
+    /// Share of the precision points the zone is worth, 1 for the main
+    /// goal zone.
+    pub value: f64
+}
+
+#[wasm_bindgen]
+impl GoalZone {
+    #[wasm_bindgen(constructor)]
+    pub fn new(start: f64, end: f64, value: f64) -> GoalZone {
+        GoalZone { start, end, value }
+    }
+}
+
+impl GoalZone {
+    pub fn contains(&self, position: f64) -> bool {
+        position > self.start && position < self.end
+    }
+}
+
+/// How the main goal zone moves along the road over the run, say a ferry
+/// drifting off or bobbing at the quay. It stands still by default.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GoalMotion {
+    /// Steady speed, in m/s, the zone drifts forwards at.
+    pub drift_speed: f64,
+    /// Distance, in m, the zone swings either way of its drifting position.
+    pub amplitude: f64,
+    /// Seconds a full swing takes. 0 for no swinging.
+    pub period: f64
+}
+
+#[wasm_bindgen]
+impl GoalMotion {
+    #[wasm_bindgen(constructor)]
+    pub fn new(drift_speed: f64, amplitude: f64, period: f64) -> GoalMotion {
+        GoalMotion { drift_speed, amplitude, period }
+    }
+
+    /// How far the zone has moved from where it was at the start of the
+    /// run, `time` seconds in.
+    pub fn offset(&self, time: f64) -> f64 {
+        let swing = if self.period > 0.0 {
+            self.amplitude * (std::f64::consts::TAU * time / self.period).sin()
+        } else {
+            0.0
+        };
+        self.drift_speed * time + swing
+    }
+}
+
+/// First zone of `zones` containing `position`, if any.
+pub fn zone_at(zones: &[GoalZone], position: f64) -> Option<GoalZone> {
+    zones.iter().copied().find(|zone| zone.contains(position))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn zone_should_not_contain_its_bounds(){
+        let zone = GoalZone::new(10.0, 20.0, 1.0);
+
+        assert!(zone.contains(15.0));
+        assert!(!zone.contains(10.0));
+        assert!(!zone.contains(20.0));
+    }
+
+    #[test]
+    fn offset_should_drift_and_swing(){
+        let motion = GoalMotion::new(2.0, 5.0, 4.0);
+
+        assert_eq!(0.0, motion.offset(0.0));
+        assert!((motion.offset(1.0) - 7.0).abs() < 1e-9);
+        assert!((motion.offset(4.0) - 8.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn still_zone_should_not_move(){
+        assert_eq!(0.0, GoalMotion::default().offset(10.0));
+    }
+
+    #[test]
+    fn zone_at_should_find_the_zone_containing_the_position(){
+        let zones = [GoalZone::new(10.0, 20.0, 0.5), GoalZone::new(30.0, 40.0, 1.0)];
+
+        assert_eq!(Some(zones[1]), zone_at(&zones, 35.0));
+        assert_eq!(None, zone_at(&zones, 25.0));
+    }
+}
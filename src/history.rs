@@ -0,0 +1,122 @@
+use wasm_bindgen::prelude::*;
+
+use crate::State;
+
+/// Every tick's state of a run, for post-run analysis screens.
+#[wasm_bindgen]
+#[derive(Clone, Default)]
+pub struct History {
+    /// States in tick order, one per tick from the first recorded.
+    states: Vec<State>
+}
+
+#[wasm_bindgen]
+impl History {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> History {
+        History::default()
+    }
+
+    /// Number of states recorded.
+    pub fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+
+    /// State at tick `tick` of the run, or nothing when it wasn't
+    /// recorded.
+    pub fn state_at(&self, tick: u32) -> Option<State> {
+        self.index(tick).map(|index| self.states[index].clone())
+    }
+
+    /// Fastest the car went, forwards or backwards, from tick `from` to
+    /// tick `to` included, or nothing when none of them was recorded.
+    pub fn max_speed_between(&self, from: u32, to: u32) -> Option<f64> {
+        self.between(from, to)
+            .iter()
+            .map(|state| state.speed.abs())
+            .fold(None, |max, speed| Some(max.map_or(speed, |max: f64| max.max(speed))))
+    }
+}
+
+impl History {
+    /// Records the state of the next tick.
+    pub fn record(&mut self, state: &State) {
+        self.states.push(state.clone());
+    }
+
+    /// Forgets every state after tick `tick`.
+    pub fn truncate(&mut self, tick: u32) {
+        if let Some(first) = self.states.first().map(|state| state.ticks) {
+            self.states.truncate(tick.saturating_sub(first) as usize + 1);
+        }
+    }
+
+    fn index(&self, tick: u32) -> Option<usize> {
+        let first = self.states.first()?.ticks;
+        let index = tick.checked_sub(first)? as usize;
+        if index < self.states.len() { Some(index) } else { None }
+    }
+
+    /// Recorded states from tick `from` to tick `to` included.
+    fn between(&self, from: u32, to: u32) -> &[State] {
+        let first = match self.states.first() {
+            Some(state) => state.ticks,
+            None => return &[]
+        };
+        let start = (from.saturating_sub(first) as usize).min(self.states.len());
+        let end = ((to.saturating_sub(first) as usize) + 1).min(self.states.len());
+        if to < first || start >= end { &[] } else { &self.states[start..end] }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::{update, Input};
+
+    fn history(ticks: u32) -> History {
+        let mut history = History::new();
+        let mut state = State::new();
+        history.record(&state);
+        for tick in 0..ticks {
+            let input = if tick < 5 { Input::new(1.0, 0.0) } else { Input::new(0.0, 1.0) };
+            state = update(state, input, 0.5);
+            history.record(&state);
+        }
+        history
+    }
+
+    #[test]
+    fn state_at_should_find_recorded_ticks_only(){
+        let history = history(10);
+
+        assert_eq!(Some(3), history.state_at(3).map(|state| state.ticks));
+        assert!(history.state_at(11).is_none());
+    }
+
+    #[test]
+    fn max_speed_between_should_look_at_the_range_only(){
+        let history = history(10);
+        let speed_at = |tick| history.state_at(tick).unwrap().speed;
+
+        let fastest = (0..=10).map(speed_at).fold(0.0, f64::max);
+
+        assert_eq!(Some(fastest), history.max_speed_between(0, 10));
+        assert_eq!(Some(speed_at(2)), history.max_speed_between(0, 2));
+        assert_eq!(None, history.max_speed_between(20, 30));
+    }
+
+    #[test]
+    fn truncate_should_forget_later_ticks(){
+        let mut history = history(10);
+        history.truncate(4);
+
+        assert_eq!(5, history.len());
+        assert!(history.state_at(5).is_none());
+    }
+}
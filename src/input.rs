@@ -0,0 +1,121 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+/// Driver controls for one tick.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Input {
+    pub throttle: f64,
+    /// Brake pedal position, from 0 (released) to 1 (floored).
+    pub brake: f64,
+    /// Clutch pedal position, from 0 (released, engaged) to 1 (pressed).
+    /// Only cars with a clutch use it.
+    pub clutch: f64
+}
+
+#[wasm_bindgen]
+impl Input {
+    #[wasm_bindgen(constructor)]
+    pub fn new(throttle: f64, brake: f64) -> Input {
+        Input {
+            throttle,
+            brake,
+            clutch: 0.0
+        }
+    }
+}
+
+impl Input {
+    /// Moves each pedal from these positions towards `target` by at most
+    /// `max_change`.
+    pub fn towards(&self, target: Input, max_change: f64) -> Input {
+        let step = |from: f64, to: f64| from + (to - from).clamp(-max_change, max_change);
+        Input {
+            throttle: step(self.throttle, target.throttle),
+            brake: step(self.brake, target.brake),
+            clutch: step(self.clutch, target.clutch)
+        }
+    }
+}
+
+/// One of the pedals.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pedal {
+    Throttle,
+    Brake,
+    Clutch
+}
+
+/// Pedal moves the front end queued as they happened, each with its
+/// wall-clock time, so they take effect on the tick they fall in rather
+/// than on the next animation frame. A key press is a move to 1, its
+/// release a move back to 0.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct InputQueue {
+    /// Wall-clock time in milliseconds, pedal and position of each move,
+    /// oldest first.
+    moves: Vec<(f64, Pedal, f64)>
+}
+
+impl InputQueue {
+    pub fn new() -> InputQueue {
+        InputQueue::default()
+    }
+
+    pub fn push(&mut self, time_ms: f64, pedal: Pedal, position: f64) {
+        let index = self.moves.partition_point(|(time, _, _)| *time <= time_ms);
+        self.moves.insert(index, (time_ms, pedal, position));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.moves.is_empty()
+    }
+
+    /// `input` with every move made by `time_ms` applied, in order. The
+    /// moves are taken off the queue.
+    pub fn apply_until(&mut self, mut input: Input, time_ms: f64) -> Input {
+        let due = self.moves.partition_point(|(time, _, _)| *time <= time_ms);
+        for (_, pedal, position) in self.moves.drain(..due) {
+            match pedal {
+                Pedal::Throttle => input.throttle = position,
+                Pedal::Brake => input.brake = position,
+                Pedal::Clutch => input.clutch = position
+            }
+        }
+        input
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn towards_should_move_pedals_by_at_most_max_change(){
+        let released = Input::new(0.0, 1.0);
+
+        assert_eq!(Input::new(0.25, 0.75), released.towards(Input::new(1.0, 0.0), 0.25));
+        assert_eq!(Input::new(0.1, 0.9), released.towards(Input::new(0.1, 0.9), 0.25));
+    }
+
+    #[test]
+    fn apply_until_should_apply_only_the_moves_made_by_then(){
+        let mut queue = InputQueue::new();
+        queue.push(20.0, Pedal::Throttle, 0.0);
+        queue.push(10.0, Pedal::Throttle, 1.0);
+        queue.push(15.0, Pedal::Brake, 0.5);
+
+        assert_eq!(Input::new(1.0, 0.5), queue.apply_until(Input::default(), 15.0));
+        assert_eq!(Input::new(0.0, 0.5), queue.apply_until(Input::new(1.0, 0.5), 30.0));
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn unlimited_max_change_should_reach_target_at_once(){
+        let target = Input::new(1.0, 0.0);
+
+        assert_eq!(target, Input::new(0.0, 1.0).towards(target, f64::MAX));
+    }
+}
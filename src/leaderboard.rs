@@ -0,0 +1,155 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::binary;
+
+/// A scored run on a leaderboard.
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LeaderboardEntry {
+    pub name: String,
+    pub score: f64,
+    /// Run seed, so others can play the same run.
+    pub seed: u64,
+    /// Key the host page stored the run's replay under.
+    pub replay_key: String
+}
+
+#[wasm_bindgen]
+impl LeaderboardEntry {
+    #[wasm_bindgen(constructor)]
+    pub fn new(name: String, score: f64, seed: u64, replay_key: String) -> LeaderboardEntry {
+        LeaderboardEntry { name, score, seed, replay_key }
+    }
+}
+
+/// The best `capacity` scored runs, best first. Ties keep the run
+/// submitted first ahead.
+///
+/// The host page only stores and fetches the board as a blob, with
+/// [`Leaderboard::to_bytes`] or [`Leaderboard::to_json`].
+#[wasm_bindgen]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Leaderboard {
+    capacity: u32,
+    entries: Vec<LeaderboardEntry>
+}
+
+#[wasm_bindgen]
+impl Leaderboard {
+    #[wasm_bindgen(constructor)]
+    pub fn new(capacity: u32) -> Leaderboard {
+        Leaderboard { capacity, entries: Vec::new() }
+    }
+
+    pub fn capacity(&self) -> u32 {
+        self.capacity
+    }
+
+    /// Runs on the board, best first.
+    #[wasm_bindgen(getter)]
+    pub fn entries(&self) -> Vec<LeaderboardEntry> {
+        self.entries.clone()
+    }
+
+    /// Puts `entry` on the board. Returns its rank, from 0, or nothing
+    /// when it scored too low to make it or is already on the board.
+    pub fn submit(&mut self, entry: LeaderboardEntry) -> Option<u32> {
+        if self.entries.contains(&entry) {
+            return None;
+        }
+        let rank = self.entries.partition_point(|ranked| ranked.score >= entry.score);
+        if rank >= self.capacity as usize {
+            return None;
+        }
+        self.entries.insert(rank, entry);
+        self.entries.truncate(self.capacity as usize);
+        Some(rank as u32)
+    }
+
+    /// Adds the runs of `other`, say a board fetched from a server, keeping
+    /// the best `capacity` of both.
+    pub fn merge(&mut self, other: &Leaderboard) {
+        for entry in &other.entries {
+            self.submit(entry.clone());
+        }
+    }
+
+    /// The board as JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("leaderboard should serialize to JSON")
+    }
+
+    /// Board saved with [`Leaderboard::to_json`], or why `json` isn't one.
+    pub fn from_json(json: &str) -> Result<Leaderboard, String> {
+        serde_json::from_str(json).map_err(|error| error.to_string())
+    }
+
+    /// The board in the compact binary encoding.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        binary::to_bytes(self).expect("leaderboard should encode to bytes")
+    }
+
+    /// Board encoded with [`Leaderboard::to_bytes`], or why `bytes` aren't
+    /// one.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Leaderboard, String> {
+        binary::from_bytes(bytes).map_err(|error| error.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn entry(name: &str, score: f64) -> LeaderboardEntry {
+        LeaderboardEntry::new(name.to_string(), score, 7, format!("replay-{}", name))
+    }
+
+    fn names(leaderboard: &Leaderboard) -> Vec<String> {
+        leaderboard.entries().into_iter().map(|entry| entry.name).collect()
+    }
+
+    #[test]
+    fn submit_should_keep_the_best_runs_in_order(){
+        let mut leaderboard = Leaderboard::new(2);
+
+        assert_eq!(Some(0), leaderboard.submit(entry("a", 100.0)));
+        assert_eq!(Some(0), leaderboard.submit(entry("b", 300.0)));
+        assert_eq!(Some(1), leaderboard.submit(entry("c", 200.0)));
+        assert_eq!(None, leaderboard.submit(entry("d", 50.0)));
+        assert_eq!(vec!["b", "c"], names(&leaderboard));
+    }
+
+    #[test]
+    fn ties_should_keep_the_earlier_run_ahead(){
+        let mut leaderboard = Leaderboard::new(3);
+        leaderboard.submit(entry("a", 100.0));
+
+        assert_eq!(Some(1), leaderboard.submit(entry("b", 100.0)));
+    }
+
+    #[test]
+    fn merge_should_keep_the_best_of_both_boards_once(){
+        let mut leaderboard = Leaderboard::new(3);
+        leaderboard.submit(entry("a", 100.0));
+        leaderboard.submit(entry("b", 300.0));
+        let mut other = Leaderboard::new(3);
+        other.submit(entry("b", 300.0));
+        other.submit(entry("c", 200.0));
+        other.submit(entry("d", 50.0));
+
+        leaderboard.merge(&other);
+        assert_eq!(vec!["b", "c", "a"], names(&leaderboard));
+    }
+
+    #[test]
+    fn leaderboard_should_round_trip_through_json_and_bytes(){
+        let mut leaderboard = Leaderboard::new(3);
+        leaderboard.submit(entry("a", 100.0));
+        leaderboard.submit(entry("b", 300.0));
+
+        assert_eq!(Ok(leaderboard.clone()), Leaderboard::from_json(&leaderboard.to_json()));
+        assert_eq!(Ok(leaderboard.clone()), Leaderboard::from_bytes(&leaderboard.to_bytes()));
+    }
+}
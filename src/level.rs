@@ -0,0 +1,495 @@
+use std::fmt::{self, Display};
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::rng::Rng;
+use crate::validate::{finite, non_negative, positive, stretch};
+use crate::{Checkpoint, Endless, Engine, Forecast, Gearbox, GoalMotion, GoalZone, LoseConditions, OvershootPolicy, Powertrain, Surface, Track, TrafficCar, Trailer, Vehicle, VehicleLimits, Weather, WinCondition, Wind, DEFAULT_TIME_SCALE};
+
+/// Number of levels in the built-in catalogue.
+const LEVEL_COUNT: usize = 5;
+
+/// Streams of the run seed each source of randomness draws its own seed
+/// from.
+const WIND_STREAM: u64 = 1;
+const WEATHER_STREAM: u64 = 2;
+const ENDLESS_STREAM: u64 = 3;
+
+/// Why start and goal bounds were rejected.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BoundsError {
+    /// A bound is NaN or infinite.
+    NotFinite,
+    /// The goal zone ends before it starts, or is empty.
+    EmptyGoal,
+    /// The car would start in or past the goal zone.
+    StartNotBeforeGoal,
+    /// A value is beyond what the car can do, like a speed past its
+    /// limits.
+    OutOfRange
+}
+
+impl Display for BoundsError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(match self {
+            BoundsError::NotFinite => "a bound is not a finite number",
+            BoundsError::EmptyGoal => "the goal zone ends before it starts",
+            BoundsError::StartNotBeforeGoal => "the car starts in or past the goal zone",
+            BoundsError::OutOfRange => "a value is beyond what the car can do"
+        })
+    }
+}
+
+/// Everything a level sets up before the run: where the car starts, the
+/// goal zone, the car itself and the road conditions.
+#[wasm_bindgen]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Level {
+    pub start_position: f64,
+    pub goal_start: f64,
+    pub goal_end: f64,
+    #[wasm_bindgen(getter_with_clone)]
+    pub vehicle: Vehicle,
+    #[wasm_bindgen(skip)]
+    pub track: Track,
+    /// Other cars on the road, from rearmost to frontmost.
+    #[wasm_bindgen(skip)]
+    pub traffic: Vec<TrafficCar>,
+    pub trailer: Trailer,
+    pub wind: Wind,
+    pub forecast: Forecast,
+    /// `f64::MAX` when the level has no limiter.
+    pub speed_limit: f64,
+    /// `f64::MAX` when the car may enter the goal zone at any speed.
+    pub max_entry_speed: f64,
+    /// Nitrous bottles the car starts with.
+    pub nitrous_charges: u32,
+    pub time_of_day: f64,
+    /// In-game hours going by per simulated second. 0 stops the clock.
+    pub time_scale: f64,
+    /// Seconds the run must be won within, making it a time trial.
+    /// `f64::MAX` for no time limit.
+    pub target_time: f64,
+    pub win_condition: WinCondition,
+    /// Fuel, or charge, [`WinCondition::StopWithFuelRemaining`] needs left.
+    pub energy_reserve: f64,
+    /// Distance from the middle of the goal zone
+    /// [`WinCondition::ExactPosition`] accepts.
+    pub position_tolerance: f64,
+    pub lose_conditions: LoseConditions,
+    pub overshoot_policy: OvershootPolicy,
+    /// Ticks the car may stay past the goal zone under
+    /// [`OvershootPolicy::GraceWindow`].
+    pub overshoot_grace_ticks: u32,
+    pub endless: Endless,
+    /// Checkpoints to cross before the goal zone, in road order.
+    #[wasm_bindgen(skip)]
+    pub checkpoints: Vec<Checkpoint>,
+    /// Goal zones worth less than the main one, short of it, in road
+    /// order.
+    #[wasm_bindgen(skip)]
+    pub extra_goals: Vec<GoalZone>,
+    /// How the main goal zone moves over the run.
+    pub goal_motion: GoalMotion,
+    /// Run seed the wind, weather and endless zones were seeded from with
+    /// [`Level::set_seed`]. 0 keeps the seeds the level was designed with.
+    pub seed: u64,
+    /// Score a win needs for two stars.
+    pub two_star_score: f64,
+    /// Score a win needs for three stars.
+    pub three_star_score: f64
+}
+
+#[wasm_bindgen]
+impl Level {
+    /// Level number `index` of the built-in catalogue, from 0, or nothing
+    /// past the last one.
+    pub fn get(index: usize) -> Option<Level> {
+        let level = match index {
+            0 => Level::default(),
+            1 => Level::hills(),
+            2 => Level::black_ice(),
+            3 => Level::truck_delivery(),
+            4 => Level::drag_strip(),
+            _ => return None
+        };
+        Some(level)
+    }
+
+    pub fn count() -> usize {
+        LEVEL_COUNT
+    }
+
+    /// Level generated from `seed` at `difficulty`, from 0 (easiest) to 1.
+    /// Players sharing a seed get the same level.
+    pub fn generate(seed: u64, difficulty: f64) -> Level {
+        crate::generate(seed, difficulty)
+    }
+
+    /// Checks that the goal zone is a real stretch of road ahead of the
+    /// start.
+    pub fn validate(&self) -> Result<(), BoundsError> {
+        if ![self.start_position, self.goal_start, self.goal_end].iter().all(|bound| bound.is_finite()) {
+            return Err(BoundsError::NotFinite);
+        }
+        if self.goal_end <= self.goal_start {
+            return Err(BoundsError::EmptyGoal);
+        }
+        if self.start_position >= self.goal_start {
+            return Err(BoundsError::StartNotBeforeGoal);
+        }
+        Ok(())
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn track(&self) -> Track {
+        self.track.clone()
+    }
+
+    /// Swaps in `track`, unless it fails [`Track::check`].
+    #[wasm_bindgen(setter)]
+    pub fn set_track(&mut self, track: Track) -> Result<(), String> {
+        track.check()?;
+        self.track = track;
+        Ok(())
+    }
+
+    /// Adds a checkpoint at `position` to be crossed by `split_time`,
+    /// `f64::MAX` for none.
+    pub fn add_checkpoint(&mut self, position: f64, split_time: f64) {
+        let index = self.checkpoints.partition_point(|checkpoint| checkpoint.position < position);
+        self.checkpoints.insert(index, Checkpoint::new(position, split_time));
+    }
+
+    /// Adds a zone from `start` to `end` the car may stop in instead of the
+    /// main goal zone, worth `value` times its precision points.
+    pub fn add_goal_zone(&mut self, start: f64, end: f64, value: f64) {
+        let index = self.extra_goals.partition_point(|zone| zone.start < start);
+        self.extra_goals.insert(index, GoalZone::new(start, end, value));
+    }
+
+    /// The level as JSON, for saving custom levels.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("level should serialize to JSON")
+    }
+
+    /// Level saved with [`Level::to_json`] or written by hand, or why
+    /// `json` isn't a playable one: a missing, unknown or mistyped field,
+    /// with where it is, or a value that makes no sense, with its name.
+    pub fn from_json(json: &str) -> Result<Level, String> {
+        let level: Level = serde_json::from_str(json).map_err(|error| error.to_string())?;
+        level.validate().map_err(|error| format!("invalid level: {}", error))?;
+        level.check().map_err(|error| format!("invalid level: {}", error))?;
+        Ok(level)
+    }
+
+    /// Reseeds every source of randomness of the level from the one run
+    /// `seed`, so sharing it shares the run.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.wind.seed = Rng::keyed(seed, WIND_STREAM).next_u64();
+        self.forecast.seed = Rng::keyed(seed, WEATHER_STREAM).next_u64();
+        self.endless.seed = Rng::keyed(seed, ENDLESS_STREAM).next_u64();
+    }
+
+    /// Puts a car driving at `cruise_speed` on the road at `position`.
+    pub fn add_traffic(&mut self, position: f64, cruise_speed: f64) {
+        let index = self.traffic.partition_point(|car| car.position < position);
+        self.traffic.insert(index, TrafficCar::new(position, cruise_speed));
+    }
+}
+
+impl Level {
+    /// Checks everything past the bounds [`Level::validate`] covers: the
+    /// car's physics, the road and the rest of the level have sane values,
+    /// and checkpoints, extra goal zones and traffic come in road order, as
+    /// the `add_` methods keep them. Errors name the offending field.
+    pub fn check(&self) -> Result<(), String> {
+        self.vehicle.check()?;
+        self.track.check()?;
+        positive("speed_limit", self.speed_limit)?;
+        for (field, value) in [
+            ("max_entry_speed", self.max_entry_speed),
+            ("time_of_day", self.time_of_day),
+            ("time_scale", self.time_scale),
+            ("target_time", self.target_time),
+            ("energy_reserve", self.energy_reserve),
+            ("position_tolerance", self.position_tolerance),
+            ("two_star_score", self.two_star_score),
+            ("three_star_score", self.three_star_score),
+            ("trailer.mass", self.trailer.mass),
+            ("trailer.length", self.trailer.length),
+            ("trailer.max_push_force", self.trailer.max_push_force),
+            ("wind.gust_strength", self.wind.gust_strength),
+            ("wind.gust_period", self.wind.gust_period),
+            ("forecast.change_period", self.forecast.change_period),
+            ("endless.min_gap", self.endless.min_gap),
+            ("endless.max_gap", self.endless.max_gap),
+            ("goal_motion.period", self.goal_motion.period)
+        ] {
+            non_negative(field, value)?;
+        }
+        finite("wind.force", self.wind.force)?;
+        finite("goal_motion.drift_speed", self.goal_motion.drift_speed)?;
+        finite("goal_motion.amplitude", self.goal_motion.amplitude)?;
+        for (index, checkpoint) in self.checkpoints.iter().enumerate() {
+            finite(&format!("checkpoints[{}].position", index), checkpoint.position)?;
+            non_negative(&format!("checkpoints[{}].split_time", index), checkpoint.split_time)?;
+        }
+        for (index, zone) in self.extra_goals.iter().enumerate() {
+            let field = format!("extra_goals[{}]", index);
+            stretch(&field, zone.start, zone.end)?;
+            non_negative(&format!("{}.value", field), zone.value)?;
+        }
+        for (index, car) in self.traffic.iter().enumerate() {
+            finite(&format!("traffic[{}].position", index), car.position)?;
+            non_negative(&format!("traffic[{}].cruise_speed", index), car.cruise_speed)?;
+        }
+        if self.checkpoints.windows(2).any(|pair| pair[0].position > pair[1].position) {
+            return Err(String::from("checkpoints are not in road order"));
+        }
+        if self.extra_goals.windows(2).any(|pair| pair[0].start > pair[1].start) {
+            return Err(String::from("extra_goals are not in road order"));
+        }
+        if self.traffic.windows(2).any(|pair| pair[0].position > pair[1].position) {
+            return Err(String::from("traffic is not in road order"));
+        }
+        Ok(())
+    }
+}
+
+impl Level {
+    /// Rolling hills: the car coasts down on its own and has to climb into
+    /// a goal zone on a rise.
+    fn hills() -> Level {
+        let mut track = Track::new();
+        track.add_slope(1000.0, 2000.0, 0.05);
+        track.add_slope(2000.0, 3500.0, -0.04);
+        track.add_slope(3500.0, 4500.0, 0.03);
+        Level {
+            goal_start: 4200.0,
+            goal_end: 4400.0,
+            vehicle: Vehicle { resistance: true, ..Vehicle::default() },
+            track,
+            ..Level::default()
+        }
+    }
+
+    /// A night run in the rain with an icy patch right before a short goal
+    /// zone.
+    fn black_ice() -> Level {
+        let mut track = Track::new();
+        track.add_surface(2500.0, 2800.0, Surface::Ice);
+        Level {
+            goal_start: 2900.0,
+            goal_end: 3100.0,
+            vehicle: Vehicle { grip_limit: true, brake_force: 8.0, ..Vehicle::default() },
+            track,
+            forecast: Forecast::new(Weather::Rain, 0.0, 0),
+            time_of_day: 22.0,
+            ..Level::default()
+        }
+    }
+
+    /// A loaded truck with a limiter that must roll gently into the loading
+    /// bay.
+    fn truck_delivery() -> Level {
+        Level {
+            goal_start: 5900.0,
+            goal_end: 6000.0,
+            vehicle: Vehicle {
+                mass: 12000.0,
+                brake_force: 60000.0,
+                powertrain: Powertrain::Combustion,
+                engine: Engine { peak_force: 20000.0, ..Default::default() },
+                gearbox: Gearbox::five_speed_automatic(),
+                resistance: true,
+                limits: VehicleLimits { max_reverse_speed: 3.0, ..VehicleLimits::default() },
+                ..Vehicle::default()
+            },
+            wind: Wind::new(-2000.0, 1000.0, 7),
+            speed_limit: 25.0,
+            max_entry_speed: 8.0,
+            overshoot_policy: OvershootPolicy::Penalty,
+            two_star_score: 1000.0,
+            three_star_score: 1200.0,
+            ..Level::default()
+        }
+    }
+
+    /// A short time trial with a couple of nitrous bottles and a goal zone
+    /// that punishes arriving flat out.
+    fn drag_strip() -> Level {
+        Level {
+            start_position: 0.0,
+            goal_start: 1500.0,
+            goal_end: 1600.0,
+            vehicle: Vehicle {
+                mass: 1000.0,
+                brake_force: 10000.0,
+                powertrain: Powertrain::Combustion,
+                engine: Engine { peak_force: 6000.0, ..Default::default() },
+                gearbox: Gearbox::five_speed(),
+                grip_limit: true,
+                ..Vehicle::default()
+            },
+            max_entry_speed: 30.0,
+            nitrous_charges: 2,
+            target_time: 120.0,
+            win_condition: WinCondition::StopWithinTime,
+            two_star_score: 1300.0,
+            three_star_score: 1600.0,
+            ..Level::default()
+        }
+    }
+}
+
+/// The first level of the catalogue: the arcade car on a flat, dry road.
+impl Default for Level {
+    fn default() -> Level {
+        Level {
+            start_position: 500.0,
+            goal_start: 9000.0,
+            goal_end: 10000.0,
+            vehicle: Vehicle::default(),
+            track: Track::default(),
+            traffic: Vec::new(),
+            trailer: Trailer::default(),
+            wind: Wind::default(),
+            forecast: Forecast::default(),
+            speed_limit: f64::MAX,
+            max_entry_speed: f64::MAX,
+            nitrous_charges: 0,
+            time_of_day: 12.0,
+            time_scale: DEFAULT_TIME_SCALE,
+            target_time: f64::MAX,
+            win_condition: WinCondition::StopInZone,
+            energy_reserve: 0.0,
+            position_tolerance: 1.0,
+            lose_conditions: LoseConditions::default(),
+            overshoot_policy: OvershootPolicy::InstantLoss,
+            overshoot_grace_ticks: 0,
+            endless: Endless::default(),
+            checkpoints: Vec::new(),
+            extra_goals: Vec::new(),
+            goal_motion: GoalMotion::default(),
+            seed: 0,
+            two_star_score: 1100.0,
+            three_star_score: 1300.0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn catalogue_should_have_count_levels(){
+        assert!(Level::get(Level::count() - 1).is_some());
+        assert!(Level::get(Level::count()).is_none());
+    }
+
+    #[test]
+    fn every_level_should_have_valid_bounds(){
+        for index in 0..Level::count() {
+            assert_eq!(Ok(()), Level::get(index).unwrap().validate(), "level {}", index);
+        }
+    }
+
+    #[test]
+    fn validate_should_reject_goal_zones_that_are_empty_or_not_ahead(){
+        let level = |start_position, goal_start, goal_end| Level { start_position, goal_start, goal_end, ..Level::default() };
+
+        assert_eq!(Err(BoundsError::NotFinite), level(f64::NAN, 10.0, 20.0).validate());
+        assert_eq!(Err(BoundsError::EmptyGoal), level(0.0, 20.0, 20.0).validate());
+        assert_eq!(Err(BoundsError::EmptyGoal), level(0.0, 20.0, 10.0).validate());
+        assert_eq!(Err(BoundsError::StartNotBeforeGoal), level(15.0, 10.0, 20.0).validate());
+    }
+
+    #[test]
+    fn star_thresholds_should_rise_with_the_stars(){
+        for index in 0..Level::count() {
+            let level = Level::get(index).unwrap();
+            assert!(level.two_star_score < level.three_star_score, "level {}", index);
+        }
+    }
+
+    #[test]
+    fn set_seed_should_reseed_every_source_of_randomness(){
+        let mut level = Level::default();
+        let mut other = Level::default();
+        level.set_seed(9);
+        other.set_seed(9);
+
+        assert_eq!(level, other);
+        assert_ne!(level.wind.seed, Level::default().wind.seed);
+        assert_ne!(level.wind.seed, level.forecast.seed);
+    }
+
+    #[test]
+    fn every_level_should_round_trip_through_json(){
+        for index in 0..Level::count() {
+            let level = Level::get(index).unwrap();
+            assert_eq!(Ok(level.clone()), Level::from_json(&level.to_json()), "level {}", index);
+        }
+    }
+
+    #[test]
+    fn from_json_should_say_what_is_wrong_with_a_level(){
+        let json = Level::default().to_json();
+
+        let unknown = json.replacen("{", "{\"goal_middle\":5.0,", 1);
+        assert!(Level::from_json(&unknown).unwrap_err().contains("unknown field `goal_middle`"));
+        let missing = json.replacen("\"goal_end\":10000.0,", "", 1);
+        assert!(Level::from_json(&missing).unwrap_err().contains("missing field `goal_end`"));
+        let empty = json.replacen("\"goal_end\":10000.0", "\"goal_end\":1.0", 1);
+        assert_eq!(Err(String::from("invalid level: the goal zone ends before it starts")), Level::from_json(&empty));
+
+        let level = Level { traffic: vec![TrafficCar::new(3000.0, 5.0), TrafficCar::new(2000.0, 5.0)], ..Level::default() };
+        assert_eq!(Err(String::from("invalid level: traffic is not in road order")), Level::from_json(&level.to_json()));
+    }
+
+    #[test]
+    fn from_json_should_check_nested_values_and_keys(){
+        let json = Level::default().to_json();
+
+        let massless = json.replacen("\"mass\":1.0", "\"mass\":0.0", 1);
+        assert_eq!(Err(String::from("invalid level: vehicle.mass must be above 0, not 0")), Level::from_json(&massless));
+        let pushing_wind = json.replacen("\"gust_period\":2.0", "\"gust_period\":-2.0", 1);
+        assert_eq!(Err(String::from("invalid level: wind.gust_period must be 0 or more, not -2")), Level::from_json(&pushing_wind));
+        let level = Level { vehicle: Vehicle { brake_max_temperature: 300.0, ..Vehicle::default() }, ..Level::default() };
+        assert_eq!(
+            Err(String::from("invalid level: vehicle.brake_max_temperature must be above vehicle.brake_fade_temperature (300), not 300")),
+            Level::from_json(&level.to_json())
+        );
+        let typo = json.replacen("\"brake_force\"", "\"brake_forse\"", 1);
+        assert!(Level::from_json(&typo).unwrap_err().contains("unknown field `brake_forse`"));
+
+        let mut track = Track::new();
+        track.add_slope(200.0, 100.0, 0.1);
+        let level = Level { track, ..Level::default() };
+        assert_eq!(Err(String::from("invalid level: track.slopes[0] ends at 100, before it starts at 200")), Level::from_json(&level.to_json()));
+    }
+
+    #[test]
+    fn every_level_should_pass_the_checks(){
+        for index in 0..Level::count() {
+            assert_eq!(Ok(()), Level::get(index).unwrap().check(), "level {}", index);
+        }
+        for seed in 0..20 {
+            assert_eq!(Ok(()), Level::generate(seed, seed as f64 / 20.0).check(), "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn levels_should_differ(){
+        for index in 1..Level::count() {
+            assert_ne!(Level::get(0), Level::get(index));
+        }
+    }
+}
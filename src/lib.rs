@@ -1,77 +1,1069 @@
+use serde::{Deserialize, Serialize};
 use wasm_bindgen::prelude::*;
 
+mod achievements;
+mod autopilot;
+mod binary;
+mod builder;
+mod camera;
+mod checkpoint;
+mod controller;
+mod damage;
+mod daylight;
+mod difficulty;
+mod endless;
+mod engine;
+mod events;
+mod game;
+mod game_loop;
+mod gearbox;
+mod generator;
+mod goal;
+mod history;
+mod input;
+mod leaderboard;
+mod level;
+mod minimap;
+mod nitrous;
+mod replay;
+mod rng;
+mod rules;
+mod score;
+mod session;
+mod simulation;
+mod split_screen;
+mod stopping;
+mod suspension;
+mod track;
+mod traffic;
+mod trailer;
+mod turbo;
+mod tutorial;
+mod typescript;
+mod units;
+mod validate;
+mod vehicle;
+mod weather;
+mod wind;
 
+pub use achievements::{Achievement, Achievements, NIGHT_DAYLIGHT, PERFECT_STOP_DISTANCE};
+pub use autopilot::autopilot;
+pub use builder::StateBuilder;
+pub use camera::{Camera, CameraTarget, ScreenPoint, ScreenSpan, VerticalPolicy, COLLISION_SHAKE, LANDING_SHAKE, SHAKE_HALF_LIFE};
+pub use checkpoint::Checkpoint;
+pub use controller::{Pid, StopAssist};
+pub use damage::{damaged_performance, CONE_DAMAGE, DAMAGED_PERFORMANCE_LOSS, LANDING_DAMAGE};
+pub use daylight::{daylight, visibility, DAY_VISIBILITY, DEFAULT_TIME_SCALE, HEADLIGHT_VISIBILITY, NIGHT_VISIBILITY};
+pub use difficulty::Difficulty;
+pub use endless::Endless;
+pub use engine::{Engine, Powertrain};
+pub use events::Event;
+pub use game::{Game, GameStatus, RenderInfo};
+pub use game_loop::GameLoop;
+pub use gearbox::{Gearbox, Transmission};
+pub use generator::generate;
+pub use goal::{GoalMotion, GoalZone};
+pub use history::History;
+pub use input::{Input, InputQueue, Pedal};
+pub use leaderboard::{Leaderboard, LeaderboardEntry};
+pub use level::{BoundsError, Level};
+pub use minimap::Minimap;
+pub use nitrous::Nitrous;
+pub use replay::Replay;
+pub use rules::{comfort_score, LoseConditions, LoseReason, OvershootPolicy, WinCondition, COMFORT_SCALE, LANDING_DISCOMFORT};
+pub use score::{score, stars, ScoreBreakdown, COLLISION_PENALTY, OVERSHOOT_PENALTY, PRECISION_POINTS, REVERSING_PENALTY, TIME_POINTS, TIME_SCALE};
+pub use session::{LevelStats, Session, SessionStats};
+pub use simulation::Simulation;
+pub use split_screen::SplitScreen;
+pub use stopping::stopping_distance;
+pub use suspension::Suspension;
+pub use track::{FrictionZone, Obstacle, ObstacleKind, Slope, Surface, Track, GRAVITY};
+pub use traffic::{TrafficCar, DRAFT_DISTANCE, DRAFT_DRAG, TRAFFIC_CAR_LENGTH};
+pub use trailer::Trailer;
+pub use turbo::Turbo;
+pub use tutorial::{hint, Hint, Tutorial, HINT_BRAKING};
+pub use units::{kmh_to_mps, mph_to_mps, mps_to_kmh, mps_to_mph, KMH, MPH};
+pub use vehicle::{Vehicle, VehicleLimits, AMBIENT_TEMPERATURE, CRUISE_GAIN, TRACTION_CONTROL_GRIP};
+pub use weather::{Forecast, Weather};
+pub use wind::Wind;
+
+/// Physics and game state of the car.
+///
+/// Positions, speed and acceleration are `f64` so throttle response can be
+/// tuned smoothly. On the JS side they are still plain numbers, so existing
+/// callers keep working; they just may now see fractional values.
+///
+/// Everything is in SI units: positions in metres, speeds in m/s,
+/// accelerations in m/s², masses in kg and forces in newtons. Use
+/// [`mps_to_kmh`] or [`mps_to_mph`] to show a speedometer.
+///
+/// The JS side only reads the fields; the few worth changing between
+/// ticks go through setters such as [`State::set_goal`] that keep the
+/// state sane.
 #[wasm_bindgen]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct State {
-    pub acceleration: i32,
-    pub speed: i32,
-    pub position: i32,
-    pub position_goal_start: i32,
-    pub position_goal_end: i32,
+    #[wasm_bindgen(readonly)]
+    pub acceleration: f64,
+    #[wasm_bindgen(readonly)]
+    pub speed: f64,
+    #[wasm_bindgen(readonly)]
+    pub position: f64,
+    /// Distance covered backwards, for modes where reversing costs score.
+    #[wasm_bindgen(readonly)]
+    pub reverse_distance: f64,
+    /// Rate of change of acceleration this tick, in m/s³.
+    #[wasm_bindgen(readonly)]
+    pub jerk: f64,
+    /// Total change of acceleration over the run, behind the comfort score.
+    /// Harsh landings add to it too.
+    #[wasm_bindgen(readonly)]
+    pub discomfort: f64,
+    /// Height of the body above its rest height on the suspension, for
+    /// bouncing the car sprite. Always 0 with a rigid suspension.
+    #[wasm_bindgen(readonly)]
+    pub suspension_offset: f64,
+    /// Vertical speed of the body.
+    #[wasm_bindgen(readonly)]
+    pub body_speed: f64,
+    /// Pedal positions actually applied last tick.
+    #[wasm_bindgen(readonly)]
+    pub pedals: Input,
+    /// Fastest a pedal may move, in full travels per second. `f64::MAX`
+    /// lets pedals jump, a low value is chauffeur mode.
+    #[wasm_bindgen(readonly)]
+    pub pedal_rate: f64,
+    /// Simulated seconds since the run started.
+    #[wasm_bindgen(readonly)]
+    pub elapsed_time: f64,
+    /// Updates run since the run started.
+    #[wasm_bindgen(readonly)]
+    pub ticks: u32,
+    /// Hour of the day, from 0 up to 24, for lighting the scene.
+    #[wasm_bindgen(readonly)]
+    pub time_of_day: f64,
+    /// In-game hours going by per simulated second. 0 stops the clock.
+    #[wasm_bindgen(readonly)]
+    pub time_scale: f64,
+    #[wasm_bindgen(readonly)]
+    pub headlights: bool,
+    /// Engine speed, for the tachometer. Always 0 without an engine.
+    #[wasm_bindgen(readonly)]
+    pub rpm: f64,
+    /// Engaged gear, 0 being neutral and -1 reverse.
+    #[wasm_bindgen(readonly)]
+    pub gear: i32,
+    /// Set when the engine stalled; it gives no power until restarted.
+    #[wasm_bindgen(readonly)]
+    pub stalled: bool,
+    /// Set while braking beyond tyre grip, for drawing skid marks.
+    #[wasm_bindgen(readonly)]
+    pub wheels_locked: bool,
+    /// Set while the engine pulls harder than the tyres grip.
+    #[wasm_bindgen(readonly)]
+    pub wheels_spinning: bool,
+    /// Brake temperature in degrees Celsius; hot brakes fade.
+    #[wasm_bindgen(readonly)]
+    pub brake_temperature: f64,
+    /// Turbo boost from 0 to 1, for the boost gauge. Always 0 without a
+    /// turbo.
+    #[wasm_bindgen(readonly)]
+    pub boost: f64,
+    /// Nitrous bottles in the inventory and the one burning, if any.
+    #[wasm_bindgen(readonly)]
+    pub nitrous: Nitrous,
+    /// Set while launch control holds the engine at peak torque for a
+    /// standing start. It lets go once the clutch catches up.
+    #[wasm_bindgen(readonly)]
+    pub launch_control: bool,
+    /// Set while cruise control holds `cruise_speed`.
+    #[wasm_bindgen(readonly)]
+    pub cruise_active: bool,
+    #[wasm_bindgen(readonly)]
+    pub cruise_speed: f64,
+    /// Speed the level's limiter caps the car at, whatever the throttle.
+    /// `f64::MAX` when the level has no limiter.
+    #[wasm_bindgen(readonly)]
+    pub speed_limit: f64,
+    /// Set while the limiter cuts throttle.
+    #[wasm_bindgen(readonly)]
+    pub limiter_active: bool,
+    /// Cones knocked over so far, each one a penalty.
+    #[wasm_bindgen(readonly)]
+    pub obstacles_hit: u32,
+    /// Damage from cones and harsh landings, from 0 (intact) to 1
+    /// (wrecked). It saps the brakes and the drive.
+    #[wasm_bindgen(readonly)]
+    pub damage: f64,
+    /// Fuel left in the tank. Only drained when the vehicle uses fuel.
+    #[wasm_bindgen(readonly)]
+    pub fuel: f64,
+    /// Charge left in the battery of electric vehicles.
+    #[wasm_bindgen(readonly)]
+    pub battery: f64,
+    /// Set while the motor alone drives the car, for the hybrid EV light.
+    #[wasm_bindgen(readonly)]
+    pub electric: bool,
+    /// Fuel burnt over the run, for scoring efficiency.
+    #[wasm_bindgen(readonly)]
+    pub fuel_used: f64,
+    /// Battery charge drawn over the run.
+    #[wasm_bindgen(readonly)]
+    pub charge_used: f64,
+    /// Battery charge won back braking over the run.
+    #[wasm_bindgen(readonly)]
+    pub charge_recovered: f64,
+    #[wasm_bindgen(readonly)]
+    pub position_goal_start: f64,
+    #[wasm_bindgen(readonly)]
+    pub position_goal_end: f64,
+    /// Fastest the car may go inside the goal zone; faster counts as a
+    /// crash. `f64::MAX` when the level doesn't care.
+    #[wasm_bindgen(readonly)]
+    pub max_entry_speed: f64,
+    /// Run time, in seconds, a time trial must be won within. `f64::MAX`
+    /// outside time trials.
+    #[wasm_bindgen(readonly)]
+    pub target_time: f64,
+    /// What stopping in the goal zone has to achieve to win.
+    #[wasm_bindgen(readonly)]
+    pub win_condition: WinCondition,
+    /// Fuel, or charge, [`WinCondition::StopWithFuelRemaining`] needs left.
+    #[wasm_bindgen(readonly)]
+    pub energy_reserve: f64,
+    /// Distance from the middle of the goal zone
+    /// [`WinCondition::ExactPosition`] accepts.
+    #[wasm_bindgen(readonly)]
+    pub position_tolerance: f64,
+    #[wasm_bindgen(readonly)]
+    pub lose_conditions: LoseConditions,
+    #[wasm_bindgen(readonly)]
+    pub overshoot_policy: OvershootPolicy,
+    /// Ticks the car may stay past the goal zone under
+    /// [`OvershootPolicy::GraceWindow`].
+    #[wasm_bindgen(readonly)]
+    pub overshoot_grace_ticks: u32,
+    /// Ticks the car has been past the goal zone in a row.
+    #[wasm_bindgen(readonly)]
+    pub overshoot_ticks: u32,
+    /// Times the car went past the goal zone.
+    #[wasm_bindgen(readonly)]
+    pub overshoots: u32,
+    #[wasm_bindgen(readonly)]
+    pub endless: Endless,
+    /// Checkpoints to cross before the goal zone counts, in road order.
+    #[wasm_bindgen(skip)]
+    pub checkpoints: Vec<Checkpoint>,
+    /// Checkpoints crossed so far.
+    #[wasm_bindgen(readonly)]
+    pub checkpoints_passed: u32,
+    /// Goal zones worth less than the main one, which the car may stop in
+    /// instead, short of the main zone.
+    #[wasm_bindgen(skip)]
+    pub extra_goals: Vec<GoalZone>,
+    /// How the main goal zone moves over the run.
+    #[wasm_bindgen(readonly)]
+    pub goal_motion: GoalMotion,
+    #[wasm_bindgen(readonly)]
     pub won: bool,
-    pub lost: bool
-}
-
-#[wasm_bindgen]
-pub struct Camera {
-    pub screen_size: i32,
-    pub world_size: i32,
-    pub world_position: i32
+    #[wasm_bindgen(readonly)]
+    pub lost: bool,
+    #[wasm_bindgen(readonly)]
+    pub lose_reason: LoseReason,
+    #[wasm_bindgen(getter_with_clone, readonly)]
+    pub vehicle: Vehicle,
+    /// Trailer hitched behind the car. When towing, it is the trailer that
+    /// has to stop in the goal zone.
+    #[wasm_bindgen(readonly)]
+    pub trailer: Trailer,
+    #[wasm_bindgen(skip)]
+    pub track: Track,
+    /// Other cars on the road, from rearmost to frontmost.
+    #[wasm_bindgen(skip)]
+    pub traffic: Vec<TrafficCar>,
+    /// Set while the car sits in the slipstream of a traffic car, with
+    /// less air drag.
+    #[wasm_bindgen(readonly)]
+    pub drafting: bool,
+    #[wasm_bindgen(readonly)]
+    pub wind: Wind,
+    #[wasm_bindgen(readonly)]
+    pub forecast: Forecast,
+    /// Wind force acting on the car this tick, for the wind sock.
+    #[wasm_bindgen(readonly)]
+    pub wind_force: f64,
+    #[wasm_bindgen(skip)]
+    pub events: Vec<Event>,
+    /// Level the run started from, for restarting it.
+    #[wasm_bindgen(skip)]
+    pub level: Level,
+    #[wasm_bindgen(readonly)]
+    pub session: Session,
+    /// Run seed the randomness of the run derives from.
+    #[wasm_bindgen(readonly)]
+    pub seed: u64
 }
 
 #[wasm_bindgen]
-impl Camera {
+impl State {
+    /// State at the start of the first level.
     #[wasm_bindgen(constructor)]
-    pub fn new(screen_size: i32, world_size: i32) -> Camera {
-        Camera {
-            screen_size,
-            world_size,
-            world_position: 0
-        }
+    pub fn new() -> State {
+        State::from_level(&Level::default())
     }
-    pub fn project(&self, world_position: i32) -> i32{
-        self.screen_size * (self.world_position + (self.world_size / 2) - world_position)  / self.world_size
+
+    /// State of the first level with the car starting at `start` and the
+    /// goal zone between `goal_start` and `goal_end`. The goal zone must be
+    /// ahead of the start.
+    pub fn with_params(start: f64, goal_start: f64, goal_end: f64) -> Result<State, BoundsError> {
+        let level = Level {
+            start_position: start,
+            goal_start,
+            goal_end,
+            ..Level::default()
+        };
+        level.validate()?;
+        Ok(State::from_level(&level))
     }
-}
 
-#[wasm_bindgen]
-impl State {
-    #[wasm_bindgen(constructor)]
-    pub fn new() -> State {
+    /// State at the start of `level`, with full tank and battery.
+    pub fn from_level(level: &Level) -> State {
         State {
-            position_goal_start: 9000,
-            position_goal_end: 10000,
-            position: 500,
+            position: level.start_position,
+            position_goal_start: level.goal_start,
+            position_goal_end: level.goal_end,
+            fuel: level.vehicle.tank_capacity,
+            battery: level.vehicle.battery_capacity,
+            vehicle: level.vehicle.clone(),
+            track: level.track.clone(),
+            wind: level.wind,
+            forecast: level.forecast,
+            speed_limit: level.speed_limit,
+            max_entry_speed: level.max_entry_speed,
+            target_time: level.target_time,
+            win_condition: level.win_condition,
+            energy_reserve: level.energy_reserve,
+            position_tolerance: level.position_tolerance,
+            lose_conditions: level.lose_conditions,
+            overshoot_policy: level.overshoot_policy,
+            overshoot_grace_ticks: level.overshoot_grace_ticks,
+            endless: level.endless,
+            checkpoints: level.checkpoints.clone(),
+            extra_goals: level.extra_goals.clone(),
+            goal_motion: level.goal_motion,
+            nitrous: Nitrous { charges: level.nitrous_charges, ..Nitrous::default() },
+            time_of_day: level.time_of_day,
+            time_scale: level.time_scale,
+            traffic: level.traffic.clone(),
+            trailer: level.trailer,
+            level: level.clone(),
+            session: Session { attempts: 1, ..Session::default() },
+            seed: level.seed,
             ..State::default()
         }
     }
+
+    /// State at the start of `level` played at `difficulty`.
+    pub fn from_level_at(level: &Level, difficulty: Difficulty) -> State {
+        State::from_level(&difficulty.apply(level))
+    }
+
+    /// Puts the car at `position`.
+    pub fn set_position(&mut self, position: f64) -> Result<(), BoundsError> {
+        if !position.is_finite() {
+            return Err(BoundsError::NotFinite);
+        }
+        self.position = position;
+        Ok(())
+    }
+
+    /// Sets the speed, which must be within what the car can do forwards
+    /// and backwards.
+    pub fn set_speed(&mut self, speed: f64) -> Result<(), BoundsError> {
+        if !speed.is_finite() {
+            return Err(BoundsError::NotFinite);
+        }
+        let limits = &self.vehicle.limits;
+        if speed < -limits.max_reverse_speed || speed > limits.max_speed {
+            return Err(BoundsError::OutOfRange);
+        }
+        self.speed = speed;
+        Ok(())
+    }
+
+    /// Sets how fast the pedals may move, in full travels per second:
+    /// `f64::MAX` lets them jump, a low value is chauffeur mode.
+    pub fn set_pedal_rate(&mut self, pedal_rate: f64) -> Result<(), BoundsError> {
+        self.pedal_rate = non_negative_bound(pedal_rate)?;
+        Ok(())
+    }
+
+    /// Sets the in-game hours going by per simulated second, 0 stopping
+    /// the clock.
+    pub fn set_time_scale(&mut self, time_scale: f64) -> Result<(), BoundsError> {
+        self.time_scale = non_negative_bound(time_scale)?;
+        Ok(())
+    }
+
+    /// Moves the goal zone to between `goal_start` and `goal_end`, which
+    /// must be a real stretch of road.
+    pub fn set_goal(&mut self, goal_start: f64, goal_end: f64) -> Result<(), BoundsError> {
+        if !goal_start.is_finite() || !goal_end.is_finite() {
+            return Err(BoundsError::NotFinite);
+        }
+        if goal_end <= goal_start {
+            return Err(BoundsError::EmptyGoal);
+        }
+        self.position_goal_start = goal_start;
+        self.position_goal_end = goal_end;
+        Ok(())
+    }
+
+    /// Independent copy of the state, say from just before braking, to
+    /// compare the run against later or to go back to.
+    pub fn snapshot(&self) -> State {
+        self.clone()
+    }
+
+    /// Runs one tick of `dt` seconds with `input` on the state in place,
+    /// like [`update`] does, so a JS handle to the state stays valid from
+    /// frame to frame.
+    pub fn step(&mut self, input: Input, dt: f64) {
+        let current_state = std::mem::take(self);
+        *self = update(current_state, input, dt);
+    }
+
+    /// The whole state as JSON, for saving progress to `localStorage`.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("state should serialize to JSON")
+    }
+
+    /// State saved with [`State::to_json`], exactly as it was, or why
+    /// `json` isn't one.
+    pub fn from_json(json: &str) -> Result<State, String> {
+        serde_json::from_str(json).map_err(|error| error.to_string())
+    }
+
+    /// The whole state as a plain JS object with the same fields as
+    /// [`State::to_json`], for framework state that shouldn't hold on to
+    /// a wasm handle. Seeds are `BigInt`s so they keep all 64 bits.
+    #[wasm_bindgen(unchecked_return_type = "StateData")]
+    pub fn to_js(&self) -> Result<JsValue, JsValue> {
+        let serializer = serde_wasm_bindgen::Serializer::new().serialize_large_number_types_as_bigints(true);
+        self.serialize(&serializer).map_err(JsValue::from)
+    }
+
+    /// State given as a plain JS object by [`State::to_js`], exactly as
+    /// it was, or why `value` isn't one.
+    pub fn from_js(#[wasm_bindgen(unchecked_param_type = "StateData")] value: &JsValue) -> Result<State, String> {
+        serde_wasm_bindgen::from_value(value.clone()).map_err(|error| error.to_string())
+    }
+
+    /// The whole state in the compact binary encoding, a `Uint8Array` on
+    /// the JS side, for storage and network transfer.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        binary::to_bytes(self).expect("state should encode to bytes")
+    }
+
+    /// State encoded with [`State::to_bytes`], or why `bytes` aren't one.
+    pub fn from_bytes(bytes: &[u8]) -> Result<State, String> {
+        binary::from_bytes(bytes).map_err(|error| error.to_string())
+    }
+
+    /// Reseeds the wind, weather and endless zones of the run, and of its
+    /// restarts, from the one run `seed`.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.level.set_seed(seed);
+        self.seed = seed;
+        self.wind.seed = self.level.wind.seed;
+        self.forecast.seed = self.level.forecast.seed;
+        self.endless.seed = self.level.endless.seed;
+    }
+
+    /// Puts the car back at the start of its level, as it was when the
+    /// level was loaded, and counts the run that ends in the session
+    /// statistics.
+    pub fn restart_level(&mut self) {
+        let session = self.session.next_attempt(self.won, self.lost);
+        *self = State {
+            session,
+            ..State::from_level(&self.level)
+        };
+    }
+
+    /// Level the run started from.
+    #[wasm_bindgen(getter)]
+    pub fn level(&self) -> Level {
+        self.level.clone()
+    }
+
+    /// Engages the next gear up. Returns false when already in top gear.
+    pub fn shift_up(&mut self) -> bool {
+        if self.gear >= self.vehicle.gearbox.gear_count() {
+            return false;
+        }
+        self.gear += 1;
+        true
+    }
+
+    /// Engages the next gear down. Returns false when already in reverse,
+    /// when the lower gear would over-rev the engine past its redline, or
+    /// when going into reverse while the car can't reverse or still moves.
+    pub fn shift_down(&mut self) -> bool {
+        if self.gear < 0 {
+            return false;
+        }
+        if self.gear == 0 {
+            if !self.vehicle.limits.can_reverse() || self.speed != 0.0 {
+                return false;
+            }
+            self.gear = -1;
+            return true;
+        }
+        let rpm = self.vehicle.rpm(self.speed, self.gear - 1);
+        if self.vehicle.has_engine() && rpm > self.vehicle.engine.redline_rpm {
+            return false;
+        }
+        self.gear -= 1;
+        true
+    }
+
+    /// Arms launch control: while the brake is held the engine is kept at
+    /// peak torque, then releasing it launches at full throttle, as hard as
+    /// the tyres grip. Refused unless a car with an engine stands still in
+    /// first gear.
+    pub fn arm_launch_control(&mut self) -> bool {
+        if !self.vehicle.has_engine() || self.speed != 0.0 || self.gear != 1 {
+            return false;
+        }
+        self.launch_control = true;
+        true
+    }
+
+    /// Fires a nitrous bottle. Returns false when none is left or one is
+    /// still burning.
+    pub fn fire_nitrous(&mut self) -> bool {
+        self.nitrous.fire()
+    }
+
+    /// Disengages the gears so the car coasts without engine braking.
+    /// `shift_up` puts it back in first.
+    pub fn set_neutral(&mut self) {
+        self.gear = 0;
+    }
+
+    pub fn restart_engine(&mut self) {
+        self.stalled = false;
+    }
+
+    pub fn toggle_headlights(&mut self) {
+        self.headlights = !self.headlights;
+    }
+
+    /// How far ahead the driver sees, for fogging out distant goal
+    /// markers. Shorter at night unless the headlights are on.
+    pub fn visibility(&self) -> f64 {
+        visibility(self.time_of_day, self.headlights)
+    }
+
+    pub fn replace_tires(&mut self) {
+        self.vehicle.replace_tires();
+    }
+
+    /// Holds `target_speed` without the player touching the throttle. The
+    /// player can still throttle above it; braking cancels it. Returns
+    /// false, leaving cruise control as it was, when `target_speed` isn't a
+    /// finite speed of 0 or more.
+    pub fn set_cruise(&mut self, target_speed: f64) -> bool {
+        if !(target_speed.is_finite() && target_speed >= 0.0) {
+            return false;
+        }
+        self.cruise_active = true;
+        self.cruise_speed = target_speed;
+        true
+    }
+
+    pub fn cancel_cruise(&mut self) {
+        self.cruise_active = false;
+    }
+
+    /// Events fired during the tick that produced this state.
+    #[wasm_bindgen(getter)]
+    pub fn events(&self) -> Vec<Event> {
+        self.events.clone()
+    }
+
+    /// Comfort score of the run so far, out of 100. Slamming the pedals
+    /// lowers it.
+    pub fn comfort_score(&self) -> f64 {
+        comfort_score(self.discomfort)
+    }
+
+    /// Score of the run for the results screen, once it is won.
+    pub fn score(&self) -> Option<ScoreBreakdown> {
+        if self.won { Some(score(self)) } else { None }
+    }
+
+    /// Seconds left to win a time trial, 0 once the time is up.
+    pub fn remaining_time(&self) -> f64 {
+        (self.target_time - self.elapsed_time).max(0.0)
+    }
+
+    /// Stars, from 1 to 3, the run earns on its level once it is won, 0
+    /// before.
+    pub fn stars(&self) -> u32 {
+        match self.score() {
+            Some(breakdown) => stars(breakdown.total(), self.level.two_star_score, self.level.three_star_score),
+            None => 0
+        }
+    }
+
+    /// Current weather, for rendering rain or snow.
+    #[wasm_bindgen(getter)]
+    pub fn weather(&self) -> Weather {
+        self.forecast.weather_at(self.elapsed_time)
+    }
+
+    /// Friction of the road under the car, in the current weather.
+    pub fn friction(&self) -> f64 {
+        self.track.friction_at(self.position) * self.weather().friction()
+    }
+
+    /// Damage as a percentage, for the HUD.
+    pub fn damage_percent(&self) -> f64 {
+        self.damage * 100.0
+    }
+
+    /// Position of the trailer, or of the car when it tows none.
+    pub fn trailer_position(&self) -> f64 {
+        self.position - self.trailer.length
+    }
+
+    /// Height of the car body, the road height plus the suspension
+    /// offset, for drawing the car over hills.
+    pub fn body_height(&self) -> f64 {
+        self.track.height_at(self.position) + self.suspension_offset
+    }
+
+    /// Position where the car would stand still braking fully from now
+    /// on, for the HUD marker.
+    pub fn stopping_point(&self) -> f64 {
+        let friction = self.friction();
+        // The braking already accounts for the road's friction.
+        let (braking, _) = self.vehicle.braking(1.0, friction, self.speed, self.brake_temperature);
+        let braking = braking * self.trailer.load_share(self.vehicle.mass) * damaged_performance(self.damage);
+        let distance = stopping_distance(self.speed, braking, 1.0, self.track.gradient_at(self.position));
+        self.position + distance * self.speed.signum()
+    }
+
+    /// Puts a car driving at `cruise_speed` on the road at `position`.
+    pub fn add_traffic(&mut self, position: f64, cruise_speed: f64) {
+        let index = self.traffic.partition_point(|car| car.position < position);
+        self.traffic.insert(index, TrafficCar::new(position, cruise_speed));
+    }
+
+    /// Every goal zone of the run, the main one first, for rendering.
+    #[wasm_bindgen(getter)]
+    pub fn goal_zones(&self) -> Vec<GoalZone> {
+        let main = GoalZone::new(self.position_goal_start, self.position_goal_end, 1.0);
+        std::iter::once(main).chain(self.extra_goals.iter().copied()).collect()
+    }
+
+    /// Checkpoints of the run, for drawing their lines.
+    #[wasm_bindgen(getter)]
+    pub fn checkpoints(&self) -> Vec<Checkpoint> {
+        self.checkpoints.clone()
+    }
+
+    /// Traffic cars, for rendering through the camera.
+    #[wasm_bindgen(getter)]
+    pub fn traffic(&self) -> Vec<TrafficCar> {
+        self.traffic.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn track(&self) -> Track {
+        self.track.clone()
+    }
+
+    /// Swaps in `track`, unless one of its stretches of road is empty or
+    /// holds a value that makes no sense; it then throws on the JS side.
+    #[wasm_bindgen(setter)]
+    pub fn set_track(&mut self, track: Track) -> Result<(), String> {
+        track.check()?;
+        self.track = track;
+        Ok(())
+    }
+}
+
+/// `value` when it is a number of at least 0, for the setters.
+fn non_negative_bound(value: f64) -> Result<f64, BoundsError> {
+    if !value.is_finite() {
+        Err(BoundsError::NotFinite)
+    } else if value < 0.0 {
+        Err(BoundsError::OutOfRange)
+    } else {
+        Ok(value)
+    }
 }
 
 impl Default for State {
     fn default() -> State {
         State {
-            acceleration: 0,
-            speed: 0,
-            position: 0,
-            position_goal_start: 0,
-            position_goal_end: 0,
+            acceleration: 0.0,
+            speed: 0.0,
+            position: 0.0,
+            reverse_distance: 0.0,
+            jerk: 0.0,
+            discomfort: 0.0,
+            suspension_offset: 0.0,
+            body_speed: 0.0,
+            pedals: Input::default(),
+            pedal_rate: f64::MAX,
+            elapsed_time: 0.0,
+            ticks: 0,
+            time_of_day: 12.0,
+            time_scale: DEFAULT_TIME_SCALE,
+            headlights: false,
+            rpm: 0.0,
+            gear: 1,
+            stalled: false,
+            wheels_locked: false,
+            wheels_spinning: false,
+            brake_temperature: AMBIENT_TEMPERATURE,
+            boost: 0.0,
+            nitrous: Nitrous::default(),
+            launch_control: false,
+            cruise_active: false,
+            cruise_speed: 0.0,
+            speed_limit: f64::MAX,
+            limiter_active: false,
+            obstacles_hit: 0,
+            damage: 0.0,
+            fuel: Vehicle::default().tank_capacity,
+            battery: Vehicle::default().battery_capacity,
+            electric: false,
+            fuel_used: 0.0,
+            charge_used: 0.0,
+            charge_recovered: 0.0,
+            position_goal_start: 0.0,
+            position_goal_end: 0.0,
+            max_entry_speed: f64::MAX,
+            target_time: f64::MAX,
+            win_condition: WinCondition::StopInZone,
+            energy_reserve: 0.0,
+            position_tolerance: 1.0,
+            lose_conditions: LoseConditions::default(),
+            overshoot_policy: OvershootPolicy::InstantLoss,
+            overshoot_grace_ticks: 0,
+            overshoot_ticks: 0,
+            overshoots: 0,
+            endless: Endless::default(),
+            checkpoints: Vec::new(),
+            checkpoints_passed: 0,
+            extra_goals: Vec::new(),
+            goal_motion: GoalMotion::default(),
+            won: false,
             lost: false,
-            won: false
+            lose_reason: LoseReason::None,
+            vehicle: Vehicle::default(),
+            trailer: Trailer::default(),
+            track: Track::default(),
+            traffic: Vec::new(),
+            drafting: false,
+            wind: Wind::default(),
+            forecast: Forecast::default(),
+            wind_force: 0.0,
+            events: Vec::new(),
+            level: Level::default(),
+            session: Session::default(),
+            seed: 0
         }
     }
 }
 
+/// Advances the simulation by `dt` seconds of real elapsed time.
+///
+/// Speed and position are integrated against `dt`, so calling this once per
+/// animation frame gives much the same gameplay whatever the display refresh
+/// rate: exactly the same at a steady speed, within centimetres while
+/// accelerating. Runs that must match exactly step a fixed `dt`, as
+/// [`Simulation`] does.
+///
+/// It must stay deterministic for replays to work: the next state depends on
+/// nothing but `current_state`, `input` and `dt`, with no clock, global or
+/// unseeded randomness, and any randomness goes through the seeded
+/// generator in `rng`.
+///
+/// A `dt` that isn't a positive number leaves the state unchanged.
 #[wasm_bindgen]
-pub fn update(current_state: State, throttle: i32) -> State{
+pub fn update(current_state: State, input: Input, dt: f64) -> State{
+    if !dt.is_finite() || dt <= 0.0 {
+        return current_state;
+    }
+    let input = current_state.pedals.towards(input, current_state.pedal_rate * dt);
+    let vehicle = &current_state.vehicle;
+    let limits = vehicle.limits;
+    let gear = vehicle.select_gear(current_state.speed, current_state.gear);
+    let rpm = vehicle.rpm(current_state.speed, gear);
+    let rpm = vehicle.clutch_rpm(rpm, input.clutch, input.throttle, gear);
+    let engagement = vehicle.clutch_engagement(input.clutch);
+    let launch_control = current_state.launch_control && gear == 1 && rpm < vehicle.engine.peak_torque_rpm;
+    let rpm = if launch_control { vehicle.engine.peak_torque_rpm } else { rpm };
+    let electric = vehicle.electric_mode(input.throttle, current_state.speed, current_state.battery, current_state.fuel);
+    let stalled = current_state.stalled || (!electric && vehicle.stalls(rpm, gear));
+    let out_of_fuel = vehicle.uses_fuel() && current_state.fuel <= 0.0 && !electric;
+    let out_of_charge = electric && vehicle.uses_battery() && current_state.battery <= 0.0;
+    let weather = current_state.weather();
+    let friction = current_state.friction();
+    let wind_force = current_state.wind.force_at(current_state.elapsed_time);
+    // Brakes and resistance work against the direction of travel, or the
+    // direction the gear pulls in when stopped.
+    let direction = if current_state.speed < 0.0 || (current_state.speed == 0.0 && gear < 0) { -1.0 } else { 1.0 };
+    let drafting = traffic::drafting(&current_state.traffic, current_state.position);
+    let drag_factor = weather.drag() * if drafting { DRAFT_DRAG } else { 1.0 };
+    let resistance = direction * vehicle.resistance_deceleration(current_state.speed, friction, drag_factor)
+        + current_state.track.gravity_deceleration(current_state.position)
+        - wind_force / vehicle.mass;
+
+    let cruise_active = current_state.cruise_active && input.brake <= 0.0;
+    let throttle = if cruise_active {
+        let cruise_throttle = vehicle.cruise_throttle(current_state.cruise_speed, current_state.speed, resistance, rpm, gear);
+        input.throttle.max(cruise_throttle)
+    } else {
+        input.throttle
+    };
+    let throttle = if out_of_charge { 0.0 } else { throttle };
+    // Launch control holds the revs on the brake and floors it on release.
+    let throttle = match (launch_control, input.brake > 0.0) {
+        (true, true) => 0.0,
+        (true, false) => 1.0,
+        (false, _) => throttle
+    };
+    let (rpm, drive) = if stalled || out_of_fuel || out_of_charge {
+        (0.0, 0.0)
+    } else if electric {
+        (0.0, vehicle.motor_acceleration(throttle, gear))
+    } else {
+        (rpm, vehicle.drive_acceleration(throttle, rpm, gear) * engagement * vehicle.turbo.gain(current_state.boost))
+    };
+    let boost = if vehicle.uses_turbo() && rpm > 0.0 {
+        vehicle.turbo.boost(current_state.boost, throttle, dt)
+    } else {
+        0.0
+    };
+    let fuel = (current_state.fuel - vehicle.fuel_rate(throttle, rpm) * dt).max(0.0);
+    let motor_throttle = if electric { throttle } else { 0.0 };
+    let battery = (current_state.battery + vehicle.battery_rate(motor_throttle, input.brake, current_state.speed) * dt)
+        .clamp(0.0, vehicle.battery_capacity.max(0.0));
+    // Only the car drives and brakes, but it has to move the trailer too,
+    // and damage saps both.
+    let effort = current_state.trailer.load_share(vehicle.mass) * damaged_performance(current_state.damage);
+    let drive = if launch_control { vehicle.launch_drive(drive, friction) } else { drive };
+    let drive = drive + current_state.nitrous.boost();
+    let (drive, wheels_spinning) = vehicle.traction(drive, friction);
+    let (braking, wheels_locked) = vehicle.braking(input.brake, friction, current_state.speed, current_state.brake_temperature);
+    let brake_temperature = vehicle.brake_temperature(current_state.brake_temperature, braking, current_state.speed, dt);
+    // Engine braking holds the car back through the gears without heating
+    // the brakes.
+    let braking = braking + vehicle.engine_braking(throttle, current_state.speed, rpm, gear) * engagement;
+
+    // The limiter only cuts drive: it never brakes, so the car can still run
+    // past the limit downhill or with a tailwind.
+    // Speed stops at 0 rather than crossing it: brakes and resistance never
+    // push the car, and only the reverse gear pulls a stopped car backwards.
+    let speed = current_state.speed + current_state.acceleration * dt;
+    let speed = limits.clamp_speed(if speed * direction < 0.0 { 0.0 } else { speed });
+    let drive = drive * effort;
+    let braking = braking * direction * effort;
+    let limiter_drive = ((current_state.speed_limit - speed) / dt + braking + resistance).max(0.0);
+    let limiter_active = drive > limiter_drive;
+    let drive = drive.min(limiter_drive);
+    let acceleration = drive - braking - resistance;
+    let trailer = current_state.trailer;
+    let jackknifed = trailer.jackknifes(trailer.push_force(drive, braking));
+
+    let mut events = Vec::new();
+    if limiter_active && !current_state.limiter_active {
+        events.push(Event::LimiterHit);
+    }
+
+    let position = current_state.position + current_state.speed * dt;
+    let mut obstacles_hit = current_state.obstacles_hit;
+    let mut damage = current_state.damage;
+    let mut crashed = false;
+    for obstacle in current_state.track.obstacles.iter().filter(|obstacle| obstacle.hit_between(current_state.position, position)) {
+        events.push(Event::Collision);
+        match obstacle.kind {
+            ObstacleKind::Cone => {
+                obstacles_hit += 1;
+                damage += CONE_DAMAGE;
+            }
+            ObstacleKind::ParkedCar => crashed = true
+        }
+    }
+
+    let traffic = traffic::step_traffic(&current_state.traffic, dt);
+    let rear_ended = current_state.traffic.iter().zip(&traffic).any(|(before, after)| {
+        current_state.position < before.position && position >= after.position
+    });
+    if rear_ended {
+        events.push(Event::Collision);
+        crashed = true;
+    }
+
+    let road_speed = current_state.track.gradient_at(current_state.position) * current_state.speed;
+    let (suspension_offset, body_speed, impact) = vehicle.suspension.step(
+        current_state.suspension_offset,
+        current_state.body_speed,
+        road_speed,
+        dt
+    );
+    if impact > 0.0 {
+        events.push(Event::HarshLanding);
+    }
+    let damage = (damage + impact * LANDING_DAMAGE).min(1.0);
+
+    let checkpoints_passed = checkpoint::passed_checkpoints(
+        &current_state.checkpoints,
+        current_state.checkpoints_passed,
+        current_state.position,
+        position
+    );
+    if checkpoints_passed > current_state.checkpoints_passed {
+        events.push(Event::CheckpointPassed);
+    }
+    let all_checkpoints_passed = checkpoints_passed as usize == current_state.checkpoints.len();
+    let split_missed = current_state.checkpoints
+        .get(checkpoints_passed as usize)
+        .is_some_and(|checkpoint| current_state.elapsed_time + dt > checkpoint.split_time);
+
+    let goal_position = current_state.trailer_position();
+    let in_goal = goal::zone_at(&current_state.goal_zones(), goal_position).is_some();
+    let in_time = current_state.elapsed_time <= current_state.target_time;
+    let stopped_in_goal = current_state.speed == 0.0
+        && in_goal
+        && all_checkpoints_passed
+        && current_state.win_condition.is_met(&current_state);
+    let new_goal_position = position - current_state.trailer.length;
+    if goal_position <= current_state.position_goal_start && new_goal_position > current_state.position_goal_start {
+        events.push(Event::GoalEntered);
+    }
+    let mut overshoots = current_state.overshoots;
+    if goal_position < current_state.position_goal_end && new_goal_position >= current_state.position_goal_end {
+        events.push(Event::GoalOvershot);
+        overshoots += 1;
+    }
+    let overshot = goal_position > current_state.position_goal_end;
+    let overshoot_ticks = if overshot { current_state.overshoot_ticks + 1 } else { 0 };
+    let overshoot_lost = match current_state.overshoot_policy {
+        OvershootPolicy::InstantLoss => overshot,
+        OvershootPolicy::Penalty => false,
+        OvershootPolicy::GraceWindow => overshoot_ticks > current_state.overshoot_grace_ticks
+    };
+    let lose_conditions = current_state.lose_conditions;
+    let lose_reason = if crashed {
+        LoseReason::Collision
+    } else if lose_conditions.wrecked && damage >= 1.0 {
+        LoseReason::Wrecked
+    } else if jackknifed {
+        LoseReason::Jackknife
+    } else if in_goal && current_state.speed.abs() > current_state.max_entry_speed {
+        LoseReason::TooFast
+    } else if lose_conditions.overshoot && overshoot_lost {
+        LoseReason::Overshoot
+    } else if lose_conditions.time_out && !in_time {
+        LoseReason::TimeOut
+    } else if split_missed {
+        LoseReason::SplitMissed
+    } else if lose_conditions.behind_start && position < current_state.level.start_position {
+        LoseReason::BehindStart
+    } else if lose_conditions.fuel_empty && current_state.speed == 0.0 && goal_position <= current_state.position_goal_start {
+        if out_of_fuel {
+            LoseReason::OutOfFuel
+        } else if out_of_charge {
+            LoseReason::OutOfCharge
+        } else {
+            LoseReason::None
+        }
+    } else {
+        LoseReason::None
+    };
+
+    let goal_reached = stopped_in_goal && lose_reason == LoseReason::None;
+    // In endless mode a stop only clears the zone and the next one spawns.
+    let won = goal_reached && !current_state.endless.enabled;
+    let (position_goal_start, position_goal_end, endless) = if goal_reached && current_state.endless.enabled {
+        events.push(Event::GoalCleared);
+        let (start, end) = current_state.endless.next_goal(current_state.position_goal_start, current_state.position_goal_end);
+        (start, end, Endless { streak: current_state.endless.streak + 1, ..current_state.endless })
+    } else {
+        (current_state.position_goal_start, current_state.position_goal_end, current_state.endless)
+    };
+    let goal_motion = current_state.goal_motion;
+    let goal_shift = goal_motion.offset(current_state.elapsed_time + dt) - goal_motion.offset(current_state.elapsed_time);
+
+    if won && !current_state.won {
+        events.push(Event::Won);
+    }
+    if lose_reason != LoseReason::None && !current_state.lost {
+        events.push(Event::Lost);
+    }
+
+    let acceleration = limits.clamp_acceleration(acceleration);
+    let jerk = (acceleration - current_state.acceleration) / dt;
+
+    let mut vehicle = current_state.vehicle.clone();
+    vehicle.tire_wear = (vehicle.tire_wear
+        + vehicle.tire_wear_per_second(current_state.speed, braking.abs(), wheels_locked) * dt)
+        .min(1.0);
     State {
-        acceleration: throttle,
-        speed: (current_state.speed + current_state.acceleration).clamp(0, i32::MAX),
-        position: current_state.position + current_state.speed,
-        lost: current_state.position > current_state.position_goal_end,
-        won: current_state.speed == 0 && current_state.position > current_state.position_goal_start && current_state.position < current_state.position_goal_end,
-        position_goal_start: current_state.position_goal_start,
-        position_goal_end: current_state.position_goal_end,
-        ..Default::default()
+        acceleration,
+        jerk,
+        discomfort: current_state.discomfort + (jerk * dt).abs() + impact * LANDING_DISCOMFORT,
+        suspension_offset,
+        body_speed,
+        pedals: input,
+        vehicle,
+        rpm,
+        gear,
+        stalled,
+        wheels_locked,
+        wheels_spinning,
+        brake_temperature,
+        boost,
+        nitrous: current_state.nitrous.step(),
+        launch_control,
+        cruise_active,
+        limiter_active,
+        obstacles_hit,
+        damage,
+        fuel,
+        battery,
+        electric,
+        fuel_used: current_state.fuel_used + (current_state.fuel - fuel),
+        charge_used: current_state.charge_used + (current_state.battery - battery).max(0.0),
+        charge_recovered: current_state.charge_recovered + (battery - current_state.battery).max(0.0),
+        speed,
+        position,
+        traffic,
+        drafting,
+        reverse_distance: current_state.reverse_distance + (-current_state.speed * dt).max(0.0),
+        elapsed_time: current_state.elapsed_time + dt,
+        ticks: current_state.ticks + 1,
+        position_goal_start: position_goal_start + goal_shift,
+        position_goal_end: position_goal_end + goal_shift,
+        endless,
+        checkpoints_passed,
+        overshoot_ticks,
+        overshoots,
+        time_of_day: (current_state.time_of_day + current_state.time_scale * dt).rem_euclid(24.0),
+        wind_force,
+        events,
+        lost: lose_reason != LoseReason::None,
+        lose_reason,
+        won,
+        ..current_state
     }
 }
 
@@ -80,181 +1072,1849 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn state_from_level_should_start_the_level_with_a_full_tank(){
+        let level = Level::get(4).unwrap();
+        let state = State::from_level(&level);
+
+        assert_eq!(level.start_position, state.position);
+        assert_eq!(level.goal_start, state.position_goal_start);
+        assert_eq!(level.goal_end, state.position_goal_end);
+        assert_eq!(level.vehicle, state.vehicle);
+        assert_eq!(level.vehicle.tank_capacity, state.fuel);
+        assert_eq!(level.nitrous_charges, state.nitrous.charges);
+    }
+
+    #[test]
+    fn state_with_params_should_use_the_given_bounds(){
+        let state = State::with_params(10.0, 100.0, 150.0).unwrap();
+
+        assert_eq!(10.0, state.position);
+        assert_eq!(100.0, state.position_goal_start);
+        assert_eq!(150.0, state.position_goal_end);
+    }
+
+    #[test]
+    fn state_with_params_should_reject_insane_bounds(){
+        assert_eq!(Some(BoundsError::EmptyGoal), State::with_params(10.0, 150.0, 100.0).err());
+        assert_eq!(Some(BoundsError::StartNotBeforeGoal), State::with_params(120.0, 100.0, 150.0).err());
+    }
+
+    #[test]
+    fn update_without_elapsed_time_should_leave_the_state_unchanged(){
+        let state = State { speed: 10.0, ..State::new() };
+
+        for dt in [0.0, -0.1, f64::NAN] {
+            assert_eq!(state.to_json(), update(state.clone(), Input::new(1.0, 0.0), dt).to_json());
+        }
+    }
+
+    #[test]
+    fn step_should_update_the_state_in_place(){
+        let mut state = State { speed: 10.0, ..State::new() };
+        let updated = update(state.clone(), Input::new(1.0, 0.0), 0.1);
+        state.step(Input::new(1.0, 0.0), 0.1);
+
+        assert_eq!(updated.position, state.position);
+        assert_eq!(updated.speed, state.speed);
+        assert_eq!(updated.elapsed_time, state.elapsed_time);
+    }
+
+    #[test]
+    fn setters_should_keep_the_state_sane(){
+        let mut state = State::new();
+
+        assert_eq!(Err(BoundsError::EmptyGoal), state.set_goal(200.0, 100.0));
+        assert_eq!(Err(BoundsError::NotFinite), state.set_position(f64::NAN));
+        assert_eq!(Err(BoundsError::OutOfRange), state.set_speed(-5.0));
+        assert_eq!(Ok(()), state.set_speed(5.0));
+        assert_eq!(5.0, state.speed);
+        let mut track = Track::new();
+        track.add_friction_zone(100.0, 50.0, 0.5);
+        assert!(state.set_track(track).is_err());
+        assert_eq!(Ok(()), state.set_goal(100.0, 200.0));
+        assert_eq!((100.0, 200.0), (state.position_goal_start, state.position_goal_end));
+    }
+
+    #[test]
+    fn pedal_rate_and_time_scale_setters_should_reject_negative_values(){
+        let mut state = State::new();
+
+        assert_eq!(Err(BoundsError::OutOfRange), state.set_pedal_rate(-1.0));
+        assert_eq!(Err(BoundsError::NotFinite), state.set_time_scale(f64::NAN));
+        assert_eq!(Ok(()), state.set_pedal_rate(0.5));
+        assert_eq!(Ok(()), state.set_time_scale(2.0));
+        assert_eq!((0.5, 2.0), (state.pedal_rate, state.time_scale));
+    }
+
+    #[test]
+    fn snapshot_should_not_follow_the_state(){
+        let mut state = State { speed: 10.0, ..State::new() };
+        let snapshot = state.snapshot();
+        state.step(Input::new(0.0, 1.0), 0.1);
+
+        assert_eq!(500.0, snapshot.position);
+        assert_eq!(10.0, snapshot.speed);
+        assert!(state.position > snapshot.position);
+    }
+
+    #[test]
+    fn restart_level_should_reset_the_run_and_keep_the_session(){
+        let mut state = State::from_level(&Level::get(1).unwrap());
+        for _ in 0..10 {
+            state = update(state, Input::new(1.0, 0.0), 1.0);
+        }
+        state.lost = true;
+
+        state.restart_level();
+        let fresh = State::from_level(&Level::get(1).unwrap());
+        assert_eq!(fresh.position, state.position);
+        assert_eq!(0.0, state.speed);
+        assert_eq!(0.0, state.elapsed_time);
+        assert_eq!(0, state.ticks);
+        assert_eq!(fresh.track, state.track);
+        assert_eq!(Session { attempts: 2, wins: 0, losses: 1 }, state.session);
+    }
+
+    #[test]
+    fn new_state_should_start_the_first_level(){
+        let state = State::new();
+
+        assert_eq!(500.0, state.position);
+        assert_eq!(9000.0, state.position_goal_start);
+        assert_eq!(10000.0, state.position_goal_end);
+    }
+
     #[test]
     fn throttle_should_set_acceleration_when_initially_zero() {
         let current_state = State {
             ..Default::default()
         };
-        let new_state = update(current_state, 1);
-        assert_eq!(1, new_state.acceleration);
+        let new_state = update(current_state, Input::new(1.0, 0.0), 1.0);
+        assert_eq!(1.0, new_state.acceleration);
     }
 
     #[test]
     fn throttle_should_not_increment_acceleration_if_already_to_maximum() {
         let current_state = State {
-            acceleration: 1,
+            acceleration: 1.0,
             ..Default::default()
         };
-        let new_state = update(current_state, 1);
-        assert_eq!(1, new_state.acceleration);
+        let new_state = update(current_state, Input::new(1.0, 0.0), 1.0);
+        assert_eq!(1.0, new_state.acceleration);
     }
 
     #[test]
     fn no_throttle_should_update_speed_with_current_acceleration(){
 
         let current_state = State {
-            acceleration: 1,
-            speed: 0,
+            acceleration: 1.0,
+            speed: 0.0,
             ..Default::default()
         };
-        let new_state = update(current_state, 0);
-        assert_eq!(1, new_state.speed);
+        let new_state = update(current_state, Input::new(0.0, 0.0), 1.0);
+        assert_eq!(1.0, new_state.speed);
     }
 
     #[test]
     fn no_throttle_should_update_position_from_speed (){
         let current_state = State {
-            speed: 1,
+            speed: 1.0,
             ..Default::default()
         };
 
-        let new_state = update(current_state, 0);
-        assert_eq!(1, new_state.position);
+        let new_state = update(current_state, Input::new(0.0, 0.0), 1.0);
+        assert_eq!(1.0, new_state.position);
     }
 
     #[test]
     fn no_throttle_should_add_current_speed_to_current_position () {
         let current_state = State {
-            speed: 1,
-            position: 1,
+            speed: 1.0,
+            position: 1.0,
             ..Default::default()
         };
 
-        let new_state = update(current_state, 0);
-        assert_eq!(2, new_state.position);
+        let new_state = update(current_state, Input::new(0.0, 0.0), 1.0);
+        assert_eq!(2.0, new_state.position);
     }
 
     #[test]
     fn game_is_lost_if_over_position_goal_end() {
         let current_state = State {
-            position: 2,
-            position_goal_end: 1,
+            position: 2.0,
+            position_goal_end: 1.0,
             lost: false,
             ..Default::default()
         };
 
-        let new_state = update(current_state, 0);
-        assert_eq!(true, new_state.lost);
+        let new_state = update(current_state, Input::new(0.0, 0.0), 1.0);
+        assert!(new_state.lost);
     }
 
     #[test]
     fn game_is_not_lost_if_position_is_before_position_goal_end(){
         let current_state = State {
-            position: 1,
-            position_goal_end: 2,
+            position: 1.0,
+            position_goal_end: 2.0,
             lost: false,
             ..Default::default()
         };
 
-        let new_state = update(current_state, 0);
-        assert_eq!(false, new_state.lost);
+        let new_state = update(current_state, Input::new(0.0, 0.0), 1.0);
+        assert!(!new_state.lost);
     }
 
     #[test]
     fn game_is_won_if_speed_is_0_and_position_between_lower_and_upper_goal_bounds() {
         let current_state = State {
-            position_goal_start: 1,
-            position_goal_end: 3,
-            position: 2,
-            speed: 0,
+            position_goal_start: 1.0,
+            position_goal_end: 3.0,
+            position: 2.0,
+            speed: 0.0,
             won: false,
             ..Default::default()
         };
 
-        let new_state = update(current_state, 0);
-        assert_eq!(true, new_state.won);
+        let new_state = update(current_state, Input::new(0.0, 0.0), 1.0);
+        assert!(new_state.won);
     }
 
     #[test]
     fn game_should_keep_goal_start_and_end_values_on_update(){
         let current_state = State {
-            position_goal_end: 2,
-            position_goal_start: 1,
+            position_goal_end: 2.0,
+            position_goal_start: 1.0,
             ..Default::default()
         };
 
-        let new_state = update(current_state, 0);
-        assert_eq!(1, new_state.position_goal_start);
-        assert_eq!(2, new_state.position_goal_end);
+        let new_state = update(current_state, Input::new(0.0, 0.0), 1.0);
+        assert_eq!(1.0, new_state.position_goal_start);
+        assert_eq!(2.0, new_state.position_goal_end);
     }
 
     #[test]
     fn throttle_should_not_directly_update_speed_or_position(){
         let current_state = State {
-            position: 0,
-            speed: 0,
+            position: 0.0,
+            reverse_distance: 0.0,
+            speed: 0.0,
             ..Default::default()
         };
 
-        let new_state = update(current_state, 1);
-        assert_eq!(0, new_state.position);
-        assert_eq!(0, new_state.speed);
+        let new_state = update(current_state, Input::new(1.0, 0.0), 1.0);
+        assert_eq!(0.0, new_state.position);
+        assert_eq!(0.0, new_state.speed);
     }
 
     #[test]
     fn negative_acceleration_should_not_make_speed_negative(){
         let current_state = State {
-            acceleration: -1,
-            speed: 0,
+            acceleration: -1.0,
+            speed: 0.0,
+            ..Default::default()
+        };
+
+        let new_state = update(current_state, Input::new(0.0, 0.0), 1.0);
+        assert_eq!(0.0, new_state.speed);
+    }
+
+    #[test]
+    fn fractional_throttle_should_give_fractional_speed_and_position(){
+        let current_state = State {
+            acceleration: 0.25,
+            speed: 0.5,
+            ..Default::default()
+        };
+
+        let new_state = update(current_state, Input::new(0.0, 0.0), 1.0);
+        assert_eq!(0.75, new_state.speed);
+        assert_eq!(0.5, new_state.position);
+    }
+
+
+    #[test]
+    fn brake_should_set_negative_acceleration_from_vehicle_brake_force(){
+        let current_state = State {
+            speed: 10.0,
+            vehicle: Vehicle { brake_force: 4.0, ..Default::default() },
+            ..Default::default()
+        };
+
+        let new_state = update(current_state, Input::new(0.0, 1.0), 1.0);
+        assert_eq!(-4.0, new_state.acceleration);
+    }
+
+    #[test]
+    fn brake_and_throttle_together_should_add_up(){
+        let current_state = State {
+            vehicle: Vehicle { brake_force: 4.0, ..Default::default() },
             ..Default::default()
         };
 
-        let new_state = update(current_state, 0);
-        assert_eq!(0, new_state.speed);
+        let new_state = update(current_state, Input::new(3.0, 1.0), 1.0);
+        assert_eq!(-1.0, new_state.acceleration);
     }
 
+    #[test]
+    fn braking_should_not_make_speed_negative(){
+        let current_state = State {
+            acceleration: -4.0,
+            speed: 1.0,
+            ..Default::default()
+        };
+
+        let new_state = update(current_state, Input::new(0.0, 1.0), 1.0);
+        assert_eq!(0.0, new_state.speed);
+    }
 
     #[test]
-    fn camera_should_project_900_when_car_world_position_is_100_and_camera_size_is_1000_and_world_and_screen_size_are_the_same(){
-        let camera = Camera {
-            screen_size: 1000,
-            world_size: 1000,
-            world_position: 500,
+    fn car_should_coast_down_to_a_stop_when_resistance_is_enabled(){
+        let mut state = State {
+            speed: 10.0,
+            vehicle: Vehicle { resistance: true, ..Default::default() },
+            ..Default::default()
         };
-        let screen_position = camera.project(100);
-        assert_eq!(900, screen_position);
+
+        for _ in 0..1000 {
+            state = update(state, Input::new(0.0, 0.0), 0.1);
+        }
+        assert_eq!(0.0, state.speed);
     }
 
     #[test]
-    fn camera_should_project_900_when_world_position_is_1000_and_camera_world_size_is_10x_bigger_than_screen_size(){
-        let camera= Camera {
-            screen_size: 1000,
-            world_size: 10000,
-            world_position: 5000,
+    fn throttle_should_not_accelerate_beyond_vehicle_max_acceleration(){
+        let current_state = State {
+            vehicle: Vehicle { limits: VehicleLimits::new(f64::MAX, 2.0, f64::MAX), ..Default::default() },
+            ..Default::default()
         };
-        let screen_position = camera.project(1000);
-        assert_eq!(900, screen_position);
+
+        let new_state = update(current_state, Input::new(5.0, 0.0), 1.0);
+        assert_eq!(2.0, new_state.acceleration);
     }
 
     #[test]
-    fn camera_should_project_900_when_camera_is_at_5000_in_world_position(){
-        let camera = Camera {
-            screen_size: 1000,
-            world_size: 10000,
-            world_position: 5000
+    fn speed_should_not_exceed_vehicle_max_speed(){
+        let current_state = State {
+            acceleration: 5.0,
+            speed: 9.0,
+            vehicle: Vehicle { limits: VehicleLimits::new(10.0, f64::MAX, f64::MAX), ..Default::default() },
+            ..Default::default()
         };
-        let screen_position = camera.project(1000);
-        assert_eq!(900, screen_position);
+
+        let new_state = update(current_state, Input::new(5.0, 0.0), 1.0);
+        assert_eq!(10.0, new_state.speed);
+    }
+
+    #[test]
+    fn engine_acceleration_should_depend_on_current_speed(){
+        let vehicle = Vehicle { powertrain: Powertrain::Combustion, ..Default::default() };
+        let slow = State { speed: 30.0, vehicle: vehicle.clone(), ..Default::default() };
+        let fast = State { speed: 49.0, vehicle, ..Default::default() };
+
+        let slow = update(slow, Input::new(1.0, 0.0), 1.0);
+        let fast = update(fast, Input::new(1.0, 0.0), 1.0);
+        assert!(fast.acceleration < slow.acceleration);
     }
 
     #[test]
-    fn camera_should_project_500_when_camera_is_at_same_world_position_than_target(){
-        let camera = Camera {
-            screen_size: 1000,
-            world_size: 10000,
-            world_position: 1000
+    fn update_should_expose_engine_rpm(){
+        let current_state = State {
+            speed: 20.0,
+            vehicle: Vehicle { powertrain: Powertrain::Combustion, ..Default::default() },
+            ..Default::default()
         };
-        let screen_position = camera.project(1000);
-        assert_eq!(500, screen_position);
+
+        let new_state = update(current_state, Input::new(0.0, 0.0), 1.0);
+        assert_eq!(2600.0, new_state.rpm);
+    }
+
+    fn five_speed_car() -> Vehicle {
+        Vehicle {
+            powertrain: Powertrain::Combustion,
+            gearbox: Gearbox::five_speed(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn shift_up_should_engage_next_gear_until_top_gear(){
+        let mut state = State { gear: 4, vehicle: five_speed_car(), ..Default::default() };
+
+        assert!(state.shift_up());
+        assert_eq!(5, state.gear);
+        assert!(!state.shift_up());
+        assert_eq!(5, state.gear);
+    }
+
+    #[test]
+    fn shift_down_should_go_down_to_neutral(){
+        let mut state = State { gear: 1, vehicle: five_speed_car(), ..Default::default() };
+
+        assert!(state.shift_down());
+        assert_eq!(0, state.gear);
+        assert!(!state.shift_down());
+        assert_eq!(0, state.gear);
+    }
+
+    #[test]
+    fn shift_down_should_be_refused_when_it_would_over_rev(){
+        let mut state = State { gear: 3, speed: 40.0, vehicle: five_speed_car(), ..Default::default() };
+
+        assert!(!state.shift_down());
+        assert_eq!(3, state.gear);
+    }
+
+    #[test]
+    fn engine_in_neutral_should_not_drive_the_car(){
+        let current_state = State { gear: 0, vehicle: five_speed_car(), ..Default::default() };
+
+        let new_state = update(current_state, Input::new(1.0, 0.0), 1.0);
+        assert_eq!(0.0, new_state.acceleration);
+        assert_eq!(800.0, new_state.rpm);
+    }
+
+    #[test]
+    fn closed_throttle_in_gear_should_engine_brake_harder_than_coasting_in_neutral(){
+        let mut vehicle = five_speed_car();
+        vehicle.engine.braking_force = 1.0;
+        let in_gear = State { gear: 2, speed: 20.0, vehicle, ..Default::default() };
+        let mut coasting = in_gear.clone();
+        coasting.set_neutral();
+
+        let in_gear = update(in_gear, Input::new(0.0, 0.0), 1.0);
+        let coasting = update(coasting, Input::new(0.0, 0.0), 1.0);
+        assert!(in_gear.acceleration < 0.0);
+        assert_eq!(0, coasting.gear);
+        assert_eq!(0.0, coasting.acceleration);
+    }
+
+    #[test]
+    fn launch_control_should_only_arm_for_a_combustion_car_standing_in_first(){
+        assert!(!State::default().arm_launch_control());
+        assert!(!State { gear: 2, vehicle: five_speed_car(), ..Default::default() }.arm_launch_control());
+        assert!(!State { speed: 1.0, vehicle: five_speed_car(), ..Default::default() }.arm_launch_control());
+        assert!(State { vehicle: five_speed_car(), ..Default::default() }.arm_launch_control());
+    }
+
+    #[test]
+    fn launch_control_should_hold_peak_torque_rpm_on_the_brake(){
+        let mut state = State { vehicle: five_speed_car(), ..Default::default() };
+        state.arm_launch_control();
+
+        let state = update(state, Input::new(0.0, 1.0), 0.1);
+        assert!(state.launch_control);
+        assert_eq!(state.vehicle.engine.peak_torque_rpm, state.rpm);
+        assert_eq!(0.0, update(state, Input::new(0.0, 1.0), 0.1).speed);
+    }
+
+    #[test]
+    fn launch_control_should_launch_as_hard_as_the_tyres_grip(){
+        let mut vehicle = five_speed_car();
+        vehicle.grip_limit = true;
+        vehicle.engine.peak_force = 10.0;
+        let mut state = State { vehicle, ..Default::default() };
+        let spinning = update(state.clone(), Input::new(1.0, 0.0), 0.1);
+        state.arm_launch_control();
+
+        let launched = update(state, Input::new(0.0, 0.0), 0.1);
+        assert!(spinning.wheels_spinning);
+        assert!(!launched.wheels_spinning);
+        assert_eq!(GRAVITY, launched.acceleration);
+    }
+
+    #[test]
+    fn launch_control_should_let_go_once_the_engine_reaches_peak_torque(){
+        let mut state = State { vehicle: five_speed_car(), ..Default::default() };
+        state.arm_launch_control();
+
+        for _ in 0..100 {
+            state = update(state, Input::new(0.0, 0.0), 0.1);
+        }
+        assert!(!state.launch_control);
+        assert!(state.speed > 0.0);
+    }
+
+    fn clutch_car() -> Vehicle {
+        Vehicle { clutch: true, ..five_speed_car() }
+    }
+
+    #[test]
+    fn dumping_the_clutch_at_a_standstill_should_stall_the_engine(){
+        let state = State { vehicle: clutch_car(), ..Default::default() };
+
+        let new_state = update(state, Input::new(0.5, 0.0), 0.1);
+        assert!(new_state.stalled);
+    }
+
+    #[test]
+    fn pressed_clutch_should_disengage_the_engine(){
+        let state = State { vehicle: clutch_car(), ..Default::default() };
+
+        let new_state = update(state, Input { clutch: 1.0, ..Input::new(1.0, 0.0) }, 0.1);
+        assert!(!new_state.stalled);
+        assert_eq!(0.0, new_state.acceleration);
+        assert_eq!(new_state.vehicle.engine.free_rpm(1.0), new_state.rpm);
+    }
+
+    #[test]
+    fn slipping_the_clutch_with_throttle_should_pull_away(){
+        let state = State { vehicle: clutch_car(), ..Default::default() };
+
+        let new_state = update(state, Input { clutch: 0.5, ..Input::new(1.0, 0.0) }, 0.1);
+        assert!(!new_state.stalled);
+        assert!(new_state.acceleration > 0.0);
+    }
+
+    #[test]
+    fn sustained_throttle_should_build_turbo_boost(){
+        let mut vehicle = five_speed_car();
+        vehicle.turbo = Turbo::new(0.5, 1.0);
+        let mut state = State { gear: 2, speed: 10.0, vehicle, ..Default::default() };
+
+        state = update(state, Input::new(1.0, 0.0), 0.5);
+        let first_boost = state.boost;
+        let first_acceleration = state.acceleration;
+        state = update(state, Input::new(1.0, 0.0), 0.5);
+        assert!(state.boost > first_boost);
+        assert!(state.acceleration > first_acceleration);
+
+        state = update(state, Input::new(0.0, 0.0), 0.5);
+        assert_eq!(0.0, state.boost);
+    }
+
+    #[test]
+    fn nitrous_should_add_acceleration_while_it_burns(){
+        let mut state = State { nitrous: Nitrous::new(1, 2, 5.0), ..Default::default() };
+        assert!(state.fire_nitrous());
+
+        let state = update(state, Input::new(1.0, 0.0), 0.1);
+        assert_eq!(6.0, state.acceleration);
+        let state = update(state, Input::new(1.0, 0.0), 0.1);
+        assert_eq!(6.0, state.acceleration);
+        let mut state = update(state, Input::new(1.0, 0.0), 0.1);
+        assert_eq!(1.0, state.acceleration);
+        assert!(!state.fire_nitrous());
+    }
+
+    #[test]
+    fn lugging_a_high_gear_should_stall_the_engine_until_restarted(){
+        let current_state = State { gear: 4, speed: 1.0, vehicle: five_speed_car(), ..Default::default() };
+
+        let mut new_state = update(current_state, Input::new(1.0, 0.0), 1.0);
+        assert!(new_state.stalled);
+        assert_eq!(0.0, new_state.rpm);
+        assert_eq!(0.0, new_state.acceleration);
+
+        new_state.gear = 1;
+        new_state.restart_engine();
+        let new_state = update(new_state, Input::new(1.0, 0.0), 1.0);
+        assert!(!new_state.stalled);
+        assert!(new_state.acceleration > 0.0);
+    }
+
+    #[test]
+    fn automatic_transmission_should_work_through_the_gears_while_accelerating(){
+        let mut state = State {
+            vehicle: Vehicle { gearbox: Gearbox::five_speed_automatic(), ..five_speed_car() },
+            ..Default::default()
+        };
+
+        for _ in 0..600 {
+            state = update(state, Input::new(1.0, 0.0), 0.1);
+        }
+        assert_eq!(5, state.gear);
+        assert!(!state.stalled);
+    }
+
+    #[test]
+    fn automatic_transmission_should_shift_down_without_stalling_when_braking_to_a_stop(){
+        let mut state = State {
+            gear: 5,
+            speed: 40.0,
+            vehicle: Vehicle { brake_force: 3.0, gearbox: Gearbox::five_speed_automatic(), ..five_speed_car() },
+            ..Default::default()
+        };
+
+        for _ in 0..300 {
+            state = update(state, Input::new(0.0, 1.0), 0.1);
+        }
+        assert_eq!(0.0, state.speed);
+        assert_eq!(1, state.gear);
+        assert!(!state.stalled);
+    }
+
+    #[test]
+    fn throttle_should_burn_fuel(){
+        let current_state = State {
+            fuel: 10.0,
+            vehicle: Vehicle { fuel_consumption: 2.0, ..Default::default() },
+            ..Default::default()
+        };
+
+        let new_state = update(current_state, Input::new(1.0, 0.0), 0.5);
+        assert_eq!(9.0, new_state.fuel);
+    }
+
+    #[test]
+    fn empty_tank_should_cut_the_drive(){
+        let current_state = State {
+            fuel: 0.0,
+            vehicle: Vehicle { fuel_consumption: 2.0, ..Default::default() },
+            ..Default::default()
+        };
+
+        let new_state = update(current_state, Input::new(1.0, 0.0), 1.0);
+        assert_eq!(0.0, new_state.acceleration);
+        assert_eq!(0.0, new_state.fuel);
+    }
+
+    #[test]
+    fn game_is_lost_if_out_of_fuel_and_stopped_before_the_goal(){
+        let current_state = State {
+            position: 1.0,
+            position_goal_start: 5.0,
+            position_goal_end: 10.0,
+            fuel: 0.0,
+            vehicle: Vehicle { fuel_consumption: 2.0, ..Default::default() },
+            ..Default::default()
+        };
+
+        let new_state = update(current_state, Input::new(1.0, 0.0), 1.0);
+        assert!(new_state.lost);
+        assert_eq!(LoseReason::OutOfFuel, new_state.lose_reason);
+    }
+
+    #[test]
+    fn game_is_not_lost_while_an_empty_car_still_coasts(){
+        let current_state = State {
+            position: 1.0,
+            speed: 2.0,
+            position_goal_start: 5.0,
+            position_goal_end: 10.0,
+            fuel: 0.0,
+            vehicle: Vehicle { fuel_consumption: 2.0, ..Default::default() },
+            ..Default::default()
+        };
+
+        let new_state = update(current_state, Input::new(1.0, 0.0), 1.0);
+        assert!(!new_state.lost);
+    }
+
+    #[test]
+    fn overshooting_should_be_the_lose_reason(){
+        let current_state = State {
+            position: 2.0,
+            position_goal_end: 1.0,
+            ..Default::default()
+        };
+
+        let new_state = update(current_state, Input::new(0.0, 0.0), 1.0);
+        assert_eq!(LoseReason::Overshoot, new_state.lose_reason);
+    }
+
+    fn electric_car() -> Vehicle {
+        Vehicle {
+            powertrain: Powertrain::Electric,
+            energy_consumption: 2.0,
+            regen_efficiency: 0.5,
+            battery_capacity: 10.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn electric_throttle_should_drain_battery_and_braking_recharge_it(){
+        let current_state = State { battery: 5.0, vehicle: electric_car(), ..Default::default() };
+        let new_state = update(current_state, Input::new(1.0, 0.0), 1.0);
+        assert_eq!(3.0, new_state.battery);
+
+        let current_state = State { battery: 5.0, speed: 10.0, vehicle: electric_car(), ..Default::default() };
+        let new_state = update(current_state, Input::new(0.0, 1.0), 1.0);
+        assert_eq!(6.0, new_state.battery);
+    }
+
+    #[test]
+    fn regen_should_not_charge_beyond_battery_capacity(){
+        let current_state = State { battery: 10.0, speed: 10.0, vehicle: electric_car(), ..Default::default() };
+
+        let new_state = update(current_state, Input::new(0.0, 1.0), 1.0);
+        assert_eq!(10.0, new_state.battery);
+    }
+
+    fn hybrid_car() -> Vehicle {
+        Vehicle {
+            powertrain: Powertrain::Hybrid,
+            gearbox: Gearbox::five_speed(),
+            fuel_consumption: 1.0,
+            ..electric_car()
+        }
+    }
+
+    #[test]
+    fn hybrid_should_run_on_its_motor_at_low_speed_and_light_throttle(){
+        let state = State { battery: 5.0, vehicle: hybrid_car(), ..Default::default() };
+
+        let new_state = update(state, Input::new(0.5, 0.0), 1.0);
+        assert!(new_state.electric);
+        assert_eq!(0.0, new_state.rpm);
+        assert_eq!(2.0, new_state.acceleration);
+        assert_eq!(1.0, new_state.charge_used);
+        assert_eq!(0.0, new_state.fuel_used);
+    }
+
+    #[test]
+    fn hybrid_should_start_its_engine_at_high_throttle(){
+        let state = State { battery: 5.0, vehicle: hybrid_car(), ..Default::default() };
+
+        let new_state = update(state, Input::new(1.0, 0.0), 1.0);
+        assert!(!new_state.electric);
+        assert!(new_state.rpm > 0.0);
+        assert_eq!(5.0, new_state.battery);
+        assert!(new_state.fuel_used > 0.0);
+    }
+
+    #[test]
+    fn hybrid_with_a_dry_tank_should_keep_going_on_its_battery(){
+        let state = State { battery: 5.0, fuel: 0.0, speed: 20.0, gear: 3, vehicle: hybrid_car(), ..Default::default() };
+
+        let new_state = update(state, Input::new(1.0, 0.0), 1.0);
+        assert!(new_state.electric);
+        assert_eq!(4.0, new_state.acceleration);
+    }
+
+    #[test]
+    fn braking_should_count_recovered_charge(){
+        let state = State { battery: 5.0, speed: 10.0, vehicle: electric_car(), ..Default::default() };
+
+        let new_state = update(state, Input::new(0.0, 1.0), 1.0);
+        assert_eq!(1.0, new_state.charge_recovered);
+        assert_eq!(0.0, new_state.charge_used);
+    }
+
+    #[test]
+    fn game_is_lost_if_battery_is_flat_and_stopped_before_the_goal(){
+        let current_state = State {
+            position_goal_start: 5.0,
+            position_goal_end: 10.0,
+            battery: 0.0,
+            vehicle: electric_car(),
+            ..Default::default()
+        };
+
+        let new_state = update(current_state, Input::new(1.0, 0.0), 1.0);
+        assert_eq!(0.0, new_state.acceleration);
+        assert_eq!(LoseReason::OutOfCharge, new_state.lose_reason);
+    }
+
+    #[test]
+    fn heavier_vehicle_should_need_longer_to_stop(){
+        let stopping_distance = |mass: f64| {
+            let mut state = State {
+                speed: 20.0,
+                vehicle: Vehicle { mass, brake_force: 8.0, ..Default::default() },
+                ..Default::default()
+            };
+            while state.speed > 0.0 {
+                state = update(state, Input::new(0.0, 1.0), 0.1);
+            }
+            state.position
+        };
+
+        assert!(stopping_distance(4.0) > 3.0 * stopping_distance(1.0));
+    }
+
+    fn track_with_slope(gradient: f64) -> Track {
+        let mut track = Track::new();
+        track.add_slope(0.0, 100.0, gradient);
+        track
+    }
+
+    #[test]
+    fn car_should_slow_down_uphill_without_throttle(){
+        let current_state = State { speed: 10.0, position: 50.0, track: track_with_slope(0.1), ..Default::default() };
+
+        let new_state = update(current_state, Input::new(0.0, 0.0), 1.0);
+        assert!(new_state.acceleration < 0.0);
+    }
+
+    #[test]
+    fn car_should_speed_up_downhill_without_throttle(){
+        let current_state = State { position: 50.0, track: track_with_slope(-0.1), ..Default::default() };
+
+        let new_state = update(current_state, Input::new(0.0, 0.0), 1.0);
+        assert!(new_state.acceleration > 0.0);
+    }
+
+    #[test]
+    fn gravity_should_not_apply_off_the_slope(){
+        let current_state = State { position: 150.0, track: track_with_slope(0.1), ..Default::default() };
+
+        let new_state = update(current_state, Input::new(0.0, 0.0), 1.0);
+        assert_eq!(0.0, new_state.acceleration);
+    }
+
+    #[test]
+    fn braking_on_ice_should_decelerate_less(){
+        let mut track = Track::new();
+        track.add_surface(0.0, 100.0, Surface::Ice);
+        let current_state = State {
+            speed: 10.0,
+            position: 50.0,
+            vehicle: Vehicle { brake_force: 5.0, ..Default::default() },
+            track,
+            ..Default::default()
+        };
+
+        let new_state = update(current_state, Input::new(0.0, 1.0), 1.0);
+        assert_eq!(-0.5, new_state.acceleration);
+    }
+
+    #[test]
+    fn tailwind_should_push_and_headwind_hold_back_the_car(){
+        let tailwind = State { wind: Wind::new(2.0, 0.0, 0), vehicle: Vehicle { mass: 4.0, ..Default::default() }, ..Default::default() };
+        let headwind = State { speed: 5.0, wind: Wind::new(-2.0, 0.0, 0), vehicle: Vehicle { mass: 4.0, ..Default::default() }, ..Default::default() };
+
+        assert_eq!(0.5, update(tailwind, Input::new(0.0, 0.0), 1.0).acceleration);
+        assert_eq!(-0.5, update(headwind, Input::new(0.0, 0.0), 1.0).acceleration);
+    }
+
+    #[test]
+    fn update_should_expose_current_wind_force(){
+        let current_state = State { elapsed_time: 3.0, wind: Wind::new(1.0, 2.0, 5), ..Default::default() };
+        let expected = current_state.wind.force_at(3.0);
+
+        let new_state = update(current_state, Input::new(0.0, 0.0), 1.0);
+        assert_eq!(expected, new_state.wind_force);
+    }
+
+    #[test]
+    fn elapsed_time_should_accumulate_dt(){
+        let current_state = State { elapsed_time: 1.0, ..Default::default() };
+
+        let new_state = update(current_state, Input::new(0.0, 0.0), 0.5);
+        assert_eq!(1.5, new_state.elapsed_time);
+    }
+
+    #[test]
+    fn score_should_only_be_given_for_won_runs(){
+        let state = State { won: true, ..State::new() };
+
+        assert!(State::new().score().is_none());
+        assert_eq!(Some(score(&state)), state.score());
+    }
+
+    #[test]
+    fn stars_should_use_the_thresholds_of_the_level(){
+        let level = Level { two_star_score: 0.0, three_star_score: f64::MAX, ..Level::default() };
+        let state = State { won: true, ..State::from_level(&level) };
+
+        assert_eq!(0, State::new().stars());
+        assert_eq!(2, state.stars());
+    }
+
+    #[test]
+    fn stopping_in_the_goal_after_the_target_time_should_lose_a_time_trial(){
+        let current_state = State {
+            position: 9500.0,
+            elapsed_time: 30.5,
+            target_time: 30.0,
+            ..State::new()
+        };
+
+        let new_state = update(current_state, Input::new(0.0, 0.0), 1.0);
+        assert!(!new_state.won);
+        assert_eq!(LoseReason::TimeOut, new_state.lose_reason);
+    }
+
+    #[test]
+    fn running_out_of_time_past_the_goal_should_not_count_as_an_overshoot(){
+        let current_state = State {
+            position: 10100.0,
+            elapsed_time: 30.5,
+            target_time: 30.0,
+            vehicle: reversing_car(),
+            overshoot_policy: OvershootPolicy::Penalty,
+            ..State::new()
+        };
+
+        assert_eq!(LoseReason::TimeOut, update(current_state, Input::new(0.0, 0.0), 1.0).lose_reason);
+    }
+
+    #[test]
+    fn stopping_in_the_goal_within_the_target_time_should_win_a_time_trial(){
+        let current_state = State { position: 9500.0, elapsed_time: 29.5, target_time: 30.0, ..State::new() };
+
+        let new_state = update(current_state, Input::new(0.0, 0.0), 1.0);
+        assert!(new_state.won);
+        assert_eq!(0.0, new_state.remaining_time());
+    }
+
+    #[test]
+    fn remaining_time_should_count_down_to_the_target_time(){
+        let state = State { elapsed_time: 10.0, target_time: 30.0, ..State::new() };
+
+        assert_eq!(20.0, state.remaining_time());
+    }
+
+    #[test]
+    fn stopping_in_the_goal_in_endless_mode_should_spawn_the_next_zone(){
+        let current_state = State {
+            position: 9500.0,
+            speed: 0.0,
+            reverse_distance: 3.0,
+            endless: Endless::new(1000.0, 2000.0, 1),
+            ..State::new()
+        };
+
+        let new_state = update(current_state, Input::new(0.0, 0.0), 1.0);
+        assert!(!new_state.won);
+        assert!(!new_state.lost);
+        assert_eq!(1, new_state.endless.streak);
+        assert!(new_state.position_goal_start >= 11000.0);
+        assert_eq!(1000.0, new_state.position_goal_end - new_state.position_goal_start);
+        assert_eq!(3.0, new_state.reverse_distance);
+        assert_eq!(vec![Event::GoalCleared], new_state.events);
+    }
+
+    #[test]
+    fn crossing_a_checkpoint_should_count_it_and_fire_an_event(){
+        let current_state = State {
+            position: 100.0,
+            speed: 10.0,
+            checkpoints: vec![Checkpoint::new(105.0, f64::MAX)],
+            ..State::new()
+        };
+
+        let new_state = update(current_state, Input::new(0.0, 0.0), 1.0);
+        assert_eq!(1, new_state.checkpoints_passed);
+        assert_eq!(vec![Event::CheckpointPassed], new_state.events);
+    }
+
+    #[test]
+    fn stopping_in_the_goal_should_not_win_before_every_checkpoint_is_passed(){
+        let current_state = State {
+            position: 9500.0,
+            checkpoints: vec![Checkpoint::new(100.0, f64::MAX)],
+            ..State::new()
+        };
+
+        let new_state = update(current_state, Input::new(0.0, 0.0), 1.0);
+        assert!(!new_state.won);
+    }
+
+    #[test]
+    fn missing_a_split_time_should_lose_the_run(){
+        let current_state = State {
+            position: 100.0,
+            elapsed_time: 9.5,
+            checkpoints: vec![Checkpoint::new(200.0, 10.0)],
+            ..State::new()
+        };
+
+        let new_state = update(current_state, Input::new(0.0, 0.0), 1.0);
+        assert_eq!(LoseReason::SplitMissed, new_state.lose_reason);
+    }
+
+    #[test]
+    fn from_level_at_should_keep_the_difficulty_across_restarts(){
+        let mut state = State::from_level_at(&Level::default(), Difficulty::Hard);
+        state.restart_level();
+
+        assert_eq!(9250.0, state.position_goal_start);
+    }
+
+    #[test]
+    fn set_seed_should_survive_restarts(){
+        let mut state = State::from_level(&Level::get(3).unwrap());
+        state.set_seed(11);
+        let wind = state.wind;
+        state.restart_level();
+
+        assert_eq!(11, state.seed);
+        assert_eq!(wind, state.wind);
+    }
+
+    #[test]
+    fn update_should_be_deterministic_for_replays(){
+        for index in 0..Level::count() {
+            let run = || {
+                let mut state = State::from_level(&Level::get(index).unwrap());
+                state.set_seed(index as u64);
+                for tick in 0..500 {
+                    state = update(state, Input::new((tick % 7) as f64 / 6.0, (tick % 5) as f64 / 8.0), 0.05);
+                }
+                state
+            };
+            let (first, second) = (run(), run());
+
+            assert_eq!(first.position.to_bits(), second.position.to_bits(), "level {}", index);
+            assert_eq!(first.speed.to_bits(), second.speed.to_bits(), "level {}", index);
+            assert_eq!(first.wind_force.to_bits(), second.wind_force.to_bits(), "level {}", index);
+        }
+    }
+
+    #[test]
+    fn state_should_round_trip_through_json(){
+        let mut state = State::from_level(&Level::get(3).unwrap());
+        state.add_traffic(9000.0, 5.0);
+        for _ in 0..20 {
+            state = update(state, Input::new(1.0, 0.0), 0.1);
+        }
+
+        let restored = State::from_json(&state.to_json()).unwrap();
+        assert_eq!(state.to_json(), restored.to_json());
+        assert_eq!(state.position, restored.position);
+        assert_eq!(state.level, restored.level);
+        assert_eq!(state.traffic, restored.traffic);
+    }
+
+    #[test]
+    fn state_should_round_trip_through_bytes_more_compactly_than_json(){
+        let mut state = State::from_level(&Level::get(2).unwrap());
+        for _ in 0..20 {
+            state = update(state, Input::new(1.0, 0.0), 0.1);
+        }
+
+        let bytes = state.to_bytes();
+        assert_eq!(state.to_json(), State::from_bytes(&bytes).unwrap().to_json());
+        assert!(bytes.len() < state.to_json().len());
+    }
+
+    #[test]
+    fn state_encoding_should_match_the_fixture_of_this_format_version(){
+        let fixture = include_bytes!("../tests/fixtures/state.bin");
+
+        assert_eq!(&fixture[..], &State::new().to_bytes()[..]);
+        assert_eq!(State::new().to_json(), State::from_bytes(fixture).unwrap().to_json());
+    }
+
+    #[test]
+    fn from_json_should_reject_what_is_not_a_state(){
+        assert!(State::from_json("{}").is_err());
+        assert!(State::from_json("not json").is_err());
+    }
+
+    #[test]
+    fn driving_through_the_goal_zone_should_fire_entered_then_overshot(){
+        let mut state = State { position: 8990.0, speed: 20.0, ..State::new() };
+        let mut events = Vec::new();
+        for _ in 0..60 {
+            state = update(state, Input::new(0.0, 0.0), 1.0);
+            events.extend(state.events.iter().copied());
+        }
+
+        assert_eq!(vec![Event::GoalEntered, Event::GoalOvershot, Event::Lost], events);
+    }
+
+    #[test]
+    fn winning_should_fire_won_once(){
+        let mut state = State { position: 9500.0, ..State::new() };
+
+        state = update(state, Input::new(0.0, 0.0), 1.0);
+        assert_eq!(vec![Event::Won], state.events);
+        state = update(state, Input::new(0.0, 0.0), 1.0);
+        assert!(state.events.is_empty());
+    }
+
+    #[test]
+    fn stopping_off_centre_should_not_win_an_exact_position_level(){
+        let state = State { position: 9600.0, win_condition: WinCondition::ExactPosition, ..State::new() };
+
+        assert!(!update(state.clone(), Input::new(0.0, 0.0), 1.0).won);
+        assert!(update(State { position: 9500.0, ..state }, Input::new(0.0, 0.0), 1.0).won);
+    }
+
+    #[test]
+    fn disabled_lose_conditions_should_not_lose(){
+        let lose_conditions = LoseConditions::new(false, false, true, true, false);
+        let state = State { position: 10050.0, elapsed_time: 50.0, target_time: 10.0, lose_conditions, ..State::new() };
+
+        assert_eq!(LoseReason::None, update(state, Input::new(0.0, 0.0), 1.0).lose_reason);
+    }
+
+    #[test]
+    fn reversing_behind_the_start_should_lose_when_enabled(){
+        let lose_conditions = LoseConditions { behind_start: true, ..LoseConditions::default() };
+        let state = State { position: 499.0, lose_conditions, ..State::new() };
+
+        assert_eq!(LoseReason::BehindStart, update(state.clone(), Input::new(0.0, 0.0), 1.0).lose_reason);
+        assert_eq!(LoseReason::None, update(State { lose_conditions: LoseConditions::default(), ..state }, Input::new(0.0, 0.0), 1.0).lose_reason);
+    }
+
+    #[test]
+    fn grace_window_should_lose_only_after_the_grace_ticks(){
+        let state = State {
+            position: 10001.0,
+            speed: -1.0,
+            overshoot_policy: OvershootPolicy::GraceWindow,
+            overshoot_grace_ticks: 2,
+            ..State::new()
+        };
+
+        let state = update(state, Input::new(0.0, 0.0), 0.1);
+        let state = update(state, Input::new(0.0, 0.0), 0.1);
+        assert_eq!(LoseReason::None, state.lose_reason);
+        assert_eq!(LoseReason::Overshoot, update(state, Input::new(0.0, 0.0), 0.1).lose_reason);
+    }
+
+    #[test]
+    fn rolling_back_in_should_reset_the_grace_window(){
+        let state = State {
+            position: 9999.0,
+            overshoot_policy: OvershootPolicy::GraceWindow,
+            overshoot_ticks: 5,
+            ..State::new()
+        };
+
+        assert_eq!(0, update(state, Input::new(0.0, 0.0), 1.0).overshoot_ticks);
+    }
+
+    #[test]
+    fn penalty_policy_should_count_overshoots_instead_of_losing(){
+        let state = State { position: 9999.0, speed: 5.0, overshoot_policy: OvershootPolicy::Penalty, ..State::new() };
+
+        let state = update(state, Input::new(0.0, 0.0), 1.0);
+        let state = update(state, Input::new(0.0, 0.0), 1.0);
+        assert_eq!(1, state.overshoots);
+        assert!(!state.lost);
+    }
+
+    #[test]
+    fn stopping_in_an_extra_goal_zone_should_win(){
+        let state = State { position: 5000.0, extra_goals: vec![GoalZone::new(4900.0, 5100.0, 0.5)], ..State::new() };
+
+        assert!(update(state, Input::new(0.0, 0.0), 1.0).won);
+    }
+
+    #[test]
+    fn goal_zones_should_start_with_the_main_zone(){
+        let state = State { extra_goals: vec![GoalZone::new(1.0, 2.0, 0.5)], ..State::new() };
+
+        assert_eq!(vec![GoalZone::new(9000.0, 10000.0, 1.0), GoalZone::new(1.0, 2.0, 0.5)], state.goal_zones());
+    }
+
+    #[test]
+    fn moving_goal_zone_should_drift_each_tick(){
+        let state = State { goal_motion: GoalMotion::new(2.0, 0.0, 0.0), ..State::new() };
+
+        let new_state = update(state, Input::new(0.0, 0.0), 0.5);
+        assert_eq!(9001.0, new_state.position_goal_start);
+        assert_eq!(10001.0, new_state.position_goal_end);
+    }
+
+    #[test]
+    fn car_stopped_where_the_goal_zone_drifted_to_should_win(){
+        let state = State { position: 8992.0, goal_motion: GoalMotion::new(-5.0, 0.0, 0.0), ..State::new() };
+
+        let state = update(state, Input::new(0.0, 0.0), 1.0);
+        assert!(!state.won);
+        let state = update(state, Input::new(0.0, 0.0), 1.0);
+        let state = update(state, Input::new(0.0, 0.0), 1.0);
+        assert!(state.won);
+    }
+
+    #[test]
+    fn ticks_should_count_updates(){
+        let mut state = State::new();
+        for _ in 0..3 {
+            state = update(state, Input::new(1.0, 0.0), 0.1);
+        }
+
+        assert_eq!(3, state.ticks);
+    }
+
+    #[test]
+    fn gusty_wind_seed_should_change_the_stopping_point(){
+        let stopping_point = |seed: u64| {
+            let mut state = State {
+                speed: 20.0,
+                wind: Wind::new(0.0, 1.0, seed),
+                vehicle: Vehicle { brake_force: 4.0, ..Default::default() },
+                ..Default::default()
+            };
+            for _ in 0..400 {
+                state = update(state, Input::new(0.0, 0.6), 0.05);
+            }
+            assert_eq!(0.0, state.speed);
+            state.position
+        };
+
+        assert_eq!(stopping_point(1), stopping_point(1));
+        assert_ne!(stopping_point(1), stopping_point(2));
+    }
+
+    #[test]
+    fn locking_the_wheels_should_lengthen_the_stopping_distance(){
+        let stopping_distance = |brake: f64| {
+            let mut state = State {
+                speed: 20.0,
+                vehicle: Vehicle { grip_limit: true, brake_force: 12.0, ..Default::default() },
+                ..Default::default()
+            };
+            let mut locked = false;
+            while state.speed > 0.0 {
+                state = update(state, Input::new(0.0, brake), 0.05);
+                locked = locked || state.wheels_locked;
+            }
+            (state.position, locked)
+        };
+
+        let (threshold, threshold_locked) = stopping_distance(0.9);
+        let (slammed, slammed_locked) = stopping_distance(1.0);
+        assert!(!threshold_locked);
+        assert!(slammed_locked);
+        assert!(slammed > threshold);
+    }
+
+    #[test]
+    fn driving_should_wear_the_tires_until_replaced(){
+        let mut state = State {
+            speed: 10.0,
+            vehicle: Vehicle { tire_wear_rate: 0.01, ..Default::default() },
+            ..Default::default()
+        };
+
+        state = update(state, Input::new(0.0, 0.0), 1.0);
+        assert_eq!(0.1, state.vehicle.tire_wear);
+
+        state.replace_tires();
+        assert_eq!(0.0, state.vehicle.tire_wear);
+    }
+
+    #[test]
+    fn tire_wear_should_never_exceed_bald(){
+        let current_state = State {
+            speed: 10.0,
+            vehicle: Vehicle { tire_wear: 0.95, tire_wear_rate: 0.01, ..Default::default() },
+            ..Default::default()
+        };
+
+        let new_state = update(current_state, Input::new(0.0, 0.0), 1.0);
+        assert_eq!(1.0, new_state.vehicle.tire_wear);
+    }
+
+    #[test]
+    fn repeated_hard_braking_should_fade_the_brakes_until_they_cool(){
+        let vehicle = Vehicle { brake_force: 8.0, brake_heating: 2.0, ..Default::default() };
+        let mut state = State { vehicle, ..Default::default() };
+        for _ in 0..10 {
+            state.speed = 30.0;
+            state = update(state, Input::new(0.0, 1.0), 0.5);
+        }
+        assert!(state.brake_temperature > state.vehicle.brake_fade_temperature);
+        assert!(-state.acceleration < 8.0);
+
+        for _ in 0..200 {
+            state = update(state, Input::new(0.0, 0.0), 0.5);
+        }
+        state.speed = 30.0;
+        let state = update(state, Input::new(0.0, 1.0), 0.5);
+        assert_eq!(-8.0, state.acceleration);
+    }
+
+    #[test]
+    fn abs_should_stop_shorter_than_locked_wheels_when_slamming_the_brakes(){
+        let stopping_distance = |abs: bool| {
+            let mut state = State {
+                speed: 20.0,
+                vehicle: Vehicle { grip_limit: true, abs, brake_force: 12.0, ..Default::default() },
+                ..Default::default()
+            };
+            while state.speed > 0.0 {
+                state = update(state, Input::new(0.0, 1.0), 0.05);
+                assert!(!(abs && state.wheels_locked));
+            }
+            state.position
+        };
+
+        assert!(stopping_distance(true) < stopping_distance(false));
+    }
+
+    #[test]
+    fn flooring_it_without_traction_control_should_spin_the_wheels(){
+        let current_state = State {
+            vehicle: Vehicle { grip_limit: true, ..Default::default() },
+            ..Default::default()
+        };
+
+        let new_state = update(current_state, Input::new(20.0, 0.0), 1.0);
+        assert!(new_state.wheels_spinning);
+        assert!(new_state.acceleration < GRAVITY);
+    }
+
+    #[test]
+    fn careful_launch_without_traction_control_should_beat_traction_control(){
+        let launch = |traction_control: bool| {
+            let current_state = State {
+                vehicle: Vehicle { grip_limit: true, traction_control, ..Default::default() },
+                ..Default::default()
+            };
+            update(current_state, Input::new(GRAVITY, 0.0), 1.0)
+        };
+
+        let with_traction_control = launch(true);
+        let without_traction_control = launch(false);
+        assert!(!with_traction_control.wheels_spinning);
+        assert!(!without_traction_control.wheels_spinning);
+        assert!(without_traction_control.acceleration > with_traction_control.acceleration);
+    }
+
+    #[test]
+    fn cruise_control_should_hold_target_speed_against_resistance(){
+        let mut state = State {
+            speed: 10.0,
+            vehicle: Vehicle { resistance: true, ..Default::default() },
+            track: track_with_slope(0.05),
+            ..Default::default()
+        };
+        state.set_cruise(15.0);
+
+        for _ in 0..200 {
+            state = update(state, Input::new(0.0, 0.0), 0.1);
+        }
+        assert!((state.speed - 15.0).abs() < 0.1);
+        assert!(state.cruise_active);
+    }
+
+    #[test]
+    fn braking_should_cancel_cruise_control(){
+        let mut state = State { speed: 10.0, ..Default::default() };
+        state.set_cruise(15.0);
+
+        state = update(state, Input::new(0.0, 0.5), 0.1);
+        assert!(!state.cruise_active);
+        state = update(state, Input::new(0.0, 0.0), 0.1);
+        assert_eq!(0.0, state.acceleration);
+    }
+
+    #[test]
+    fn throttle_should_override_cruise_control_upwards(){
+        let mut state = State { speed: 10.0, ..Default::default() };
+        state.set_cruise(10.0);
+
+        let new_state = update(state, Input::new(3.0, 0.0), 0.1);
+        assert_eq!(3.0, new_state.acceleration);
+    }
+
+    #[test]
+    fn cruise_control_should_ignore_targets_that_are_not_speeds(){
+        let mut state = State { speed: 10.0, ..Default::default() };
+        assert!(state.set_cruise(15.0));
+
+        for target_speed in [f64::NAN, f64::INFINITY, -5.0] {
+            assert!(!state.set_cruise(target_speed));
+            assert!(state.cruise_active);
+            assert_eq!(15.0, state.cruise_speed);
+        }
+    }
+
+    #[test]
+    fn cancelled_cruise_control_should_stop_driving(){
+        let mut state = State { speed: 10.0, ..Default::default() };
+        state.set_cruise(15.0);
+        state.cancel_cruise();
+
+        let new_state = update(state, Input::new(0.0, 0.0), 0.1);
+        assert_eq!(0.0, new_state.acceleration);
+    }
+
+    #[test]
+    fn speed_limiter_should_cap_speed_under_full_throttle(){
+        let mut state = State { speed_limit: 12.0, ..Default::default() };
+
+        for _ in 0..20 {
+            state = update(state, Input::new(5.0, 0.0), 1.0);
+            assert!(state.speed <= 12.0);
+        }
+        assert_eq!(12.0, state.speed);
+        assert_eq!(0.0, state.acceleration);
+        assert!(state.limiter_active);
+    }
+
+    #[test]
+    fn speed_limiter_should_not_brake_the_car(){
+        let state = State { speed: 15.0, speed_limit: 12.0, ..Default::default() };
+
+        let new_state = update(state, Input::new(5.0, 0.0), 1.0);
+        assert_eq!(0.0, new_state.acceleration);
+        assert_eq!(15.0, new_state.speed);
+    }
+
+    #[test]
+    fn hitting_speed_limiter_should_fire_event_once(){
+        let mut state = State { speed: 10.0, speed_limit: 12.0, ..State::new() };
+
+        state = update(state, Input::new(5.0, 0.0), 1.0);
+        assert_eq!(vec![Event::LimiterHit], state.events);
+        state = update(state, Input::new(5.0, 0.0), 1.0);
+        assert!(state.limiter_active);
+        assert!(state.events.is_empty());
+    }
+
+    #[test]
+    fn no_speed_limit_should_leave_throttle_untouched(){
+        let state = State { speed: 1000.0, ..Default::default() };
+
+        let new_state = update(state, Input::new(5.0, 0.0), 1.0);
+        assert_eq!(5.0, new_state.acceleration);
+        assert!(!new_state.limiter_active);
+    }
+
+    fn reversing_car() -> Vehicle {
+        Vehicle {
+            limits: VehicleLimits { max_reverse_speed: 3.0, ..Default::default() },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn shifting_into_reverse_should_need_a_stopped_car_allowed_to_reverse(){
+        let mut state = State { gear: 0, ..Default::default() };
+        assert!(!state.shift_down());
+
+        let mut moving = State { gear: 0, speed: 1.0, vehicle: reversing_car(), ..Default::default() };
+        assert!(!moving.shift_down());
+
+        state.vehicle = reversing_car();
+        assert!(state.shift_down());
+        assert_eq!(-1, state.gear);
+        assert!(!state.shift_down());
+        assert!(state.shift_up());
+        assert_eq!(0, state.gear);
+    }
+
+    #[test]
+    fn reverse_gear_should_drive_backwards_up_to_the_reverse_cap(){
+        let mut state = State { gear: -1, vehicle: reversing_car(), ..Default::default() };
+
+        for _ in 0..10 {
+            state = update(state, Input::new(1.0, 0.0), 1.0);
+        }
+        assert_eq!(-3.0, state.speed);
+        assert!(state.position < 0.0);
+        assert_eq!(-state.position, state.reverse_distance);
+    }
+
+    #[test]
+    fn braking_should_stop_a_reversing_car_without_pushing_it_forwards(){
+        let state = State { gear: -1, speed: -2.0, vehicle: reversing_car(), ..Default::default() };
+
+        let state = update(state, Input::new(0.0, 1.0), 1.0);
+        assert_eq!(1.0, state.acceleration);
+        let state = update(state, Input::new(0.0, 1.0), 1.0);
+        let state = update(state, Input::new(0.0, 1.0), 1.0);
+        assert_eq!(0.0, state.speed);
+    }
+
+    #[test]
+    fn braking_past_zero_should_stop_the_car_instead_of_reversing(){
+        let state = State { speed: 0.5, acceleration: -1.0, vehicle: reversing_car(), ..Default::default() };
+
+        let new_state = update(state, Input::new(0.0, 1.0), 1.0);
+        assert_eq!(0.0, new_state.speed);
+    }
+
+    #[test]
+    fn overshooting_should_not_lose_when_the_car_can_reverse_back(){
+        let state = State {
+            position: 10.0,
+            speed: 1.0,
+            position_goal_start: 5.0,
+            position_goal_end: 9.0,
+            vehicle: reversing_car(),
+            overshoot_policy: OvershootPolicy::Penalty,
+            ..Default::default()
+        };
+
+        let new_state = update(state, Input::new(0.0, 0.0), 1.0);
+        assert!(!new_state.lost);
+    }
+
+    #[test]
+    fn instant_loss_should_lose_even_when_the_car_can_reverse_back(){
+        let state = State {
+            position: 10.0,
+            speed: 1.0,
+            position_goal_start: 5.0,
+            position_goal_end: 9.0,
+            vehicle: reversing_car(),
+            ..Default::default()
+        };
+
+        let new_state = update(state, Input::new(0.0, 0.0), 1.0);
+        assert_eq!(LoseReason::Overshoot, new_state.lose_reason);
+    }
+
+    fn track_with_obstacle(kind: ObstacleKind, position: f64) -> Track {
+        let mut track = Track::new();
+        track.add_obstacle(kind, position);
+        track
+    }
+
+    #[test]
+    fn hitting_a_parked_car_should_lose_the_run(){
+        let state = State {
+            position: 95.0,
+            speed: 10.0,
+            position_goal_start: 500.0,
+            position_goal_end: 600.0,
+            track: track_with_obstacle(ObstacleKind::ParkedCar, 100.0),
+            ..Default::default()
+        };
+
+        let new_state = update(state, Input::new(0.0, 0.0), 1.0);
+        assert!(new_state.lost);
+        assert_eq!(LoseReason::Collision, new_state.lose_reason);
+        assert_eq!(vec![Event::Collision, Event::Lost], new_state.events);
+    }
+
+    #[test]
+    fn hitting_a_cone_should_count_a_penalty_once(){
+        let mut state = State {
+            position: 95.0,
+            speed: 5.0,
+            position_goal_start: 500.0,
+            position_goal_end: 600.0,
+            track: track_with_obstacle(ObstacleKind::Cone, 100.0),
+            ..Default::default()
+        };
+
+        state = update(state, Input::new(0.0, 0.0), 1.0);
+        state = update(state, Input::new(0.0, 0.0), 1.0);
+        assert!(!state.lost);
+        assert_eq!(1, state.obstacles_hit);
+    }
+
+    #[test]
+    fn rear_ending_a_traffic_car_should_lose_the_run(){
+        let mut state = State {
+            position: 90.0,
+            speed: 20.0,
+            position_goal_start: 500.0,
+            position_goal_end: 600.0,
+            ..Default::default()
+        };
+        state.add_traffic(100.0, 5.0);
+
+        let new_state = update(state, Input::new(0.0, 0.0), 1.0);
+        assert!(new_state.lost);
+        assert_eq!(LoseReason::Collision, new_state.lose_reason);
+    }
+
+    #[test]
+    fn traffic_should_drive_on_while_the_player_follows_at_a_distance(){
+        let mut state = State {
+            position: 50.0,
+            speed: 5.0,
+            position_goal_start: 500.0,
+            position_goal_end: 600.0,
+            ..Default::default()
+        };
+        state.add_traffic(100.0, 5.0);
+
+        for _ in 0..5 {
+            state = update(state, Input::new(0.0, 0.0), 1.0);
+        }
+        assert!(!state.lost);
+        assert_eq!(125.0, state.traffic()[0].position);
+    }
+
+    #[test]
+    fn added_traffic_should_stay_sorted_from_rearmost_to_frontmost(){
+        let mut state = State::default();
+        state.add_traffic(200.0, 5.0);
+        state.add_traffic(100.0, 5.0);
+
+        let positions: Vec<f64> = state.traffic().iter().map(|car| car.position).collect();
+        assert_eq!(vec![100.0, 200.0], positions);
+    }
+
+    #[test]
+    fn stopping_point_should_be_ahead_by_the_stopping_distance(){
+        let state = State { position: 100.0, speed: 10.0, ..Default::default() };
+
+        assert_eq!(150.0, state.stopping_point());
+    }
+
+    #[test]
+    fn stopping_point_should_match_where_full_braking_stops_the_car(){
+        let mut state = State {
+            position: 100.0,
+            speed: 10.0,
+            acceleration: -2.0,
+            vehicle: Vehicle { brake_force: 2.0, ..Default::default() },
+            ..Default::default()
+        };
+        let predicted = state.stopping_point();
+
+        while state.speed > 0.0 {
+            state = update(state, Input::new(0.0, 1.0), 0.01);
+        }
+        assert!((state.position - predicted).abs() < 0.1);
+    }
+
+    #[test]
+    fn jerk_should_be_the_rate_of_change_of_acceleration(){
+        let state = State::default();
+
+        let new_state = update(state, Input::new(2.0, 0.0), 0.5);
+        assert_eq!(4.0, new_state.jerk);
+        assert_eq!(2.0, new_state.discomfort);
+    }
+
+    #[test]
+    fn slamming_the_pedals_should_lower_the_comfort_score(){
+        let mut smooth = State::default();
+        let mut harsh = State::default();
+
+        for tick in 0..10 {
+            smooth = update(smooth, Input::new(0.5, 0.0), 1.0);
+            harsh = update(harsh, Input::new((tick % 2) as f64, 0.0), 1.0);
+        }
+        assert!(smooth.comfort_score() > harsh.comfort_score());
+    }
+
+    #[test]
+    fn pedal_rate_should_limit_how_fast_the_pedals_move(){
+        let state = State { pedal_rate: 0.5, ..Default::default() };
+
+        let state = update(state, Input::new(1.0, 0.0), 1.0);
+        assert_eq!(0.5, state.acceleration);
+        let state = update(state, Input::new(1.0, 0.0), 1.0);
+        assert_eq!(1.0, state.acceleration);
+        let state = update(state, Input::new(0.0, 0.0), 1.0);
+        assert_eq!(0.5, state.acceleration);
+    }
+
+    #[test]
+    fn rigid_suspension_should_keep_the_body_on_the_road(){
+        let mut track = Track::new();
+        track.add_slope(0.0, 10.0, 0.5);
+        let state = State { speed: 10.0, track, ..Default::default() };
+
+        let state = update(state, Input::new(0.0, 0.0), 0.5);
+        assert_eq!(0.0, state.suspension_offset);
+        assert_eq!(2.5, state.body_height());
+    }
+
+    #[test]
+    fn cresting_a_hill_fast_should_land_harshly(){
+        let mut track = Track::new();
+        track.add_slope(0.0, 20.0, 0.5);
+        track.add_slope(20.0, 40.0, -0.5);
+        let mut state = State {
+            speed: 20.0,
+            track,
+            vehicle: Vehicle { suspension: Suspension::new(100.0, 6.0, 0.1), ..Default::default() },
+            ..Default::default()
+        };
+
+        let mut events = Vec::new();
+        while state.position < 40.0 {
+            state = update(state, Input::new(0.0, 0.0), 0.01);
+            events.extend(state.events());
+        }
+        assert!(events.contains(&Event::HarshLanding));
+        assert!(state.comfort_score() < 100.0);
+    }
+
+    #[test]
+    fn trailer_should_slow_down_the_car(){
+        let vehicle = Vehicle { mass: 2.0, brake_force: 8.0, ..Default::default() };
+        let state = State { speed: 10.0, vehicle, ..Default::default() };
+        let towing = State { trailer: Trailer::new(2.0, 5.0, f64::MAX), ..state.clone() };
+
+        assert_eq!(-4.0, update(state, Input::new(0.0, 1.0), 1.0).acceleration);
+        assert_eq!(-2.0, update(towing, Input::new(0.0, 1.0), 1.0).acceleration);
+    }
+
+    #[test]
+    fn braking_too_hard_with_a_trailer_should_jackknife(){
+        let state = State {
+            speed: 10.0,
+            position_goal_end: 1000.0,
+            vehicle: Vehicle { brake_force: 8.0, ..Default::default() },
+            trailer: Trailer::new(1.0, 5.0, 3.0),
+            ..Default::default()
+        };
+
+        assert!(!update(state.clone(), Input::new(0.0, 0.5), 1.0).lost);
+        let new_state = update(state, Input::new(0.0, 1.0), 1.0);
+        assert!(new_state.lost);
+        assert_eq!(LoseReason::Jackknife, new_state.lose_reason);
+    }
+
+    #[test]
+    fn trailer_should_be_what_stops_in_the_goal_zone(){
+        let state = State {
+            position: 12.0,
+            position_goal_start: 5.0,
+            position_goal_end: 10.0,
+            trailer: Trailer::new(1.0, 5.0, f64::MAX),
+            ..Default::default()
+        };
+
+        let new_state = update(state, Input::new(0.0, 0.0), 1.0);
+        assert!(new_state.won);
+        assert!(!new_state.lost);
+    }
+
+    #[test]
+    fn drafting_should_reduce_drag_close_behind_traffic(){
+        let vehicle = Vehicle { resistance: true, rolling_resistance: 0.0, drag_coefficient: 0.01, ..Default::default() };
+        let alone = State { position: 90.0, speed: 10.0, vehicle, ..Default::default() };
+        let mut behind = alone.clone();
+        behind.add_traffic(100.0, 10.0);
+
+        let alone = update(alone, Input::new(0.0, 0.0), 0.1);
+        let behind = update(behind, Input::new(0.0, 0.0), 0.1);
+        assert!(!alone.drafting);
+        assert!(behind.drafting);
+        assert_eq!(-1.0, alone.acceleration);
+        assert_eq!(-DRAFT_DRAG, behind.acceleration);
+    }
+
+    #[test]
+    fn weather_should_scale_friction_and_drag(){
+        let vehicle = Vehicle { resistance: true, rolling_resistance: 0.0, drag_coefficient: 0.01, ..Default::default() };
+        let state = State {
+            speed: 10.0,
+            vehicle,
+            forecast: Forecast::new(Weather::Rain, 0.0, 0),
+            ..Default::default()
+        };
+
+        assert_eq!(Weather::Rain, state.weather());
+        assert_eq!(0.7, state.friction());
+        assert_eq!(-1.1, update(state, Input::new(0.0, 0.0), 0.1).acceleration);
+    }
+
+    #[test]
+    fn time_of_day_should_advance_and_wrap_around_midnight(){
+        let state = State { time_of_day: 23.0, time_scale: 0.5, ..Default::default() };
+
+        let state = update(state, Input::new(0.0, 0.0), 1.0);
+        assert_eq!(23.5, state.time_of_day);
+        let state = update(state, Input::new(0.0, 0.0), 2.0);
+        assert_eq!(0.5, state.time_of_day);
+    }
+
+    #[test]
+    fn level_clock_should_run_by_default_and_wrap_at_midnight(){
+        let level = Level { time_of_day: 23.9, ..Level::default() };
+        let mut state = State::from_level(&level);
+        for _ in 0..20 {
+            state = update(state, Input::new(0.0, 0.0), 1.0);
+        }
+
+        assert!((state.time_of_day - (23.9 + 20.0 * DEFAULT_TIME_SCALE - 24.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn headlights_should_extend_visibility_at_night(){
+        let mut state = State { time_of_day: 0.0, ..Default::default() };
+        assert_eq!(NIGHT_VISIBILITY, state.visibility());
+
+        state.toggle_headlights();
+        assert_eq!(HEADLIGHT_VISIBILITY, state.visibility());
+    }
+
+    #[test]
+    fn knocking_cones_should_damage_the_car(){
+        let mut state = State { speed: 10.0, ..Default::default() };
+        state.track.add_obstacle(ObstacleKind::Cone, 5.0);
+
+        let state = update(state, Input::new(0.0, 0.0), 1.0);
+        assert_eq!(CONE_DAMAGE, state.damage);
+        assert_eq!(CONE_DAMAGE * 100.0, state.damage_percent());
+    }
+
+    #[test]
+    fn damage_should_degrade_brakes_and_drive(){
+        let state = State { speed: 10.0, damage: 0.5, ..Default::default() };
+
+        assert_eq!(0.75, update(state.clone(), Input::new(1.0, 0.0), 1.0).acceleration);
+        assert_eq!(-0.75, update(state, Input::new(0.0, 1.0), 1.0).acceleration);
+    }
+
+    #[test]
+    fn full_damage_should_wreck_the_car(){
+        let mut state = State { speed: 10.0, damage: 1.0 - CONE_DAMAGE, position_goal_end: 1000.0, ..Default::default() };
+        state.track.add_obstacle(ObstacleKind::Cone, 5.0);
+
+        let state = update(state, Input::new(0.0, 0.0), 1.0);
+        assert!(state.lost);
+        assert_eq!(LoseReason::Wrecked, state.lose_reason);
+    }
+
+    #[test]
+    fn entering_the_goal_zone_too_fast_should_crash(){
+        let state = State {
+            position: 2.0,
+            speed: 5.0,
+            position_goal_start: 1.0,
+            position_goal_end: 100.0,
+            max_entry_speed: 4.0,
+            ..Default::default()
+        };
+
+        let new_state = update(state.clone(), Input::new(0.0, 0.0), 1.0);
+        assert!(new_state.lost);
+        assert_eq!(LoseReason::TooFast, new_state.lose_reason);
+        let new_state = update(State { speed: 4.0, ..state }, Input::new(0.0, 0.0), 1.0);
+        assert!(!new_state.lost);
+    }
+
+    #[test]
+    fn speed_should_integrate_acceleration_over_elapsed_time(){
+        let current_state = State {
+            acceleration: 2.0,
+            ..Default::default()
+        };
+
+        let new_state = update(current_state, Input::new(0.0, 0.0), 0.5);
+        assert_eq!(1.0, new_state.speed);
+    }
+
+    #[test]
+    fn position_should_integrate_speed_over_elapsed_time(){
+        let current_state = State {
+            speed: 10.0,
+            position: 1.0,
+            ..Default::default()
+        };
+
+        let new_state = update(current_state, Input::new(0.0, 0.0), 0.25);
+        assert_eq!(3.5, new_state.position);
+    }
+
+    #[test]
+    fn same_elapsed_time_should_give_same_position_at_different_frame_rates(){
+        let mut slow = State { speed: 6.0, ..Default::default() };
+        let mut fast = State { speed: 6.0, ..Default::default() };
+
+        for _ in 0..60 {
+            slow = update(slow, Input::new(0.0, 0.0), 1.0 / 60.0);
+        }
+        for _ in 0..144 {
+            fast = update(fast, Input::new(0.0, 0.0), 1.0 / 144.0);
+        }
+        assert!((slow.position - fast.position).abs() < 1e-9);
+
+        // Under throttle the speed changes within a tick, which the
+        // integration only follows to first order in dt, so the frame
+        // rates agree to within centimetres rather than exactly.
+        let mut slow = State::new();
+        let mut fast = State::new();
+        for _ in 0..60 {
+            slow = update(slow, Input::new(1.0, 0.0), 1.0 / 60.0);
+        }
+        for _ in 0..144 {
+            fast = update(fast, Input::new(1.0, 0.0), 1.0 / 144.0);
+        }
+        assert!(slow.speed > 0.5);
+        assert!((slow.speed - fast.speed).abs() < 0.05);
+        assert!((slow.position - fast.position).abs() < 0.05);
     }
 }
\ No newline at end of file
@@ -0,0 +1,103 @@
+use wasm_bindgen::prelude::*;
+
+use crate::State;
+
+/// Maps the whole run, from the start to past the last goal zone, onto a
+/// strip of `width` pixels, for drawing an overview bar. Unlike the
+/// [`Camera`](crate::Camera) the road runs left to right.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Minimap {
+    pub width: f64,
+    /// World position at the left end of the strip.
+    pub start: f64,
+    /// World position at the right end of the strip.
+    pub end: f64
+}
+
+#[wasm_bindgen]
+impl Minimap {
+    #[wasm_bindgen(constructor)]
+    pub fn new(width: f64, start: f64, end: f64) -> Minimap {
+        Minimap { width, start, end }
+    }
+
+    /// Minimap spanning everything on the road in `state`: the car, the
+    /// goal zones, obstacles, checkpoints and traffic.
+    pub fn of_state(state: &State, width: f64) -> Minimap {
+        let positions = std::iter::once(state.level.start_position)
+            .chain(std::iter::once(state.position))
+            .chain(state.goal_zones().into_iter().flat_map(|zone| [zone.start, zone.end]))
+            .chain(state.track.obstacles.iter().flat_map(|obstacle| [obstacle.start, obstacle.end]))
+            .chain(state.checkpoints.iter().map(|checkpoint| checkpoint.position))
+            .chain(state.traffic.iter().map(|car| car.position));
+        let (start, end) = positions.fold((f64::MAX, f64::MIN), |(start, end), position| (start.min(position), end.max(position)));
+        Minimap::new(width, start, end)
+    }
+
+    /// Pixels from the left of the strip `position` is drawn at, clamped
+    /// to the strip.
+    pub fn project(&self, position: f64) -> f64 {
+        if self.end <= self.start {
+            return 0.0;
+        }
+        ((position - self.start) / (self.end - self.start)).clamp(0.0, 1.0) * self.width
+    }
+
+    pub fn car(&self, state: &State) -> f64 {
+        self.project(state.position)
+    }
+
+    /// Left and right ends of every goal zone, the main one first, one
+    /// after the other.
+    pub fn goal_zones(&self, state: &State) -> Vec<f64> {
+        state.goal_zones().iter().flat_map(|zone| [self.project(zone.start), self.project(zone.end)]).collect()
+    }
+
+    /// Left and right ends of every obstacle, one after the other.
+    pub fn obstacles(&self, state: &State) -> Vec<f64> {
+        state.track.obstacles.iter().flat_map(|obstacle| [self.project(obstacle.start), self.project(obstacle.end)]).collect()
+    }
+
+    pub fn checkpoints(&self, state: &State) -> Vec<f64> {
+        state.checkpoints.iter().map(|checkpoint| self.project(checkpoint.position)).collect()
+    }
+
+    pub fn traffic(&self, state: &State) -> Vec<f64> {
+        state.traffic.iter().map(|car| self.project(car.position)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn minimap_should_span_from_the_start_to_the_goal(){
+        let minimap = Minimap::of_state(&State::new(), 95.0);
+
+        assert_eq!(Minimap::new(95.0, 500.0, 10000.0), minimap);
+        assert_eq!(0.0, minimap.car(&State::new()));
+        assert_eq!(vec![85.0, 95.0], minimap.goal_zones(&State::new()));
+    }
+
+    #[test]
+    fn projection_should_stay_on_the_strip(){
+        let minimap = Minimap::new(100.0, 0.0, 1000.0);
+
+        assert_eq!(25.0, minimap.project(250.0));
+        assert_eq!(0.0, minimap.project(-50.0));
+        assert_eq!(100.0, minimap.project(2000.0));
+    }
+
+    #[test]
+    fn traffic_and_checkpoints_should_be_on_the_map(){
+        let mut state = State::new();
+        state.add_traffic(11000.0, 5.0);
+        let minimap = Minimap::of_state(&state, 105.0);
+
+        assert_eq!(vec![105.0], minimap.traffic(&state));
+        assert!(minimap.checkpoints(&state).is_empty());
+    }
+}
@@ -0,0 +1,97 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+/// Nitrous bottles the player can fire for a short burst of acceleration.
+///
+/// The number of bottles is set per level; each one burns for a fixed
+/// number of ticks.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Nitrous {
+    /// Bottles left in the inventory.
+    pub charges: u32,
+    /// Ticks one bottle burns for.
+    pub burn_ticks: u32,
+    /// Acceleration added while a bottle burns, in m/s².
+    pub acceleration: f64,
+    /// Ticks left on the bottle burning now, 0 when none is.
+    pub active_ticks: u32
+}
+
+#[wasm_bindgen]
+impl Nitrous {
+    #[wasm_bindgen(constructor)]
+    pub fn new(charges: u32, burn_ticks: u32, acceleration: f64) -> Nitrous {
+        Nitrous {
+            charges,
+            burn_ticks,
+            acceleration,
+            active_ticks: 0
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active_ticks > 0
+    }
+
+    /// Fires a bottle. Returns false when none is left or one is still
+    /// burning.
+    pub fn fire(&mut self) -> bool {
+        if self.charges == 0 || self.is_active() {
+            return false;
+        }
+        self.charges -= 1;
+        self.active_ticks = self.burn_ticks;
+        true
+    }
+}
+
+impl Nitrous {
+    /// Acceleration added this tick.
+    pub fn boost(&self) -> f64 {
+        if self.is_active() { self.acceleration } else { 0.0 }
+    }
+
+    /// The bottles one tick later.
+    pub fn step(&self) -> Nitrous {
+        Nitrous {
+            active_ticks: self.active_ticks.saturating_sub(1),
+            ..*self
+        }
+    }
+}
+
+impl Default for Nitrous {
+    fn default() -> Nitrous {
+        Nitrous::new(0, 60, 5.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn firing_should_use_a_charge_and_burn_for_burn_ticks(){
+        let mut nitrous = Nitrous::new(2, 2, 5.0);
+
+        assert!(nitrous.fire());
+        assert_eq!(1, nitrous.charges);
+        assert_eq!(5.0, nitrous.boost());
+        let nitrous = nitrous.step();
+        assert_eq!(5.0, nitrous.boost());
+        let nitrous = nitrous.step();
+        assert_eq!(0.0, nitrous.boost());
+    }
+
+    #[test]
+    fn firing_should_be_refused_without_charges_or_while_burning(){
+        let mut nitrous = Nitrous::new(2, 10, 5.0);
+
+        assert!(!Nitrous::default().fire());
+        assert!(nitrous.fire());
+        assert!(!nitrous.fire());
+        assert_eq!(1, nitrous.charges);
+    }
+}
@@ -0,0 +1,256 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::{binary, Input, State};
+
+/// Something the driver does between ticks besides working the pedals.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Action {
+    ShiftUp,
+    ShiftDown,
+    ArmLaunchControl,
+    FireNitrous,
+    SetNeutral,
+    RestartEngine,
+    ReplaceTires,
+    ToggleHeadlights,
+    SetCruise(f64),
+    CancelCruise,
+    SetSeed(u64)
+}
+
+impl Action {
+    /// Does the action on `state`. Returns false when it had no effect,
+    /// like shifting past the top gear.
+    pub fn apply(self, state: &mut State) -> bool {
+        match self {
+            Action::ShiftUp => return state.shift_up(),
+            Action::ShiftDown => return state.shift_down(),
+            Action::ArmLaunchControl => return state.arm_launch_control(),
+            Action::FireNitrous => return state.fire_nitrous(),
+            Action::SetNeutral => state.set_neutral(),
+            Action::RestartEngine => state.restart_engine(),
+            Action::ReplaceTires => state.replace_tires(),
+            Action::ToggleHeadlights => state.toggle_headlights(),
+            Action::SetCruise(target_speed) => return state.set_cruise(target_speed),
+            Action::CancelCruise => state.cancel_cruise(),
+            Action::SetSeed(seed) => state.set_seed(seed)
+        }
+        true
+    }
+}
+
+/// A run as its starting state plus what the driver did every tick.
+///
+/// It is replayed by simulating the run again, which only gives the same
+/// run because [`update`](crate::update) is deterministic: the same state, input and `dt`
+/// always give the same next state, on every platform.
+#[wasm_bindgen]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Replay {
+    initial: State,
+    tick_duration: f64,
+    /// Pedals of every tick, in order.
+    inputs: Vec<Input>,
+    /// Actions with the number of ticks run before each.
+    actions: Vec<(u32, Action)>
+}
+
+#[wasm_bindgen]
+impl Replay {
+    /// Number of ticks recorded.
+    pub fn ticks(&self) -> u32 {
+        self.inputs.len() as u32
+    }
+
+    pub fn tick_duration(&self) -> f64 {
+        self.tick_duration
+    }
+
+    pub fn initial_state(&self) -> State {
+        self.initial.clone()
+    }
+
+    /// State after the first `ticks` ticks of the run, re-simulated.
+    pub fn state_at(&self, ticks: u32) -> State {
+        let mut playback = Playback::new(self.clone());
+        while playback.tick < ticks && playback.step() {}
+        playback.state
+    }
+
+    /// State at the end of the recording.
+    pub fn final_state(&self) -> State {
+        self.state_at(self.ticks())
+    }
+
+    /// The replay in the compact binary encoding, a `Uint8Array` on the JS
+    /// side, to store it or share it.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        binary::to_bytes(self).expect("replay should encode to bytes")
+    }
+
+    /// Replay encoded with [`Replay::to_bytes`], or why `bytes` aren't one.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Replay, String> {
+        binary::from_bytes(bytes).map_err(|error| error.to_string())
+    }
+}
+
+impl Replay {
+    /// Empty recording of a run starting from `initial`.
+    pub fn new(initial: State, tick_duration: f64) -> Replay {
+        Replay {
+            initial,
+            tick_duration,
+            inputs: Vec::new(),
+            actions: Vec::new()
+        }
+    }
+
+    /// Records the pedals of the next tick.
+    pub fn record_input(&mut self, input: Input) {
+        self.inputs.push(input);
+    }
+
+    /// Records an action done before the next tick.
+    pub fn record_action(&mut self, action: Action) {
+        self.actions.push((self.ticks(), action));
+    }
+
+    /// Keeps only the first `ticks` ticks, and the actions done before the
+    /// next one.
+    pub fn truncate(&mut self, ticks: u32) {
+        self.inputs.truncate(ticks as usize);
+        self.actions.retain(|(at, _)| *at <= ticks);
+    }
+}
+
+/// A replay being re-simulated one tick at a time, like a ghost car
+/// racing alongside live play.
+#[derive(Clone)]
+pub struct Playback {
+    replay: Replay,
+    state: State,
+    /// Ticks re-simulated so far.
+    tick: u32,
+    /// Index of the first action not done yet.
+    next_action: usize
+}
+
+impl Playback {
+    pub fn new(replay: Replay) -> Playback {
+        Playback {
+            state: replay.initial.clone(),
+            replay,
+            tick: 0,
+            next_action: 0
+        }
+    }
+
+    /// Re-simulated state of the tick reached.
+    pub fn state(&self) -> &State {
+        &self.state
+    }
+
+    /// Re-simulates the next tick. Returns false, leaving the state as it
+    /// is, once the recording is over.
+    pub fn step(&mut self) -> bool {
+        let input = match self.replay.inputs.get(self.tick as usize) {
+            Some(input) => *input,
+            None => return false
+        };
+        while let Some((_, action)) = self.replay.actions.get(self.next_action).filter(|(at, _)| *at == self.tick) {
+            action.apply(&mut self.state);
+            self.next_action += 1;
+        }
+        self.state.step(input, self.replay.tick_duration);
+        self.tick += 1;
+        true
+    }
+
+    /// Starts the replay over.
+    pub fn rewind(&mut self) {
+        *self = Playback::new(self.replay.clone());
+    }
+
+    /// Moves to the state after `tick` ticks, or the end of the recording.
+    pub fn seek(&mut self, tick: u32) {
+        if tick < self.tick {
+            self.rewind();
+        }
+        while self.tick < tick && self.step() {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::update;
+
+    #[test]
+    fn replay_should_reproduce_the_recorded_run(){
+        let mut state = State::new();
+        let mut replay = Replay::new(state.clone(), 0.1);
+        for tick in 0..50 {
+            if tick == 20 {
+                replay.record_action(Action::SetCruise(3.0));
+                Action::SetCruise(3.0).apply(&mut state);
+            }
+            let input = Input::new(if tick < 30 { 1.0 } else { 0.0 }, 0.0);
+            replay.record_input(input);
+            state = update(state, input, 0.1);
+        }
+
+        let replayed = replay.final_state();
+        assert_eq!(state.position, replayed.position);
+        assert_eq!(state.speed, replayed.speed);
+        assert_eq!(state.cruise_speed, replayed.cruise_speed);
+    }
+
+    #[test]
+    fn state_at_should_stop_part_way(){
+        let mut replay = Replay::new(State::new(), 0.1);
+        for _ in 0..10 {
+            replay.record_input(Input::new(1.0, 0.0));
+        }
+
+        assert_eq!(5, replay.state_at(5).ticks);
+        assert_eq!(10, replay.final_state().ticks);
+    }
+
+    #[test]
+    fn replay_should_round_trip_through_bytes(){
+        let mut replay = Replay::new(State::new(), 0.1);
+        replay.record_action(Action::SetSeed(4));
+        replay.record_input(Input::new(1.0, 0.0));
+        let decoded = Replay::from_bytes(&replay.to_bytes()).unwrap();
+
+        assert_eq!(replay.inputs, decoded.inputs);
+        assert_eq!(replay.actions, decoded.actions);
+        assert_eq!(replay.final_state().to_json(), decoded.final_state().to_json());
+    }
+
+    #[test]
+    fn truncate_should_drop_later_ticks_and_actions(){
+        let mut replay = Replay::new(State::new(), 0.1);
+        for tick in 0..4 {
+            replay.record_action(Action::SetCruise(tick as f64));
+            replay.record_input(Input::new(1.0, 0.0));
+        }
+        replay.truncate(2);
+
+        assert_eq!(2, replay.ticks());
+        assert_eq!(vec![(0, Action::SetCruise(0.0)), (1, Action::SetCruise(1.0)), (2, Action::SetCruise(2.0))], replay.actions);
+    }
+
+    #[test]
+    fn playback_should_stop_at_the_end_of_the_recording(){
+        let mut replay = Replay::new(State::new(), 0.1);
+        replay.record_input(Input::new(1.0, 0.0));
+        let mut playback = Playback::new(replay);
+
+        assert!(playback.step());
+        assert!(!playback.step());
+        assert_eq!(1, playback.state().ticks);
+    }
+}
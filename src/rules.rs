@@ -0,0 +1,183 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::State;
+
+/// Total change of acceleration, in m/s², that halves the comfort score.
+pub const COMFORT_SCALE: f64 = 10.0;
+
+/// Discomfort added for each m/s of speed the suspension bottoms out with.
+pub const LANDING_DISCOMFORT: f64 = 10.0;
+
+/// Comfort score out of 100 for a run with `discomfort`, the total change
+/// of acceleration so far. A perfectly smooth run scores 100.
+pub fn comfort_score(discomfort: f64) -> f64 {
+    100.0 / (1.0 + discomfort / COMFORT_SCALE)
+}
+
+/// Why a run was lost.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LoseReason {
+    /// The run is not lost.
+    #[default]
+    None,
+    /// The car went past the end of the goal zone.
+    Overshoot,
+    /// The tank ran dry and the car stopped short of the goal zone.
+    OutOfFuel,
+    /// The battery ran flat and the car stopped short of the goal zone.
+    OutOfCharge,
+    /// The car ran into a parked car or rear-ended traffic.
+    Collision,
+    /// The trailer pushed the car too hard under braking and swung round.
+    Jackknife,
+    /// The car entered the goal zone faster than the level allows.
+    TooFast,
+    /// Cones and harsh landings damaged the car beyond repair.
+    Wrecked,
+    /// The time trial's target time ran out before the car stopped in the
+    /// goal zone.
+    TimeOut,
+    /// The car reached a checkpoint after its split time.
+    SplitMissed,
+    /// The car reversed behind where it started.
+    BehindStart
+}
+
+/// What driving past the end of the goal zone does, picked per level.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OvershootPolicy {
+    /// Losing at once, even when the car could reverse back in.
+    #[default]
+    InstantLoss,
+    /// Losing [`OVERSHOOT_PENALTY`](crate::OVERSHOOT_PENALTY) points each
+    /// time, the car being free to come back in.
+    Penalty,
+    /// Losing once the car stays past the zone for more than the level's
+    /// grace ticks.
+    GraceWindow
+}
+
+/// Ways of losing a level enables, so levels can combine several.
+/// Collisions, jackknifing, entering the goal zone too fast and missed
+/// splits always lose, as they come with the level's traffic, trailer,
+/// entry speed limit and checkpoints.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LoseConditions {
+    /// Driving past the end of the goal zone, when and how often as the
+    /// level's [`OvershootPolicy`] says.
+    pub overshoot: bool,
+    /// Running out of the target time.
+    pub time_out: bool,
+    /// Stopping short of the goal zone with an empty tank or a flat battery.
+    pub fuel_empty: bool,
+    /// Damaging the car beyond repair.
+    pub wrecked: bool,
+    /// Reversing behind the start.
+    pub behind_start: bool
+}
+
+#[wasm_bindgen]
+impl LoseConditions {
+    #[wasm_bindgen(constructor)]
+    pub fn new(overshoot: bool, time_out: bool, fuel_empty: bool, wrecked: bool, behind_start: bool) -> LoseConditions {
+        LoseConditions {
+            overshoot,
+            time_out,
+            fuel_empty,
+            wrecked,
+            behind_start
+        }
+    }
+}
+
+/// Every condition but reversing behind the start.
+impl Default for LoseConditions {
+    fn default() -> LoseConditions {
+        LoseConditions::new(true, true, true, true, false)
+    }
+}
+
+/// What stopping in the goal zone has to achieve to win, picked per level.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WinCondition {
+    /// Stopping anywhere in the goal zone.
+    #[default]
+    StopInZone,
+    /// Stopping in the goal zone by the level's target time.
+    StopWithinTime,
+    /// Stopping in the goal zone with at least the level's energy reserve
+    /// left in the tank, or in the battery of cars without one.
+    StopWithFuelRemaining,
+    /// Stopping within the level's position tolerance of the middle of the
+    /// goal zone.
+    ExactPosition
+}
+
+impl WinCondition {
+    /// Whether a car stopped in the goal zone in `state` meets the
+    /// condition.
+    pub fn is_met(self, state: &State) -> bool {
+        match self {
+            WinCondition::StopInZone => true,
+            WinCondition::StopWithinTime => state.elapsed_time <= state.target_time,
+            WinCondition::StopWithFuelRemaining => {
+                let energy = if state.vehicle.uses_fuel() { state.fuel } else { state.battery };
+                energy >= state.energy_reserve
+            }
+            WinCondition::ExactPosition => {
+                let centre = (state.position_goal_start + state.position_goal_end) / 2.0;
+                (state.trailer_position() - centre).abs() <= state.position_tolerance
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::{Powertrain, Vehicle};
+
+    #[test]
+    fn comfort_score_should_drop_as_discomfort_grows(){
+        assert_eq!(100.0, comfort_score(0.0));
+        assert_eq!(50.0, comfort_score(COMFORT_SCALE));
+        assert_eq!(25.0, comfort_score(3.0 * COMFORT_SCALE));
+    }
+
+    #[test]
+    fn stop_in_zone_should_always_be_met(){
+        assert!(WinCondition::StopInZone.is_met(&State::new()));
+    }
+
+    #[test]
+    fn stop_within_time_should_need_the_target_time(){
+        let state = State { elapsed_time: 20.0, target_time: 10.0, ..State::new() };
+
+        assert!(!WinCondition::StopWithinTime.is_met(&state));
+        assert!(WinCondition::StopWithinTime.is_met(&State { target_time: 30.0, ..state }));
+    }
+
+    #[test]
+    fn stop_with_fuel_remaining_should_need_the_reserve(){
+        let vehicle = Vehicle { powertrain: Powertrain::Combustion, fuel_consumption: 1.0, ..Vehicle::default() };
+        let state = State { vehicle, fuel: 2.0, energy_reserve: 5.0, ..State::new() };
+
+        assert!(!WinCondition::StopWithFuelRemaining.is_met(&state));
+        assert!(WinCondition::StopWithFuelRemaining.is_met(&State { fuel: 5.0, ..state }));
+    }
+
+    #[test]
+    fn exact_position_should_need_the_middle_of_the_zone(){
+        let state = State { position: 9502.0, position_tolerance: 1.0, ..State::new() };
+
+        assert!(!WinCondition::ExactPosition.is_met(&state));
+        assert!(WinCondition::ExactPosition.is_met(&State { position: 9500.5, ..state }));
+    }
+}
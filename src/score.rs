@@ -0,0 +1,153 @@
+use wasm_bindgen::prelude::*;
+
+use crate::goal::zone_at;
+use crate::{GoalZone, OvershootPolicy, State};
+
+/// Points for stopping dead on the centre of the goal zone, none at its
+/// edges.
+pub const PRECISION_POINTS: f64 = 1000.0;
+
+/// Points for finishing instantly, halved by every `TIME_SCALE` seconds
+/// the run takes.
+pub const TIME_POINTS: f64 = 1000.0;
+
+/// Seconds of run time that halve the time points.
+pub const TIME_SCALE: f64 = 60.0;
+
+/// Points lost for each metre driven in reverse.
+pub const REVERSING_PENALTY: f64 = 10.0;
+
+/// Points lost for each obstacle hit.
+pub const COLLISION_PENALTY: f64 = 100.0;
+
+/// Points lost each time the car goes past the goal zone under
+/// [`OvershootPolicy::Penalty`].
+pub const OVERSHOOT_PENALTY: f64 = 200.0;
+
+/// How a won run scored, part by part, for the results screen.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ScoreBreakdown {
+    pub precision: f64,
+    pub time: f64,
+    pub reversing_penalty: f64,
+    pub collision_penalty: f64,
+    pub overshoot_penalty: f64
+}
+
+#[wasm_bindgen]
+impl ScoreBreakdown {
+    /// Bonuses minus penalties, never below 0.
+    pub fn total(&self) -> f64 {
+        (self.precision + self.time - self.reversing_penalty - self.collision_penalty - self.overshoot_penalty).max(0.0)
+    }
+}
+
+/// Stars, from 1 to 3, a win scoring `total` earns against a level's
+/// thresholds.
+pub fn stars(total: f64, two_star_score: f64, three_star_score: f64) -> u32 {
+    if total >= three_star_score {
+        3
+    } else if total >= two_star_score {
+        2
+    } else {
+        1
+    }
+}
+
+/// Score of `state` as it stands, whether or not the run is won. Precision
+/// is measured for the trailer, the car itself without one, in the goal
+/// zone it is in, the main one when it is in none.
+pub fn score(state: &State) -> ScoreBreakdown {
+    let goal_position = state.trailer_position();
+    let zone = zone_at(&state.goal_zones(), goal_position)
+        .unwrap_or_else(|| GoalZone::new(state.position_goal_start, state.position_goal_end, 1.0));
+    let centre = (zone.start + zone.end) / 2.0;
+    let half_width = (zone.end - zone.start) / 2.0;
+    let off_centre = (goal_position - centre).abs() / half_width;
+    ScoreBreakdown {
+        precision: PRECISION_POINTS * zone.value * (1.0 - off_centre).max(0.0),
+        time: TIME_POINTS * 0.5f64.powf(state.elapsed_time / TIME_SCALE),
+        reversing_penalty: REVERSING_PENALTY * state.reverse_distance,
+        collision_penalty: COLLISION_PENALTY * state.obstacles_hit as f64,
+        overshoot_penalty: if state.overshoot_policy == OvershootPolicy::Penalty {
+            OVERSHOOT_PENALTY * state.overshoots as f64
+        } else {
+            0.0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::Trailer;
+
+    fn stopped_at(position: f64) -> State {
+        State { position, position_goal_start: 100.0, position_goal_end: 200.0, ..Default::default() }
+    }
+
+    #[test]
+    fn stopping_on_the_centre_should_score_full_precision(){
+        assert_eq!(PRECISION_POINTS, score(&stopped_at(150.0)).precision);
+        assert_eq!(PRECISION_POINTS / 2.0, score(&stopped_at(175.0)).precision);
+        assert_eq!(0.0, score(&stopped_at(200.0)).precision);
+    }
+
+    #[test]
+    fn precision_should_be_measured_for_the_trailer(){
+        let state = State { trailer: Trailer { length: 30.0, ..Trailer::default() }, ..stopped_at(180.0) };
+
+        assert_eq!(PRECISION_POINTS, score(&state).precision);
+    }
+
+    #[test]
+    fn time_points_should_halve_every_time_scale(){
+        let state = State { elapsed_time: TIME_SCALE, ..stopped_at(150.0) };
+        assert_eq!(TIME_POINTS / 2.0, score(&state).time);
+
+        let state = State { elapsed_time: 2.0 * TIME_SCALE, ..stopped_at(150.0) };
+        assert_eq!(TIME_POINTS / 4.0, score(&state).time);
+    }
+
+    #[test]
+    fn reversing_and_collisions_should_cost_points(){
+        let state = State { reverse_distance: 2.0, obstacles_hit: 3, ..stopped_at(150.0) };
+        let breakdown = score(&state);
+
+        assert_eq!(2.0 * REVERSING_PENALTY, breakdown.reversing_penalty);
+        assert_eq!(3.0 * COLLISION_PENALTY, breakdown.collision_penalty);
+        assert_eq!(PRECISION_POINTS + TIME_POINTS - 2.0 * REVERSING_PENALTY - 3.0 * COLLISION_PENALTY, breakdown.total());
+    }
+
+    #[test]
+    fn extra_goal_zones_should_be_worth_their_value(){
+        let state = State { extra_goals: vec![GoalZone::new(0.0, 50.0, 0.5)], ..stopped_at(25.0) };
+
+        assert_eq!(PRECISION_POINTS / 2.0, score(&state).precision);
+    }
+
+    #[test]
+    fn stars_should_follow_the_thresholds(){
+        assert_eq!(1, stars(0.0, 10.0, 20.0));
+        assert_eq!(2, stars(10.0, 10.0, 20.0));
+        assert_eq!(3, stars(25.0, 10.0, 20.0));
+    }
+
+    #[test]
+    fn total_should_never_be_negative(){
+        let state = State { obstacles_hit: 100, ..stopped_at(100.0) };
+
+        assert_eq!(0.0, score(&state).total());
+    }
+
+    #[test]
+    fn overshoots_should_cost_points_only_under_the_penalty_policy(){
+        let state = State { overshoots: 2, ..stopped_at(150.0) };
+
+        assert_eq!(0.0, score(&state).overshoot_penalty);
+        let state = State { overshoot_policy: OvershootPolicy::Penalty, ..state };
+        assert_eq!(2.0 * OVERSHOOT_PENALTY, score(&state).overshoot_penalty);
+    }
+}
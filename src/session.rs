@@ -0,0 +1,206 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::State;
+
+/// Statistics over every run of a level since it was loaded. They survive
+/// restarting the level.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Session {
+    /// Runs started, including the current one.
+    pub attempts: u32,
+    pub wins: u32,
+    pub losses: u32
+}
+
+#[wasm_bindgen]
+impl Session {
+    /// The statistics as JSON, for saving them.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("session should serialize to JSON")
+    }
+
+    /// Statistics saved with [`Session::to_json`], or why `json` isn't
+    /// them.
+    pub fn from_json(json: &str) -> Result<Session, String> {
+        serde_json::from_str(json).map_err(|error| error.to_string())
+    }
+}
+
+impl Session {
+    /// The statistics once a run that ended `won` or `lost`, or neither
+    /// when abandoned, is replaced by a new attempt.
+    pub fn next_attempt(&self, won: bool, lost: bool) -> Session {
+        Session {
+            attempts: self.attempts + 1,
+            wins: self.wins + won as u32,
+            losses: self.losses + lost as u32
+        }
+    }
+}
+
+/// Best results on one level over the runs of a play session.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LevelStats {
+    /// Level the results are for, as the host page numbers levels.
+    pub level: u32,
+    pub attempts: u32,
+    pub wins: u32,
+    /// Shortest winning run, in seconds. `f64::MAX` before the first win.
+    pub best_time: f64,
+    /// Most precision points a win scored, 0 before the first win.
+    pub best_precision: f64
+}
+
+#[wasm_bindgen]
+impl LevelStats {
+    /// Share of the attempts won, from 0 to 1.
+    pub fn win_rate(&self) -> f64 {
+        if self.attempts == 0 {
+            0.0
+        } else {
+            self.wins as f64 / self.attempts as f64
+        }
+    }
+}
+
+impl LevelStats {
+    fn new(level: u32) -> LevelStats {
+        LevelStats {
+            level,
+            attempts: 0,
+            wins: 0,
+            best_time: f64::MAX,
+            best_precision: 0.0
+        }
+    }
+}
+
+/// Best results on every level played in a session, for the host page to
+/// keep across visits with [`SessionStats::to_json`].
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SessionStats {
+    /// Results per level, by level.
+    levels: Vec<LevelStats>
+}
+
+#[wasm_bindgen]
+impl SessionStats {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> SessionStats {
+        SessionStats::default()
+    }
+
+    /// Counts the run of `level` that ended in `state`, won, lost or
+    /// abandoned.
+    pub fn record(&mut self, level: u32, state: &State) {
+        let index = self.levels.partition_point(|stats| stats.level < level);
+        if self.levels.get(index).is_none_or(|stats| stats.level != level) {
+            self.levels.insert(index, LevelStats::new(level));
+        }
+        let stats = &mut self.levels[index];
+        stats.attempts += 1;
+        if let Some(score) = state.score() {
+            stats.wins += 1;
+            stats.best_time = stats.best_time.min(state.elapsed_time);
+            stats.best_precision = stats.best_precision.max(score.precision);
+        }
+    }
+
+    /// Results on `level`, if it was played.
+    pub fn level(&self, level: u32) -> Option<LevelStats> {
+        self.levels.iter().copied().find(|stats| stats.level == level)
+    }
+
+    /// Results on every level played, by level.
+    #[wasm_bindgen(getter)]
+    pub fn levels(&self) -> Vec<LevelStats> {
+        self.levels.clone()
+    }
+
+    /// The results as JSON, for saving them.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("session stats should serialize to JSON")
+    }
+
+    /// Results saved with [`SessionStats::to_json`], in any level order,
+    /// or why `json` isn't them.
+    pub fn from_json(json: &str) -> Result<SessionStats, String> {
+        let mut stats: SessionStats = serde_json::from_str(json).map_err(|error| error.to_string())?;
+        stats.levels.sort_by_key(|stats| stats.level);
+        if let Some(pair) = stats.levels.windows(2).find(|pair| pair[0].level == pair[1].level) {
+            return Err(format!("level {} is listed more than once", pair[0].level));
+        }
+        Ok(stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn next_attempt_should_tally_the_outcome_of_the_run(){
+        let session = Session { attempts: 1, ..Default::default() };
+
+        assert_eq!(Session { attempts: 2, wins: 1, losses: 0 }, session.next_attempt(true, false));
+        assert_eq!(Session { attempts: 2, wins: 0, losses: 1 }, session.next_attempt(false, true));
+        assert_eq!(Session { attempts: 2, wins: 0, losses: 0 }, session.next_attempt(false, false));
+    }
+
+    #[test]
+    fn session_should_round_trip_through_json(){
+        let session = Session { attempts: 5, wins: 3, losses: 2 };
+
+        assert_eq!(Ok(session), Session::from_json(&session.to_json()));
+    }
+
+    #[test]
+    fn stats_should_keep_the_best_results_of_each_level(){
+        let mut stats = SessionStats::new();
+        let won = |position, elapsed_time| State { position, elapsed_time, won: true, ..State::new() };
+        stats.record(2, &won(9500.0, 40.0));
+        stats.record(2, &won(9700.0, 30.0));
+        stats.record(2, &State { lost: true, ..State::new() });
+        stats.record(0, &State::new());
+
+        let level = stats.level(2).unwrap();
+        assert_eq!(3, level.attempts);
+        assert_eq!(2, level.wins);
+        assert_eq!(30.0, level.best_time);
+        assert_eq!(1000.0, level.best_precision);
+        assert_eq!(2.0 / 3.0, level.win_rate());
+        assert_eq!(vec![0, 2], stats.levels().iter().map(|stats| stats.level).collect::<Vec<_>>());
+        assert_eq!(None, stats.level(1));
+    }
+
+    #[test]
+    fn stats_should_round_trip_through_json(){
+        let mut stats = SessionStats::new();
+        stats.record(1, &State { position: 9500.0, won: true, ..State::new() });
+
+        assert_eq!(Ok(stats.clone()), SessionStats::from_json(&stats.to_json()));
+    }
+    #[test]
+    fn stats_from_json_should_sort_the_levels_so_recording_still_finds_them(){
+        let level = |level| LevelStats { attempts: 1, ..LevelStats::new(level) };
+        let json = serde_json::json!({ "levels": [level(3), level(1)] }).to_string();
+        let mut stats = SessionStats::from_json(&json).unwrap();
+        stats.record(3, &State::new());
+        stats.record(2, &State::new());
+
+        assert_eq!(vec![1, 2, 3], stats.levels().iter().map(|stats| stats.level).collect::<Vec<_>>());
+        assert_eq!(2, stats.level(3).unwrap().attempts);
+    }
+
+    #[test]
+    fn stats_from_json_should_reject_a_level_listed_twice(){
+        let json = serde_json::json!({ "levels": [LevelStats::new(1), LevelStats::new(1)] }).to_string();
+
+        assert_eq!(Err("level 1 is listed more than once".to_string()), SessionStats::from_json(&json));
+    }
+}
@@ -0,0 +1,682 @@
+use std::collections::VecDeque;
+
+use js_sys::Function;
+use wasm_bindgen::prelude::*;
+
+use crate::replay::{Action, Playback};
+use crate::{autopilot, Achievements, Camera, CameraTarget, Event, GameLoop, Hint, History, Input, InputQueue, Pedal, Replay, State, Tutorial};
+
+/// Fixed-timestep driver around [`update`](crate::update).
+///
+/// The front end calls [`Simulation::advance`] once per animation frame with
+/// the current wall-clock time; the physics is stepped at `tick_rate` ticks
+/// per second whatever the frame rate, so a run is deterministic and is
+/// recorded as a [`Replay`].
+#[wasm_bindgen]
+pub struct Simulation {
+    state: State,
+    clock: GameLoop,
+    input: Input,
+    /// Pedal moves waiting for the tick they were made in.
+    queue: InputQueue,
+    /// When set, the autopilot drives instead of the player's inputs.
+    autopilot: bool,
+    events: Vec<Event>,
+    replay: Replay,
+    /// Earlier run re-simulated alongside, drawn as a ghost car.
+    ghost: Option<Playback>,
+    /// Tick of the run the ghost's tick 0 lines up with.
+    ghost_start: u32,
+    /// States before each of the latest ticks, oldest first, to rewind to.
+    history: VecDeque<State>,
+    /// Most ticks kept in `history`. 0 disables rewinding.
+    rewind_capacity: usize,
+    /// Every tick's state, when recording for post-run analysis.
+    recording: Option<History>,
+    /// JS callbacks and the event each one is called on.
+    listeners: Vec<(Event, Function)>,
+    achievements: Achievements,
+    /// Set in tutorial mode, watching the run for hints.
+    tutorial: Option<Tutorial>,
+    hints: Vec<Hint>,
+    camera: Camera,
+    /// What the camera follows every tick, and the index of the traffic
+    /// car for [`CameraTarget::Traffic`]. Nothing leaves it where it is.
+    camera_target: Option<(CameraTarget, u32)>,
+    /// Share of the gap to its target the camera closes each tick.
+    camera_smoothing: f64
+}
+
+#[wasm_bindgen]
+impl Simulation {
+    #[wasm_bindgen(constructor)]
+    pub fn new(state: State, tick_rate: f64) -> Simulation {
+        let clock = GameLoop::new(tick_rate);
+        Simulation {
+            replay: Replay::new(state.clone(), clock.tick_duration()),
+            state,
+            clock,
+            input: Input::default(),
+            queue: InputQueue::new(),
+            autopilot: false,
+            events: Vec::new(),
+            ghost: None,
+            ghost_start: 0,
+            history: VecDeque::new(),
+            rewind_capacity: 0,
+            recording: None,
+            listeners: Vec::new(),
+            achievements: Achievements::new(),
+            tutorial: None,
+            hints: Vec::new(),
+            camera: Camera::default(),
+            camera_target: None,
+            camera_smoothing: 1.0
+        }
+    }
+
+    pub fn set_throttle(&mut self, throttle: f64) {
+        self.input.throttle = throttle;
+    }
+
+    pub fn set_brake(&mut self, brake: f64) {
+        self.input.brake = brake;
+    }
+
+    pub fn set_clutch(&mut self, clutch: f64) {
+        self.input.clutch = clutch;
+    }
+
+    /// Queues moving `pedal` to `position` at wall-clock time `time_ms`,
+    /// like a key event's `timeStamp`, on the clock fed to
+    /// [`Simulation::advance`]. It takes effect from the first tick
+    /// starting after it.
+    pub fn queue_input(&mut self, time_ms: f64, pedal: Pedal, position: f64) {
+        self.queue.push(time_ms, pedal, position);
+    }
+
+    pub fn shift_up(&mut self) -> bool {
+        self.act(Action::ShiftUp)
+    }
+
+    pub fn shift_down(&mut self) -> bool {
+        self.act(Action::ShiftDown)
+    }
+
+    pub fn arm_launch_control(&mut self) -> bool {
+        self.act(Action::ArmLaunchControl)
+    }
+
+    pub fn fire_nitrous(&mut self) -> bool {
+        self.act(Action::FireNitrous)
+    }
+
+    /// Restarts the level after a loss or to retry, keeping the session
+    /// statistics. Pending events of the abandoned run are dropped.
+    pub fn restart_level(&mut self) {
+        self.state.restart_level();
+        self.events.clear();
+        self.replay = Replay::new(self.state.clone(), self.tick_duration());
+        self.history.clear();
+        if let Some(ghost) = &mut self.ghost {
+            ghost.rewind();
+        }
+        self.ghost_start = 0;
+        if let Some(recording) = &mut self.recording {
+            *recording = History::new();
+            recording.record(&self.state);
+        }
+    }
+
+    /// Turns tutorial mode, which hands out hints as the run goes, on or
+    /// off.
+    pub fn set_tutorial(&mut self, enabled: bool) {
+        self.tutorial = if enabled { Some(Tutorial::new()) } else { None };
+        self.hints.clear();
+    }
+
+    /// Hints given since the last call, oldest first.
+    pub fn drain_hints(&mut self) -> Vec<Hint> {
+        std::mem::take(&mut self.hints)
+    }
+
+    /// Achievements unlocked over every run of the simulation.
+    pub fn achievements(&self) -> Achievements {
+        self.achievements.clone()
+    }
+
+    /// Carries on unlocking from `achievements`, say ones saved on an
+    /// earlier visit.
+    pub fn set_achievements(&mut self, achievements: Achievements) {
+        self.achievements = achievements;
+    }
+
+    /// Starts recording every tick's state from the current one on, or
+    /// stops and forgets the recording.
+    pub fn record_history(&mut self, enabled: bool) {
+        self.recording = if enabled {
+            let mut recording = History::new();
+            recording.record(&self.state);
+            Some(recording)
+        } else {
+            None
+        };
+    }
+
+    /// Every tick's state since recording started, for post-run analysis.
+    pub fn history(&self) -> Option<History> {
+        self.recording.clone()
+    }
+
+    /// Keeps the last `seconds` of the run to rewind, for casual modes that
+    /// let players undo an overshoot. 0, the default, keeps none.
+    pub fn set_rewind_seconds(&mut self, seconds: f64) {
+        self.rewind_capacity = (seconds.max(0.0) * self.clock.tick_rate()).round() as usize;
+        while self.history.len() > self.rewind_capacity {
+            self.history.pop_front();
+        }
+    }
+
+    /// Undoes the last `ticks` ticks, as far back as the rewind buffer
+    /// goes. Returns the number of ticks undone.
+    pub fn rewind(&mut self, ticks: u32) -> u32 {
+        let undone = (ticks as usize).min(self.history.len());
+        if undone == 0 {
+            return 0;
+        }
+        self.history.truncate(self.history.len() - undone + 1);
+        if let Some(state) = self.history.pop_back() {
+            self.state = state;
+        }
+        let tick = self.replay.ticks() - undone as u32;
+        self.replay.truncate(tick);
+        if let Some(ghost) = &mut self.ghost {
+            ghost.seek(tick.saturating_sub(self.ghost_start));
+        }
+        if let Some(recording) = &mut self.recording {
+            recording.truncate(self.state.ticks);
+        }
+        undone as u32
+    }
+
+    /// Races the run in `replay`, like the best one so far, as a ghost car
+    /// alongside live play from the next tick on.
+    pub fn set_ghost(&mut self, replay: Replay) {
+        self.ghost = Some(Playback::new(replay));
+        self.ghost_start = self.replay.ticks();
+    }
+
+    pub fn clear_ghost(&mut self) {
+        self.ghost = None;
+    }
+
+    /// Position of the ghost car, for drawing it, or nothing without one.
+    /// It stays where its run ended once the replay is over.
+    pub fn ghost_position(&self) -> Option<f64> {
+        self.ghost.as_ref().map(|ghost| ghost.state().position)
+    }
+
+    /// Camera to draw the frame through, following its target.
+    pub fn camera(&self) -> Camera {
+        self.camera
+    }
+
+    pub fn set_camera(&mut self, camera: Camera) {
+        self.camera = camera;
+    }
+
+    /// Makes the camera follow `target` from the next tick on, closing
+    /// `smoothing` of the gap each tick. `index` picks the traffic car for
+    /// [`CameraTarget::Traffic`] and is ignored otherwise.
+    pub fn attach_camera(&mut self, target: CameraTarget, index: u32, smoothing: f64) {
+        self.camera_target = Some((target, index));
+        self.camera_smoothing = smoothing;
+    }
+
+    /// Leaves the camera where it is from the next tick on.
+    pub fn detach_camera(&mut self) {
+        self.camera_target = None;
+    }
+
+    /// Screen positions of everything to draw along the road, through the
+    /// simulation's camera, in one `Float32Array`: the player, the ghost
+    /// (NaN without one), the number of traffic cars and each of them,
+    /// then the number of obstacles and the two ends of each.
+    pub fn render_positions(&self) -> Vec<f32> {
+        let project = |position: f64| self.camera.project_precise(position) as f32;
+        let traffic = &self.state.traffic;
+        let obstacles = &self.state.track.obstacles;
+        let mut positions = Vec::with_capacity(4 + traffic.len() + 2 * obstacles.len());
+        positions.push(project(self.state.position));
+        positions.push(self.ghost_position().map_or(f32::NAN, project));
+        positions.push(traffic.len() as f32);
+        positions.extend(traffic.iter().map(|car| project(car.position)));
+        positions.push(obstacles.len() as f32);
+        positions.extend(obstacles.iter().flat_map(|obstacle| [project(obstacle.start), project(obstacle.end)]));
+        positions
+    }
+
+    /// Reseeds the run, for replays and shared seeds.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.act(Action::SetSeed(seed));
+    }
+
+    pub fn set_neutral(&mut self) {
+        self.act(Action::SetNeutral);
+    }
+
+    pub fn restart_engine(&mut self) {
+        self.act(Action::RestartEngine);
+    }
+
+    pub fn replace_tires(&mut self) {
+        self.act(Action::ReplaceTires);
+    }
+
+    pub fn toggle_headlights(&mut self) {
+        self.act(Action::ToggleHeadlights);
+    }
+
+    pub fn set_cruise(&mut self, target_speed: f64) -> bool {
+        self.act(Action::SetCruise(target_speed))
+    }
+
+    pub fn cancel_cruise(&mut self) {
+        self.act(Action::CancelCruise);
+    }
+
+    /// Hands the car over to the autopilot, for demo mode, or back to the
+    /// player.
+    pub fn set_autopilot(&mut self, enabled: bool) {
+        self.autopilot = enabled;
+    }
+
+    /// Freezes the run: [`Simulation::advance`] keeps the clock but runs no
+    /// ticks until [`Simulation::resume`].
+    pub fn pause(&mut self) {
+        self.clock.pause();
+    }
+
+    pub fn resume(&mut self) {
+        self.clock.resume();
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.clock.is_paused()
+    }
+
+    /// Simulated seconds per wall-clock second, for slow motion.
+    pub fn set_time_scale(&mut self, time_scale: f64) {
+        self.clock.set_time_scale(time_scale);
+    }
+
+    /// How far, from 0 to 1, the last frame got into the next tick, for
+    /// interpolating the drawing.
+    pub fn alpha(&self) -> f64 {
+        self.clock.alpha()
+    }
+
+    /// Duration of one physics tick, in seconds.
+    pub fn tick_duration(&self) -> f64 {
+        self.clock.tick_duration()
+    }
+
+    /// Feeds the wall-clock time in milliseconds (as given by
+    /// `performance.now()` or `requestAnimationFrame`) and runs as many fixed
+    /// ticks as it covers. Returns the number of ticks run.
+    pub fn advance(&mut self, now_ms: f64) -> u32 {
+        let ticks = self.clock.advance(now_ms);
+        for tick in 0..ticks {
+            let start = self.clock.tick_start(now_ms, tick, ticks);
+            self.input = self.queue.apply_until(self.input, start);
+            self.step();
+        }
+        ticks
+    }
+
+    /// Runs exactly one fixed tick, ignoring wall-clock time.
+    pub fn step(&mut self) {
+        let input = if self.autopilot { autopilot(&self.state) } else { self.input };
+        self.replay.record_input(input);
+        if self.rewind_capacity > 0 {
+            if self.history.len() == self.rewind_capacity {
+                self.history.pop_front();
+            }
+            self.history.push_back(self.state.clone());
+        }
+        self.state.step(input, self.tick_duration());
+        self.events.extend_from_slice(&self.state.events);
+        self.achievements.observe(&self.state);
+        if let Some(tutorial) = &mut self.tutorial {
+            self.hints.extend(tutorial.observe(&self.state));
+        }
+        for event in &self.state.events {
+            for (_, callback) in self.listeners.iter().filter(|(listened, _)| listened == event) {
+                let _ = callback.call1(&JsValue::NULL, &JsValue::from(*event));
+            }
+        }
+        if let Some(ghost) = &mut self.ghost {
+            ghost.step();
+        }
+        if let Some(recording) = &mut self.recording {
+            recording.record(&self.state);
+        }
+        if let Some((position, height, speed)) = self.camera_target.and_then(|(target, index)| self.target_point(target, index)) {
+            self.camera.lead_for(speed, self.camera_smoothing);
+            self.camera.follow(position, self.camera_smoothing);
+            self.camera.follow_height(height, self.camera_smoothing);
+        }
+        self.camera.shake_for(&self.state.events);
+        self.camera.step(self.tick_duration());
+    }
+
+    /// Calls `callback` with the event as soon as a tick fires `event`, like
+    /// `Event.Won`, on top of queueing it for [`Simulation::drain_events`].
+    /// A callback that throws doesn't stop the simulation.
+    pub fn add_listener(&mut self, event: Event, callback: Function) {
+        self.listeners.push((event, callback));
+    }
+
+    pub fn clear_listeners(&mut self) {
+        self.listeners.clear();
+    }
+
+    /// Events fired since the last call, oldest first.
+    pub fn drain_events(&mut self) -> Vec<Event> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// Copy of the latest simulated state.
+    pub fn state(&self) -> State {
+        self.state.clone()
+    }
+
+    /// Recording of the run since the simulation started or the level was
+    /// last restarted.
+    pub fn replay(&self) -> Replay {
+        self.replay.clone()
+    }
+}
+
+impl Simulation {
+    /// Where `target` is along the road, how high up and how fast it goes,
+    /// if it is there at all.
+    fn target_point(&self, target: CameraTarget, index: u32) -> Option<(f64, f64, f64)> {
+        match target {
+            CameraTarget::Player => Some((self.state.position, self.state.body_height(), self.state.speed)),
+            CameraTarget::Ghost => self.ghost.as_ref().map(|ghost| {
+                let state = ghost.state();
+                (state.position, state.body_height(), state.speed)
+            }),
+            CameraTarget::Traffic => self.state.traffic
+                .get(index as usize)
+                .map(|car| (car.position, self.state.track.height_at(car.position), car.speed))
+        }
+    }
+
+    /// Does `action` on the state and records it in the replay.
+    fn act(&mut self, action: Action) -> bool {
+        self.replay.record_action(action);
+        action.apply(&mut self.state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::ObstacleKind;
+
+    #[test]
+    fn first_advance_should_only_start_the_clock(){
+        let mut simulation = Simulation::new(State::default(), 60.0);
+
+        assert_eq!(0, simulation.advance(1000.0));
+    }
+
+    #[test]
+    fn advance_should_run_one_tick_per_elapsed_tick_duration(){
+        let mut simulation = Simulation::new(State::default(), 16.0);
+        simulation.advance(0.0);
+
+        assert_eq!(3, simulation.advance(200.0));
+    }
+
+    #[test]
+    fn leftover_time_should_carry_over_to_next_advance(){
+        let mut simulation = Simulation::new(State::default(), 16.0);
+        simulation.advance(0.0);
+        simulation.advance(100.0);
+
+        assert_eq!(1, simulation.advance(130.0));
+    }
+
+    #[test]
+    fn long_pauses_should_not_run_unbounded_catch_up_ticks(){
+        let mut simulation = Simulation::new(State::default(), 16.0);
+        simulation.advance(0.0);
+
+        assert_eq!(4, simulation.advance(60_000.0));
+    }
+
+    #[test]
+    fn queued_input_should_take_effect_on_the_tick_it_was_made_in(){
+        let mut simulation = Simulation::new(State::default(), 10.0);
+        simulation.advance(0.0);
+        simulation.queue_input(50.0, Pedal::Throttle, 1.0);
+        simulation.queue_input(250.0, Pedal::Throttle, 0.0);
+        simulation.advance(200.0);
+        simulation.advance(400.0);
+
+        let inputs: Vec<f64> = (1..=4).map(|tick| simulation.replay().state_at(tick).pedals.throttle).collect();
+        assert_eq!(vec![0.0, 1.0, 1.0, 0.0], inputs);
+    }
+
+    #[test]
+    fn paused_simulation_should_run_no_ticks(){
+        let mut simulation = Simulation::new(State::default(), 16.0);
+        simulation.advance(0.0);
+        simulation.pause();
+
+        assert_eq!(0, simulation.advance(200.0));
+        assert_eq!(0.0, simulation.state().elapsed_time);
+    }
+
+    #[test]
+    fn resumed_simulation_should_not_replay_paused_time(){
+        let mut simulation = Simulation::new(State::default(), 16.0);
+        simulation.advance(0.0);
+        simulation.pause();
+        simulation.advance(200.0);
+        simulation.resume();
+
+        assert_eq!(1, simulation.advance(270.0));
+    }
+
+    #[test]
+    fn frame_rate_should_not_change_simulated_state(){
+        let mut slow = Simulation::new(State::default(), 100.0);
+        let mut fast = Simulation::new(State::default(), 100.0);
+        slow.set_throttle(1.0);
+        fast.set_throttle(1.0);
+
+        for frame in 0..=60 {
+            slow.advance(frame as f64 * 1000.0 / 60.0);
+        }
+        for frame in 0..=144 {
+            fast.advance(frame as f64 * 1000.0 / 144.0);
+        }
+        assert_eq!(slow.state().position, fast.state().position);
+    }
+
+    #[test]
+    fn drain_events_should_collect_events_across_ticks_once(){
+        let state = State { speed: 10.0, speed_limit: 10.0, ..State::new() };
+        let mut simulation = Simulation::new(state, 16.0);
+        simulation.set_throttle(1.0);
+
+        simulation.step();
+        simulation.step();
+        assert_eq!(vec![Event::LimiterHit], simulation.drain_events());
+        assert!(simulation.drain_events().is_empty());
+    }
+
+    #[test]
+    fn replay_should_end_in_the_simulated_state(){
+        let mut simulation = Simulation::new(State::new(), 16.0);
+        simulation.set_throttle(1.0);
+        simulation.step();
+        simulation.set_cruise(2.0);
+        simulation.step();
+        simulation.set_autopilot(true);
+        simulation.step();
+
+        let replayed = simulation.replay().final_state();
+        assert_eq!(3, simulation.replay().ticks());
+        assert_eq!(simulation.state().position, replayed.position);
+        assert_eq!(simulation.state().speed, replayed.speed);
+    }
+
+    #[test]
+    fn ghost_should_follow_the_replayed_run(){
+        let mut best = Simulation::new(State::new(), 16.0);
+        best.set_throttle(1.0);
+        for _ in 0..10 {
+            best.step();
+        }
+        let mut simulation = Simulation::new(State::new(), 16.0);
+        simulation.set_ghost(best.replay());
+
+        assert_eq!(Some(State::new().position), simulation.ghost_position());
+        for _ in 0..20 {
+            simulation.step();
+        }
+        assert_eq!(Some(best.state().position), simulation.ghost_position());
+        assert_eq!(State::new().position, simulation.state().position);
+    }
+
+    #[test]
+    fn render_positions_should_pack_every_entity_on_the_screen(){
+        let mut state = State { position: 100.0, ..State::new() };
+        state.add_traffic(200.0, 0.0);
+        state.track.add_obstacle(ObstacleKind::Cone, 300.0);
+        let simulation = Simulation::new(state, 16.0);
+        let positions = simulation.render_positions();
+
+        assert_eq!(400.0, positions[0]);
+        assert!(positions[1].is_nan());
+        assert_eq!(vec![1.0, 300.0, 1.0, 200.0, 200.0 - ObstacleKind::Cone.length() as f32], positions[2..].to_vec());
+    }
+
+    #[test]
+    fn rewind_should_take_the_ghost_back_to_where_it_was_then(){
+        let mut best = Simulation::new(State::new(), 16.0);
+        best.set_throttle(1.0);
+        for _ in 0..20 {
+            best.step();
+        }
+        let mut simulation = Simulation::new(State::new(), 16.0);
+        simulation.set_rewind_seconds(1.0);
+        for _ in 0..5 {
+            simulation.step();
+        }
+        simulation.set_ghost(best.replay());
+        for _ in 0..8 {
+            simulation.step();
+        }
+        let ghost_position = simulation.ghost_position();
+        for _ in 0..4 {
+            simulation.step();
+        }
+
+        assert_eq!(4, simulation.rewind(4));
+        assert_eq!(ghost_position, simulation.ghost_position());
+    }
+
+    #[test]
+    fn attached_camera_should_follow_its_target_every_tick(){
+        let mut state = State::new();
+        state.speed = 10.0;
+        state.add_traffic(800.0, 5.0);
+        let mut simulation = Simulation::new(state, 10.0);
+
+        simulation.attach_camera(CameraTarget::Player, 0, 1.0);
+        simulation.step();
+        assert_eq!(501.0, simulation.camera().world_position);
+
+        simulation.attach_camera(CameraTarget::Traffic, 0, 1.0);
+        simulation.step();
+        assert_eq!(simulation.state().traffic()[0].position, simulation.camera().world_position);
+
+        let world_position = simulation.camera().world_position;
+        simulation.detach_camera();
+        simulation.step();
+        assert_eq!(world_position, simulation.camera().world_position);
+    }
+
+    #[test]
+    fn rewind_should_restore_an_earlier_state(){
+        let mut simulation = Simulation::new(State::new(), 10.0);
+        simulation.set_rewind_seconds(1.0);
+        simulation.set_throttle(1.0);
+        for _ in 0..5 {
+            simulation.step();
+        }
+        let earlier = simulation.state();
+        for _ in 0..3 {
+            simulation.step();
+        }
+
+        assert_eq!(3, simulation.rewind(3));
+        assert_eq!(earlier.position, simulation.state().position);
+        assert_eq!(5, simulation.replay().ticks());
+    }
+
+    #[test]
+    fn rewind_should_go_no_further_back_than_the_buffer(){
+        let mut simulation = Simulation::new(State::new(), 10.0);
+        simulation.set_rewind_seconds(0.2);
+        for _ in 0..5 {
+            simulation.step();
+        }
+
+        assert_eq!(2, simulation.rewind(10));
+        assert_eq!(0, simulation.rewind(1));
+        assert_eq!(3, simulation.state().ticks);
+    }
+
+    #[test]
+    fn rewind_should_be_off_by_default(){
+        let mut simulation = Simulation::new(State::new(), 10.0);
+        simulation.step();
+
+        assert_eq!(0, simulation.rewind(1));
+    }
+
+    #[test]
+    fn history_should_record_every_tick_once_enabled(){
+        let mut simulation = Simulation::new(State::new(), 10.0);
+        simulation.step();
+        assert!(simulation.history().is_none());
+
+        simulation.record_history(true);
+        simulation.set_throttle(1.0);
+        for _ in 0..4 {
+            simulation.step();
+        }
+        let history = simulation.history().unwrap();
+        assert_eq!(5, history.len());
+        assert_eq!(Some(simulation.state().speed), history.max_speed_between(1, 5));
+    }
+
+    #[test]
+    fn autopilot_should_drive_instead_of_player_inputs(){
+        let mut simulation = Simulation::new(State::new(), 16.0);
+        simulation.set_brake(1.0);
+        simulation.set_autopilot(true);
+
+        simulation.step();
+        simulation.step();
+        assert!(simulation.state().speed > 0.0);
+    }
+}
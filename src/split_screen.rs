@@ -0,0 +1,85 @@
+use wasm_bindgen::prelude::*;
+
+use crate::Camera;
+
+/// Cameras for local multiplayer, one per player, their viewports stacked
+/// top to bottom on a shared screen, each following its own car.
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct SplitScreen {
+    cameras: Vec<Camera>
+}
+
+#[wasm_bindgen]
+impl SplitScreen {
+    /// `players` viewports splitting a `screen_width` by `screen_height`
+    /// screen into equal bands, each showing `world_size` m of road.
+    #[wasm_bindgen(constructor)]
+    pub fn new(players: u32, screen_width: i32, screen_height: i32, world_size: i32) -> SplitScreen {
+        let band = screen_height / players.max(1) as i32;
+        let cameras = (0..players as i32)
+            .map(|player| {
+                let mut camera = Camera::new(screen_width, world_size);
+                camera.screen_height = band;
+                camera.screen_y = player * band;
+                camera
+            })
+            .collect();
+        SplitScreen { cameras }
+    }
+
+    pub fn players(&self) -> u32 {
+        self.cameras.len() as u32
+    }
+
+    /// Camera of `player`, from 0, for drawing their viewport.
+    pub fn camera(&self, player: u32) -> Option<Camera> {
+        self.cameras.get(player as usize).copied()
+    }
+
+    pub fn set_camera(&mut self, player: u32, camera: Camera) {
+        if let Some(slot) = self.cameras.get_mut(player as usize) {
+            *slot = camera;
+        }
+    }
+
+    /// Moves every camera on by a tick of `dt` seconds, following the car
+    /// of its player at `positions`, in player order.
+    pub fn follow(&mut self, positions: &[f64], smoothing: f64, dt: f64) {
+        for (camera, &position) in self.cameras.iter_mut().zip(positions) {
+            camera.follow(position, smoothing);
+            camera.step(dt);
+        }
+    }
+
+    /// Screen position of `world_position` in every viewport, in player
+    /// order, in fractional pixels.
+    pub fn project(&self, world_position: f64) -> Vec<f64> {
+        self.cameras.iter().map(|camera| camera.project_precise(world_position)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn viewports_should_split_the_screen_into_bands(){
+        let split = SplitScreen::new(2, 800, 600, 400);
+
+        assert_eq!(0, split.camera(0).unwrap().screen_y);
+        assert_eq!(300, split.camera(1).unwrap().screen_y);
+        assert_eq!(300, split.camera(1).unwrap().screen_height);
+        assert!(split.camera(2).is_none());
+    }
+
+    #[test]
+    fn each_camera_should_follow_its_own_car(){
+        let mut split = SplitScreen::new(2, 800, 600, 400);
+        split.follow(&[100.0, 300.0], 1.0, 0.1);
+
+        assert_eq!(vec![400.0, 800.0], split.project(100.0));
+        assert_eq!(vec![0.0, 400.0], split.project(300.0));
+    }
+}
@@ -0,0 +1,56 @@
+use wasm_bindgen::prelude::*;
+
+use crate::track::slope_deceleration;
+
+/// Distance a car moving at `speed` covers before standing still when
+/// braking with `braking` deceleration on dry flat road, on a road with the
+/// given `friction` and `gradient`. Rolling resistance and drag are left
+/// out, so the real stop is a little shorter.
+///
+/// `f64::MAX` when the brakes can't overcome the downhill pull.
+#[wasm_bindgen]
+pub fn stopping_distance(speed: f64, braking: f64, friction: f64, gradient: f64) -> f64 {
+    if speed == 0.0 {
+        return 0.0;
+    }
+    let deceleration = braking * friction + slope_deceleration(gradient) * speed.signum();
+    if deceleration <= 0.0 {
+        return f64::MAX;
+    }
+    speed * speed / (2.0 * deceleration)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn stopping_distance_should_grow_with_the_square_of_speed(){
+        assert_eq!(50.0, stopping_distance(10.0, 1.0, 1.0, 0.0));
+        assert_eq!(200.0, stopping_distance(20.0, 1.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn low_friction_should_lengthen_the_stop(){
+        assert_eq!(100.0, stopping_distance(10.0, 1.0, 0.5, 0.0));
+    }
+
+    #[test]
+    fn slopes_should_shorten_the_stop_uphill_and_lengthen_it_downhill(){
+        let flat = stopping_distance(10.0, 5.0, 1.0, 0.0);
+
+        assert!(stopping_distance(10.0, 5.0, 1.0, 0.1) < flat);
+        assert!(stopping_distance(10.0, 5.0, 1.0, -0.1) > flat);
+    }
+
+    #[test]
+    fn brakes_too_weak_for_the_downhill_should_never_stop(){
+        assert_eq!(f64::MAX, stopping_distance(10.0, 0.5, 1.0, -0.1));
+    }
+
+    #[test]
+    fn stopped_car_should_need_no_distance(){
+        assert_eq!(0.0, stopping_distance(0.0, 0.0, 1.0, -0.1));
+    }
+}
@@ -0,0 +1,105 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+/// Springs and dampers between the wheels and the body, modelled as one
+/// vertical degree of freedom: the body bounces up and down over bumps in
+/// the elevation profile.
+///
+/// The default is rigid: the body follows the road exactly and never
+/// bottoms out.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Suspension {
+    /// Spring rate per kg of car, in N/m/kg. 0 makes the car rigid.
+    pub stiffness: f64,
+    /// Damper rate per kg of car, in N·s/m/kg.
+    pub damping: f64,
+    /// Compression, in metres, at which the suspension bottoms out.
+    pub travel: f64
+}
+
+#[wasm_bindgen]
+impl Suspension {
+    #[wasm_bindgen(constructor)]
+    pub fn new(stiffness: f64, damping: f64, travel: f64) -> Suspension {
+        Suspension {
+            stiffness,
+            damping,
+            travel
+        }
+    }
+}
+
+impl Suspension {
+    /// Body offset above its rest height and body vertical speed `dt`
+    /// seconds later, starting from `offset` and `body_speed` while the road
+    /// under the wheels rises at `road_speed`. The last value is the speed
+    /// the body hit the bump stops with when the suspension bottomed out,
+    /// 0 otherwise.
+    pub fn step(&self, offset: f64, body_speed: f64, road_speed: f64, dt: f64) -> (f64, f64, f64) {
+        if self.stiffness <= 0.0 {
+            return (0.0, road_speed, 0.0);
+        }
+        let body_acceleration = -self.stiffness * offset - self.damping * (body_speed - road_speed);
+        let body_speed = body_speed + body_acceleration * dt;
+        let offset = offset + (body_speed - road_speed) * dt;
+        if offset < -self.travel {
+            (-self.travel, road_speed, road_speed - body_speed)
+        } else {
+            (offset, body_speed, 0.0)
+        }
+    }
+}
+
+impl Default for Suspension {
+    fn default() -> Suspension {
+        Suspension {
+            stiffness: 0.0,
+            damping: 6.0,
+            travel: 0.1
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn rigid_suspension_should_follow_the_road(){
+        assert_eq!((0.0, 2.0, 0.0), Suspension::default().step(0.5, 0.0, 2.0, 0.1));
+    }
+
+    #[test]
+    fn body_should_lag_behind_a_rising_road(){
+        let suspension = Suspension::new(100.0, 6.0, 0.1);
+
+        let (offset, body_speed, impact) = suspension.step(0.0, 0.0, 0.5, 0.01);
+        assert!(offset < 0.0);
+        assert!(body_speed > 0.0);
+        assert_eq!(0.0, impact);
+    }
+
+    #[test]
+    fn body_should_settle_back_to_rest_height(){
+        let suspension = Suspension::new(100.0, 6.0, 0.1);
+        let (mut offset, mut body_speed) = (0.05, 0.0);
+
+        for _ in 0..1000 {
+            (offset, body_speed, _) = suspension.step(offset, body_speed, 0.0, 0.01);
+        }
+        assert!(offset.abs() < 1e-3);
+    }
+
+    #[test]
+    fn hard_landing_should_bottom_out_with_the_closing_speed(){
+        let suspension = Suspension::new(100.0, 6.0, 0.1);
+
+        let (offset, body_speed, impact) = suspension.step(-0.09, -2.0, 0.0, 0.01);
+        assert_eq!(-0.1, offset);
+        assert_eq!(0.0, body_speed);
+        assert!(impact > 1.0);
+    }
+}
@@ -0,0 +1,385 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::validate::{finite, non_negative, stretch};
+
+/// Gravitational acceleration pulling the car down slopes.
+pub const GRAVITY: f64 = 9.81;
+
+/// Stretch of road with a constant gradient.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Slope {
+    pub start: f64,
+    pub end: f64,
+    /// Rise over run: 0.1 climbs 1 unit every 10, negative values go
+    /// downhill.
+    pub gradient: f64
+}
+
+/// Kind of road surface, each with its typical friction coefficient.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Surface {
+    Asphalt,
+    WetAsphalt,
+    Gravel,
+    Ice
+}
+
+impl Surface {
+    pub fn friction(self) -> f64 {
+        match self {
+            Surface::Asphalt => 1.0,
+            Surface::WetAsphalt => 0.7,
+            Surface::Gravel => 0.6,
+            Surface::Ice => 0.1
+        }
+    }
+}
+
+/// Stretch of road whose friction differs from dry asphalt.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FrictionZone {
+    pub start: f64,
+    pub end: f64,
+    /// Scales braking and rolling deceleration; dry asphalt is 1.
+    pub friction: f64
+}
+
+/// Deceleration from gravity on a road with `gradient`: positive uphill,
+/// negative downhill.
+pub fn slope_deceleration(gradient: f64) -> f64 {
+    GRAVITY * gradient / (1.0 + gradient * gradient).sqrt()
+}
+
+/// Kind of obstacle standing on the road.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ObstacleKind {
+    /// Knocked over when hit; the run goes on with a penalty.
+    Cone,
+    /// Hitting it ends the run.
+    ParkedCar
+}
+
+impl ObstacleKind {
+    /// Length of road the obstacle covers.
+    pub fn length(self) -> f64 {
+        match self {
+            ObstacleKind::Cone => 0.5,
+            ObstacleKind::ParkedCar => 4.5
+        }
+    }
+}
+
+/// Obstacle covering the road between `start` and `end`.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Obstacle {
+    pub kind: ObstacleKind,
+    pub start: f64,
+    pub end: f64
+}
+
+impl Obstacle {
+    /// Whether a car moving from `from` to `to` runs into the obstacle. A
+    /// car already on it, like one driving over a fallen cone, doesn't hit
+    /// it again.
+    pub fn hit_between(&self, from: f64, to: f64) -> bool {
+        let already_on = self.start <= from && from <= self.end;
+        from.min(to) <= self.end && from.max(to) >= self.start && !already_on
+    }
+}
+
+/// The road the car drives on, as an elevation profile made of slopes.
+/// Outside of any slope the road is flat, and outside of any friction zone
+/// it is dry asphalt.
+///
+/// The road is at `base_height` at position 0; the height anywhere else is
+/// the base height plus the climb along the slopes on the way.
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Track {
+    #[wasm_bindgen(skip)]
+    pub slopes: Vec<Slope>,
+    pub base_height: f64,
+    #[wasm_bindgen(skip)]
+    pub friction_zones: Vec<FrictionZone>,
+    #[wasm_bindgen(skip)]
+    pub obstacles: Vec<Obstacle>
+}
+
+#[wasm_bindgen]
+impl Track {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Track {
+        Track::default()
+    }
+
+    /// Builds the profile from road heights sampled every `spacing` from
+    /// position 0, linearly interpolated in between. The road stays flat
+    /// after the last sample.
+    pub fn from_heights(spacing: f64, heights: Vec<f64>) -> Track {
+        let slopes = heights
+            .windows(2)
+            .enumerate()
+            .map(|(index, pair)| Slope {
+                start: index as f64 * spacing,
+                end: (index + 1) as f64 * spacing,
+                gradient: (pair[1] - pair[0]) / spacing
+            })
+            .collect();
+        Track {
+            slopes,
+            base_height: heights.first().copied().unwrap_or(0.0),
+            ..Track::default()
+        }
+    }
+
+    /// Adds a slope between `start` and `end`. Where slopes overlap, the
+    /// first one added wins.
+    pub fn add_slope(&mut self, start: f64, end: f64, gradient: f64) {
+        self.slopes.push(Slope { start, end, gradient });
+    }
+
+    /// Adds a zone with the given `friction` between `start` and `end`.
+    /// Where zones overlap, the first one added wins.
+    pub fn add_friction_zone(&mut self, start: f64, end: f64, friction: f64) {
+        self.friction_zones.push(FrictionZone { start, end, friction });
+    }
+
+    pub fn add_surface(&mut self, start: f64, end: f64, surface: Surface) {
+        self.add_friction_zone(start, end, surface.friction());
+    }
+
+    /// Places an obstacle of the given `kind` starting at `position`.
+    pub fn add_obstacle(&mut self, kind: ObstacleKind, position: f64) {
+        self.obstacles.push(Obstacle {
+            kind,
+            start: position,
+            end: position + kind.length()
+        });
+    }
+
+    /// Obstacles on the track, for rendering.
+    #[wasm_bindgen(getter)]
+    pub fn obstacles(&self) -> Vec<Obstacle> {
+        self.obstacles.clone()
+    }
+
+    pub fn friction_at(&self, position: f64) -> f64 {
+        self.friction_zones
+            .iter()
+            .find(|zone| zone.start <= position && position < zone.end)
+            .map_or(Surface::Asphalt.friction(), |zone| zone.friction)
+    }
+
+    pub fn gradient_at(&self, position: f64) -> f64 {
+        self.slopes
+            .iter()
+            .find(|slope| slope.start <= position && position < slope.end)
+            .map_or(0.0, |slope| slope.gradient)
+    }
+
+    /// Road height at `position`, for drawing hills.
+    pub fn height_at(&self, position: f64) -> f64 {
+        let (from, to) = if position < 0.0 { (position, 0.0) } else { (0.0, position) };
+        let mut breaks: Vec<f64> = self.slopes
+            .iter()
+            .flat_map(|slope| [slope.start, slope.end])
+            .filter(|&x| from < x && x < to)
+            .chain([from, to])
+            .collect();
+        breaks.sort_by(f64::total_cmp);
+
+        let climb: f64 = breaks
+            .windows(2)
+            .map(|pair| self.gradient_at((pair[0] + pair[1]) / 2.0) * (pair[1] - pair[0]))
+            .sum();
+        if position < 0.0 {
+            self.base_height - climb
+        } else {
+            self.base_height + climb
+        }
+    }
+
+    /// Road heights from `start` to `end` every `step`, so the renderer
+    /// gets a whole hill outline in one call.
+    pub fn heights(&self, start: f64, end: f64, step: f64) -> Vec<f64> {
+        if step <= 0.0 {
+            return Vec::new();
+        }
+        let count = ((end - start) / step).floor().max(0.0) as usize + 1;
+        (0..count).map(|index| self.height_at(start + index as f64 * step)).collect()
+    }
+
+    /// Deceleration from gravity at `position`: positive uphill, negative
+    /// (pulling the car along) downhill.
+    pub fn gravity_deceleration(&self, position: f64) -> f64 {
+        slope_deceleration(self.gradient_at(position))
+    }
+}
+
+impl Track {
+    /// Checks that every slope, friction zone and obstacle is a real
+    /// stretch of road with sane values, naming the first that isn't.
+    pub fn check(&self) -> Result<(), String> {
+        finite("track.base_height", self.base_height)?;
+        for (index, slope) in self.slopes.iter().enumerate() {
+            let field = format!("track.slopes[{}]", index);
+            stretch(&field, slope.start, slope.end)?;
+            finite(&format!("{}.gradient", field), slope.gradient)?;
+        }
+        for (index, zone) in self.friction_zones.iter().enumerate() {
+            let field = format!("track.friction_zones[{}]", index);
+            stretch(&field, zone.start, zone.end)?;
+            non_negative(&format!("{}.friction", field), zone.friction)?;
+        }
+        for (index, obstacle) in self.obstacles.iter().enumerate() {
+            stretch(&format!("track.obstacles[{}]", index), obstacle.start, obstacle.end)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn track_should_be_flat_by_default(){
+        let track = Track::new();
+
+        assert_eq!(0.0, track.gradient_at(100.0));
+        assert_eq!(0.0, track.gravity_deceleration(100.0));
+    }
+
+    #[test]
+    fn gradient_should_apply_between_slope_start_and_end(){
+        let mut track = Track::new();
+        track.add_slope(10.0, 20.0, 0.1);
+
+        assert_eq!(0.0, track.gradient_at(9.0));
+        assert_eq!(0.1, track.gradient_at(10.0));
+        assert_eq!(0.0, track.gradient_at(20.0));
+    }
+
+    #[test]
+    fn first_added_slope_should_win_where_slopes_overlap(){
+        let mut track = Track::new();
+        track.add_slope(0.0, 20.0, 0.1);
+        track.add_slope(10.0, 30.0, -0.2);
+
+        assert_eq!(0.1, track.gradient_at(15.0));
+        assert_eq!(-0.2, track.gradient_at(25.0));
+    }
+
+    #[test]
+    fn gravity_should_pull_against_uphill_and_along_downhill(){
+        let mut track = Track::new();
+        track.add_slope(0.0, 10.0, 0.75);
+        track.add_slope(10.0, 20.0, -0.75);
+
+        assert_eq!(GRAVITY * 0.6, track.gravity_deceleration(5.0));
+        assert_eq!(-GRAVITY * 0.6, track.gravity_deceleration(15.0));
+    }
+
+    #[test]
+    fn height_should_climb_along_slopes(){
+        let mut track = Track::new();
+        track.add_slope(10.0, 20.0, 0.5);
+
+        assert_eq!(0.0, track.height_at(10.0));
+        assert_eq!(2.5, track.height_at(15.0));
+        assert_eq!(5.0, track.height_at(100.0));
+    }
+
+    #[test]
+    fn height_should_follow_the_slope_that_wins_where_slopes_overlap(){
+        let mut track = Track::new();
+        track.add_slope(0.0, 20.0, 0.5);
+        track.add_slope(10.0, 30.0, -0.5);
+
+        assert_eq!(10.0, track.height_at(20.0));
+        assert_eq!(5.0, track.height_at(30.0));
+    }
+
+    #[test]
+    fn height_before_origin_should_follow_slopes_backwards(){
+        let mut track = Track { base_height: 1.0, ..Default::default() };
+        track.add_slope(-10.0, 0.0, 0.5);
+
+        assert_eq!(-4.0, track.height_at(-10.0));
+    }
+
+    #[test]
+    fn height_samples_should_interpolate_between_samples(){
+        let track = Track::from_heights(10.0, vec![2.0, 4.0, 0.0]);
+
+        assert_eq!(2.0, track.height_at(0.0));
+        assert_eq!(3.0, track.height_at(5.0));
+        assert_eq!(2.0, track.height_at(15.0));
+        assert_eq!(0.0, track.height_at(50.0));
+    }
+
+    #[test]
+    fn height_samples_should_drive_the_gradient(){
+        let track = Track::from_heights(10.0, vec![2.0, 4.0, 0.0]);
+
+        assert_eq!(0.2, track.gradient_at(5.0));
+        assert_eq!(-0.4, track.gradient_at(15.0));
+        assert_eq!(0.0, track.gradient_at(25.0));
+    }
+
+    #[test]
+    fn heights_should_sample_the_profile_between_bounds(){
+        let track = Track::from_heights(10.0, vec![0.0, 10.0]);
+
+        assert_eq!(vec![0.0, 5.0, 10.0, 10.0], track.heights(0.0, 15.0, 5.0));
+        assert!(track.heights(0.0, 15.0, 0.0).is_empty());
+    }
+
+    #[test]
+    fn road_should_be_dry_asphalt_outside_friction_zones(){
+        let mut track = Track::new();
+        track.add_surface(10.0, 20.0, Surface::Ice);
+
+        assert_eq!(1.0, track.friction_at(5.0));
+        assert_eq!(0.1, track.friction_at(15.0));
+        assert_eq!(1.0, track.friction_at(20.0));
+    }
+
+    #[test]
+    fn first_added_friction_zone_should_win_where_zones_overlap(){
+        let mut track = Track::new();
+        track.add_friction_zone(0.0, 20.0, 0.5);
+        track.add_surface(10.0, 30.0, Surface::Gravel);
+
+        assert_eq!(0.5, track.friction_at(15.0));
+        assert_eq!(0.6, track.friction_at(25.0));
+    }
+
+    #[test]
+    fn obstacle_should_cover_its_kind_length_from_its_position(){
+        let mut track = Track::new();
+        track.add_obstacle(ObstacleKind::ParkedCar, 100.0);
+
+        assert_eq!(vec![Obstacle { kind: ObstacleKind::ParkedCar, start: 100.0, end: 104.5 }], track.obstacles());
+    }
+
+    #[test]
+    fn obstacle_should_be_hit_when_the_car_moves_onto_or_past_it(){
+        let cone = Obstacle { kind: ObstacleKind::Cone, start: 10.0, end: 10.5 };
+
+        assert!(cone.hit_between(9.0, 10.2));
+        assert!(cone.hit_between(9.0, 12.0));
+        assert!(cone.hit_between(12.0, 9.0));
+        assert!(!cone.hit_between(8.0, 9.0));
+        assert!(!cone.hit_between(10.2, 11.0));
+    }
+}
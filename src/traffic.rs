@@ -0,0 +1,130 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+/// Length of a traffic car, from its rear bumper at `position`.
+pub const TRAFFIC_CAR_LENGTH: f64 = 4.5;
+
+/// Largest gap, in metres, behind a traffic car at which the player is
+/// in its slipstream.
+pub const DRAFT_DISTANCE: f64 = 15.0;
+
+/// Share of the air drag left while drafting.
+pub const DRAFT_DRAG: f64 = 0.6;
+
+/// Acceleration traffic cars pick up speed with.
+const TRAFFIC_ACCELERATION: f64 = 2.0;
+/// Deceleration traffic cars brake with when closing in.
+const TRAFFIC_BRAKING: f64 = 6.0;
+/// Gap traffic cars keep to the car ahead even when stopped.
+const MIN_GAP: f64 = 2.0;
+/// Seconds of travel traffic cars keep to the car ahead on top of
+/// [`MIN_GAP`].
+const TIME_GAP: f64 = 1.5;
+
+/// Another car driving along the track ahead of the player.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TrafficCar {
+    /// Position of the rear bumper.
+    pub position: f64,
+    pub speed: f64,
+    /// Speed the car drives at on a free road.
+    pub cruise_speed: f64
+}
+
+#[wasm_bindgen]
+impl TrafficCar {
+    #[wasm_bindgen(constructor)]
+    pub fn new(position: f64, cruise_speed: f64) -> TrafficCar {
+        TrafficCar {
+            position,
+            speed: cruise_speed,
+            cruise_speed
+        }
+    }
+}
+
+impl TrafficCar {
+    /// The car `dt` seconds later, following `leader`, the next car ahead.
+    /// It brakes when closer than its safe gap and otherwise speeds up or
+    /// slows down towards its cruise speed.
+    pub fn step(&self, leader: Option<&TrafficCar>, dt: f64) -> TrafficCar {
+        let too_close = leader.is_some_and(|leader| {
+            let gap = leader.position - TRAFFIC_CAR_LENGTH - self.position;
+            gap < MIN_GAP + self.speed * TIME_GAP
+        });
+        let speed = if too_close {
+            (self.speed - TRAFFIC_BRAKING * dt).max(0.0)
+        } else if self.speed < self.cruise_speed {
+            (self.speed + TRAFFIC_ACCELERATION * dt).min(self.cruise_speed)
+        } else {
+            (self.speed - TRAFFIC_BRAKING * dt).max(self.cruise_speed)
+        };
+        TrafficCar {
+            position: self.position + self.speed * dt,
+            speed,
+            ..*self
+        }
+    }
+}
+
+/// Moves every car of `traffic`, sorted from rearmost to frontmost, by `dt`
+/// seconds.
+pub fn step_traffic(traffic: &[TrafficCar], dt: f64) -> Vec<TrafficCar> {
+    traffic
+        .iter()
+        .enumerate()
+        .map(|(index, car)| car.step(traffic.get(index + 1), dt))
+        .collect()
+}
+
+/// Whether a car with its front bumper at `position` drafts behind one of
+/// `traffic`, sitting less than [`DRAFT_DISTANCE`] behind it.
+pub fn drafting(traffic: &[TrafficCar], position: f64) -> bool {
+    traffic.iter().any(|car| (0.0..DRAFT_DISTANCE).contains(&(car.position - position)))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn free_traffic_car_should_speed_up_to_its_cruise_speed(){
+        let car = TrafficCar { speed: 0.0, ..TrafficCar::new(0.0, 3.0) };
+
+        let car = car.step(None, 1.0);
+        assert_eq!(2.0, car.speed);
+        let car = car.step(None, 1.0);
+        assert_eq!(3.0, car.speed);
+        assert_eq!(2.0, car.position);
+    }
+
+    #[test]
+    fn traffic_car_should_brake_when_closing_in_on_the_car_ahead(){
+        let leader = TrafficCar { speed: 0.0, ..TrafficCar::new(20.0, 0.0) };
+        let follower = TrafficCar::new(10.0, 10.0);
+
+        assert_eq!(4.0, follower.step(Some(&leader), 1.0).speed);
+    }
+
+    #[test]
+    fn step_traffic_should_make_each_car_follow_the_next_one(){
+        let traffic = [TrafficCar::new(10.0, 10.0), TrafficCar { speed: 0.0, ..TrafficCar::new(20.0, 0.0) }];
+
+        let traffic = step_traffic(&traffic, 1.0);
+        assert_eq!(4.0, traffic[0].speed);
+        assert_eq!(20.0, traffic[1].position);
+    }
+
+    #[test]
+    fn drafting_should_only_apply_close_behind_a_traffic_car(){
+        let traffic = [TrafficCar::new(100.0, 10.0)];
+
+        assert!(drafting(&traffic, 90.0));
+        assert!(!drafting(&traffic, 80.0));
+        assert!(!drafting(&traffic, 110.0));
+        assert!(!drafting(&[], 90.0));
+    }
+}
@@ -0,0 +1,98 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+/// Unbraked trailer hitched behind the car.
+///
+/// The coupling is rigid along the road: car and trailer move as one, but
+/// only the car drives and brakes, so it has to pull and stop the trailer's
+/// mass too. The default trailer has no mass and no length, which is the
+/// same as no trailer at all.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Trailer {
+    pub mass: f64,
+    /// Distance from the car's position back to the trailer's, in metres.
+    pub length: f64,
+    /// Largest force, in newtons, the trailer may push the car with before
+    /// it swings round and jackknifes.
+    pub max_push_force: f64
+}
+
+#[wasm_bindgen]
+impl Trailer {
+    #[wasm_bindgen(constructor)]
+    pub fn new(mass: f64, length: f64, max_push_force: f64) -> Trailer {
+        Trailer {
+            mass,
+            length,
+            max_push_force
+        }
+    }
+}
+
+impl Trailer {
+    /// Share of the car's own drive and braking left once it also has to
+    /// move the trailer, for a car of `car_mass`.
+    pub fn load_share(&self, car_mass: f64) -> f64 {
+        car_mass / (car_mass + self.mass)
+    }
+
+    /// Force the trailer pushes the car with when the pair accelerates by
+    /// `drive` and decelerates by `braking`, both coming from the car and
+    /// signed forwards. Negative when the car pulls the trailer.
+    pub fn push_force(&self, drive: f64, braking: f64) -> f64 {
+        self.mass * (braking - drive)
+    }
+
+    pub fn jackknifes(&self, push_force: f64) -> bool {
+        push_force > self.max_push_force
+    }
+}
+
+impl Default for Trailer {
+    fn default() -> Trailer {
+        Trailer {
+            mass: 0.0,
+            length: 0.0,
+            max_push_force: f64::MAX
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn no_trailer_should_leave_the_car_alone(){
+        let trailer = Trailer::default();
+
+        assert_eq!(1.0, trailer.load_share(1200.0));
+        assert_eq!(0.0, trailer.push_force(0.0, 8.0));
+    }
+
+    #[test]
+    fn trailer_should_share_the_car_effort_by_mass(){
+        let trailer = Trailer::new(600.0, 5.0, 3000.0);
+
+        assert_eq!(0.75, trailer.load_share(1800.0));
+    }
+
+    #[test]
+    fn braking_should_push_and_driving_should_pull_the_trailer(){
+        let trailer = Trailer::new(600.0, 5.0, 3000.0);
+
+        assert_eq!(2400.0, trailer.push_force(0.0, 4.0));
+        assert_eq!(-1200.0, trailer.push_force(2.0, 0.0));
+    }
+
+    #[test]
+    fn pushing_harder_than_the_coupling_holds_should_jackknife(){
+        let trailer = Trailer::new(600.0, 5.0, 3000.0);
+
+        assert!(!trailer.jackknifes(trailer.push_force(0.0, 4.0)));
+        assert!(trailer.jackknifes(trailer.push_force(0.0, 6.0)));
+    }
+}
@@ -0,0 +1,83 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+/// Turbocharger boosting the engine force.
+///
+/// Boost builds up with a lag while the throttle stays open and is dumped
+/// as soon as the throttle closes, so it rewards holding the throttle.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Turbo {
+    /// Extra share of the engine force at full boost. 0 disables the turbo.
+    pub max_gain: f64,
+    /// Seconds it takes the boost to close most of the gap to the
+    /// throttle, as the time constant of its lag.
+    pub spool_time: f64
+}
+
+#[wasm_bindgen]
+impl Turbo {
+    #[wasm_bindgen(constructor)]
+    pub fn new(max_gain: f64, spool_time: f64) -> Turbo {
+        Turbo {
+            max_gain,
+            spool_time
+        }
+    }
+
+    /// Boost, from 0 to 1, `dt` seconds after being at `boost` with the
+    /// throttle at `throttle`. It spools up towards the throttle and drops
+    /// straight down to it when lifting off.
+    pub fn boost(&self, boost: f64, throttle: f64, dt: f64) -> f64 {
+        let throttle = throttle.clamp(0.0, 1.0);
+        if throttle <= boost {
+            return throttle;
+        }
+        boost + (throttle - boost) * (dt / self.spool_time).min(1.0)
+    }
+
+    /// Multiplier on the engine force at `boost`.
+    pub fn gain(&self, boost: f64) -> f64 {
+        1.0 + self.max_gain * boost
+    }
+}
+
+impl Default for Turbo {
+    fn default() -> Turbo {
+        Turbo {
+            max_gain: 0.0,
+            spool_time: 1.5
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn boost_should_build_up_with_a_lag(){
+        let turbo = Turbo::new(0.5, 2.0);
+
+        assert_eq!(0.25, turbo.boost(0.0, 1.0, 0.5));
+        assert_eq!(0.4375, turbo.boost(0.25, 1.0, 0.5));
+    }
+
+    #[test]
+    fn lifting_off_should_dump_the_boost(){
+        let turbo = Turbo::new(0.5, 2.0);
+
+        assert_eq!(0.0, turbo.boost(0.8, 0.0, 0.1));
+        assert_eq!(0.3, turbo.boost(0.8, 0.3, 0.1));
+    }
+
+    #[test]
+    fn full_boost_should_add_max_gain(){
+        let turbo = Turbo::new(0.5, 2.0);
+
+        assert_eq!(1.0, turbo.gain(0.0));
+        assert_eq!(1.5, turbo.gain(1.0));
+    }
+}
@@ -0,0 +1,130 @@
+use wasm_bindgen::prelude::*;
+
+use crate::{damaged_performance, State};
+
+/// Share of the full braking past which the tutorial tells the player to
+/// start braking, leaving them room to brake harder.
+pub const HINT_BRAKING: f64 = 0.5;
+
+/// Advice the tutorial gives, as a code for the front end to put into
+/// words in the player's language.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Hint {
+    /// Braking now gets the car to stop in the middle of the goal zone.
+    StartBraking,
+    /// The car is too fast to enter the goal zone.
+    SlowDownForEntry,
+    /// The car is in the goal zone and only has to stop.
+    StopHere,
+    /// The car went past the goal zone and may reverse back in.
+    ReverseIntoZone,
+    /// The car went past the goal zone and the level doesn't let it
+    /// reverse.
+    OvershotNoReverse
+}
+
+/// Advice for the player in `state`, if any.
+pub fn hint(state: &State) -> Option<Hint> {
+    if state.won || state.lost {
+        return None;
+    }
+    let goal_position = state.trailer_position();
+    if goal_position >= state.position_goal_end {
+        return Some(if state.vehicle.limits.can_reverse() { Hint::ReverseIntoZone } else { Hint::OvershotNoReverse });
+    }
+    if state.speed <= 0.0 || state.pedals.brake > 0.0 {
+        return None;
+    }
+    if goal_position > state.position_goal_start {
+        return Some(Hint::StopHere);
+    }
+    if state.speed > state.max_entry_speed {
+        return Some(Hint::SlowDownForEntry);
+    }
+    let vehicle = &state.vehicle;
+    let (full_braking, _) = vehicle.braking(1.0, state.friction(), state.speed, state.brake_temperature);
+    let full_braking = full_braking * state.trailer.load_share(vehicle.mass) * damaged_performance(state.damage);
+    let distance = (state.position_goal_start + state.position_goal_end) / 2.0 - state.position;
+    let stopping = state.speed * state.speed / (2.0 * distance);
+    if stopping >= HINT_BRAKING * full_braking {
+        return Some(Hint::StartBraking);
+    }
+    None
+}
+
+/// Tutorial mode: watches the run tick after tick and hands out a hint
+/// whenever the advice changes, so the front end shows each one once.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Tutorial {
+    last: Option<Hint>
+}
+
+#[wasm_bindgen]
+impl Tutorial {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Tutorial {
+        Tutorial::default()
+    }
+
+    /// Hint for `state` when it differs from the last one given.
+    pub fn observe(&mut self, state: &State) -> Option<Hint> {
+        let hint = hint(state);
+        if hint == self.last {
+            return None;
+        }
+        self.last = hint;
+        hint
+    }
+
+    /// Advice currently standing, for drawing it every frame.
+    pub fn current(&self) -> Option<Hint> {
+        self.last
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::{Input, Vehicle, VehicleLimits};
+
+    #[test]
+    fn hint_should_tell_to_brake_when_a_stop_gets_close(){
+        assert_eq!(None, hint(&State { speed: 10.0, ..State::new() }));
+        assert_eq!(Some(Hint::StartBraking), hint(&State { position: 8900.0, speed: 40.0, ..State::new() }));
+        assert_eq!(None, hint(&State { position: 8900.0, speed: 40.0, pedals: Input::new(0.0, 0.5), ..State::new() }));
+    }
+
+    #[test]
+    fn hint_should_tell_to_slow_down_before_the_goal_zone(){
+        let state = State { position: 1000.0, speed: 10.0, max_entry_speed: 5.0, ..State::new() };
+
+        assert_eq!(Some(Hint::SlowDownForEntry), hint(&state));
+    }
+
+    #[test]
+    fn hint_should_tell_to_stop_in_the_goal_zone(){
+        assert_eq!(Some(Hint::StopHere), hint(&State { position: 9500.0, speed: 1.0, ..State::new() }));
+    }
+
+    #[test]
+    fn hint_after_an_overshoot_should_depend_on_reversing(){
+        let state = State { position: 10500.0, ..State::new() };
+        let vehicle = Vehicle { limits: VehicleLimits { max_reverse_speed: 3.0, ..Default::default() }, ..Default::default() };
+
+        assert_eq!(Some(Hint::OvershotNoReverse), hint(&state));
+        assert_eq!(Some(Hint::ReverseIntoZone), hint(&State { vehicle, ..state }));
+    }
+
+    #[test]
+    fn tutorial_should_give_each_hint_once(){
+        let mut tutorial = Tutorial::new();
+        let state = State { position: 9500.0, speed: 1.0, ..State::new() };
+
+        assert_eq!(Some(Hint::StopHere), tutorial.observe(&state));
+        assert_eq!(None, tutorial.observe(&state));
+        assert_eq!(Some(Hint::StopHere), tutorial.current());
+    }
+}
@@ -0,0 +1,337 @@
+//! TypeScript declarations for the data that crosses over to JS as plain
+//! objects from [`State::to_js`](crate::State::to_js). wasm-bindgen only
+//! types the exported classes and enums; these spell out the serde form,
+//! where enums are their variant names and 64-bit seeds are `BigInt`s.
+
+use wasm_bindgen::prelude::*;
+
+/// Only read by the custom section, which the dead-code lint doesn't see.
+#[allow(dead_code)]
+const TYPES: &str = r#"
+export type EventName = "LimiterHit" | "Collision" | "HarshLanding" | "GoalCleared" | "CheckpointPassed" | "GoalEntered" | "GoalOvershot" | "Won" | "Lost";
+export type LoseReasonName = "None" | "Overshoot" | "OutOfFuel" | "OutOfCharge" | "Collision" | "Jackknife" | "TooFast" | "Wrecked" | "TimeOut" | "SplitMissed" | "BehindStart";
+export type WinConditionName = "StopInZone" | "StopWithinTime" | "StopWithFuelRemaining" | "ExactPosition";
+export type OvershootPolicyName = "InstantLoss" | "Penalty" | "GraceWindow";
+export type PowertrainName = "Direct" | "Combustion" | "Electric" | "Hybrid";
+export type TransmissionName = "Manual" | "Automatic";
+export type ObstacleKindName = "Cone" | "ParkedCar";
+export type WeatherName = "Dry" | "Rain" | "Snow";
+
+export interface StateData {
+    acceleration: number;
+    speed: number;
+    position: number;
+    reverse_distance: number;
+    jerk: number;
+    discomfort: number;
+    suspension_offset: number;
+    body_speed: number;
+    pedals: InputData;
+    pedal_rate: number;
+    elapsed_time: number;
+    ticks: number;
+    time_of_day: number;
+    time_scale: number;
+    headlights: boolean;
+    rpm: number;
+    gear: number;
+    stalled: boolean;
+    wheels_locked: boolean;
+    wheels_spinning: boolean;
+    brake_temperature: number;
+    boost: number;
+    nitrous: NitrousData;
+    launch_control: boolean;
+    cruise_active: boolean;
+    cruise_speed: number;
+    speed_limit: number;
+    limiter_active: boolean;
+    obstacles_hit: number;
+    damage: number;
+    fuel: number;
+    battery: number;
+    electric: boolean;
+    fuel_used: number;
+    charge_used: number;
+    charge_recovered: number;
+    position_goal_start: number;
+    position_goal_end: number;
+    max_entry_speed: number;
+    target_time: number;
+    win_condition: WinConditionName;
+    energy_reserve: number;
+    position_tolerance: number;
+    lose_conditions: LoseConditionsData;
+    overshoot_policy: OvershootPolicyName;
+    overshoot_grace_ticks: number;
+    overshoot_ticks: number;
+    overshoots: number;
+    endless: EndlessData;
+    checkpoints: CheckpointData[];
+    checkpoints_passed: number;
+    extra_goals: GoalZoneData[];
+    goal_motion: GoalMotionData;
+    won: boolean;
+    lost: boolean;
+    lose_reason: LoseReasonName;
+    vehicle: VehicleData;
+    trailer: TrailerData;
+    track: TrackData;
+    traffic: TrafficCarData[];
+    drafting: boolean;
+    wind: WindData;
+    forecast: ForecastData;
+    wind_force: number;
+    events: EventName[];
+    level: LevelData;
+    session: SessionData;
+    seed: bigint;
+}
+
+export interface InputData {
+    throttle: number;
+    brake: number;
+    clutch: number;
+}
+
+export interface NitrousData {
+    charges: number;
+    burn_ticks: number;
+    acceleration: number;
+    active_ticks: number;
+}
+
+export interface LoseConditionsData {
+    overshoot: boolean;
+    time_out: boolean;
+    fuel_empty: boolean;
+    wrecked: boolean;
+    behind_start: boolean;
+}
+
+export interface EndlessData {
+    enabled: boolean;
+    min_gap: number;
+    max_gap: number;
+    seed: bigint;
+    streak: number;
+}
+
+export interface CheckpointData {
+    position: number;
+    split_time: number;
+}
+
+export interface GoalZoneData {
+    start: number;
+    end: number;
+    value: number;
+}
+
+export interface GoalMotionData {
+    drift_speed: number;
+    amplitude: number;
+    period: number;
+}
+
+export interface VehicleData {
+    mass: number;
+    brake_force: number;
+    brake_heating: number;
+    brake_cooling: number;
+    brake_fade_temperature: number;
+    brake_max_temperature: number;
+    faded_brake: number;
+    grip_limit: boolean;
+    tire_grip: number;
+    sliding_grip: number;
+    abs: boolean;
+    traction_control: boolean;
+    tire_wear: number;
+    tire_wear_rate: number;
+    worn_grip: number;
+    resistance: boolean;
+    rolling_resistance: number;
+    drag_coefficient: number;
+    suspension: SuspensionData;
+    limits: VehicleLimitsData;
+    powertrain: PowertrainName;
+    engine: EngineData;
+    turbo: TurboData;
+    gearbox: GearboxData;
+    clutch: boolean;
+    tank_capacity: number;
+    fuel_consumption: number;
+    motor_force: number;
+    battery_capacity: number;
+    energy_consumption: number;
+    hybrid_switch_speed: number;
+    hybrid_engine_throttle: number;
+    regen_efficiency: number;
+}
+
+export interface SuspensionData {
+    stiffness: number;
+    damping: number;
+    travel: number;
+}
+
+export interface VehicleLimitsData {
+    max_speed: number;
+    max_acceleration: number;
+    max_deceleration: number;
+    max_reverse_speed: number;
+}
+
+export interface EngineData {
+    idle_rpm: number;
+    stall_rpm: number;
+    peak_torque_rpm: number;
+    redline_rpm: number;
+    idle_torque: number;
+    redline_torque: number;
+    peak_force: number;
+    braking_force: number;
+    rpm_per_speed: number;
+}
+
+export interface TurboData {
+    max_gain: number;
+    spool_time: number;
+}
+
+export interface GearboxData {
+    ratios: number[];
+    final_drive: number;
+    reverse_ratio: number;
+    transmission: TransmissionName;
+    upshift_rpm: number;
+    downshift_rpm: number;
+}
+
+export interface TrailerData {
+    mass: number;
+    length: number;
+    max_push_force: number;
+}
+
+export interface TrackData {
+    slopes: SlopeData[];
+    base_height: number;
+    friction_zones: FrictionZoneData[];
+    obstacles: ObstacleData[];
+}
+
+export interface SlopeData {
+    start: number;
+    end: number;
+    gradient: number;
+}
+
+export interface FrictionZoneData {
+    start: number;
+    end: number;
+    friction: number;
+}
+
+export interface ObstacleData {
+    kind: ObstacleKindName;
+    start: number;
+    end: number;
+}
+
+export interface TrafficCarData {
+    position: number;
+    speed: number;
+    cruise_speed: number;
+}
+
+export interface WindData {
+    force: number;
+    gust_strength: number;
+    gust_period: number;
+    seed: bigint;
+}
+
+export interface ForecastData {
+    initial: WeatherName;
+    change_period: number;
+    seed: bigint;
+}
+
+export interface LevelData {
+    start_position: number;
+    goal_start: number;
+    goal_end: number;
+    vehicle: VehicleData;
+    track: TrackData;
+    traffic: TrafficCarData[];
+    trailer: TrailerData;
+    wind: WindData;
+    forecast: ForecastData;
+    speed_limit: number;
+    max_entry_speed: number;
+    nitrous_charges: number;
+    time_of_day: number;
+    time_scale: number;
+    target_time: number;
+    win_condition: WinConditionName;
+    energy_reserve: number;
+    position_tolerance: number;
+    lose_conditions: LoseConditionsData;
+    overshoot_policy: OvershootPolicyName;
+    overshoot_grace_ticks: number;
+    endless: EndlessData;
+    checkpoints: CheckpointData[];
+    extra_goals: GoalZoneData[];
+    goal_motion: GoalMotionData;
+    seed: bigint;
+    two_star_score: number;
+    three_star_score: number;
+}
+
+export interface SessionData {
+    attempts: number;
+    wins: number;
+    losses: number;
+}
+"#;
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_TYPES: &'static str = TYPES;
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::{Event, Level, LoseReason, ObstacleKind, OvershootPolicy, Powertrain, State, Transmission, Weather, WinCondition};
+
+    fn fields(json: &str) -> Vec<String> {
+        let value: serde_json::Value = serde_json::from_str(json).unwrap();
+        value.as_object().unwrap().keys().cloned().collect()
+    }
+
+    #[test]
+    fn types_should_declare_every_state_and_level_field(){
+        for field in fields(&State::new().to_json()).into_iter().chain(fields(&Level::default().to_json())) {
+            assert!(TYPES.contains(&format!("    {}: ", field)), "{} is not declared", field);
+        }
+    }
+
+    #[test]
+    fn unions_should_name_every_variant(){
+        let names = [
+            serde_json::to_string(&Event::Lost).unwrap(),
+            serde_json::to_string(&LoseReason::BehindStart).unwrap(),
+            serde_json::to_string(&WinCondition::ExactPosition).unwrap(),
+            serde_json::to_string(&OvershootPolicy::GraceWindow).unwrap(),
+            serde_json::to_string(&Powertrain::Hybrid).unwrap(),
+            serde_json::to_string(&Transmission::Automatic).unwrap(),
+            serde_json::to_string(&ObstacleKind::ParkedCar).unwrap(),
+            serde_json::to_string(&Weather::Snow).unwrap()
+        ];
+        for name in names {
+            assert!(TYPES.contains(&name), "{} is not declared", name);
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use wasm_bindgen::prelude::*;
+
+/// Metres per second in one kilometre per hour.
+pub const KMH: f64 = 1000.0 / 3600.0;
+/// Metres per second in one mile per hour.
+pub const MPH: f64 = 1609.344 / 3600.0;
+
+#[wasm_bindgen]
+pub fn mps_to_kmh(speed: f64) -> f64 {
+    speed / KMH
+}
+
+#[wasm_bindgen]
+pub fn kmh_to_mps(speed: f64) -> f64 {
+    speed * KMH
+}
+
+#[wasm_bindgen]
+pub fn mps_to_mph(speed: f64) -> f64 {
+    speed / MPH
+}
+
+#[wasm_bindgen]
+pub fn mph_to_mps(speed: f64) -> f64 {
+    speed * MPH
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn ten_metres_per_second_should_be_thirty_six_kmh(){
+        assert_eq!(36.0, mps_to_kmh(10.0));
+        assert_eq!(10.0, kmh_to_mps(36.0));
+    }
+
+    #[test]
+    fn mph_conversions_should_round_trip(){
+        assert!((mps_to_mph(mph_to_mps(60.0)) - 60.0).abs() < 1e-12);
+        assert!((mph_to_mps(60.0) - 26.8224).abs() < 1e-12);
+    }
+}
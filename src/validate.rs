@@ -0,0 +1,54 @@
+//! Checks of hand-written configuration, such as community level files,
+//! whose errors name the offending field.
+
+/// `value` of `field` must be a number, not NaN or infinite.
+pub fn finite(field: &str, value: f64) -> Result<(), String> {
+    if value.is_finite() {
+        Ok(())
+    } else {
+        Err(format!("{} must be a finite number, not {}", field, value))
+    }
+}
+
+/// `value` of `field` must be a number of at least 0.
+pub fn non_negative(field: &str, value: f64) -> Result<(), String> {
+    if value.is_finite() && value >= 0.0 {
+        Ok(())
+    } else {
+        Err(format!("{} must be 0 or more, not {}", field, value))
+    }
+}
+
+/// `value` of `field` must be a number above 0.
+pub fn positive(field: &str, value: f64) -> Result<(), String> {
+    if value.is_finite() && value > 0.0 {
+        Ok(())
+    } else {
+        Err(format!("{} must be above 0, not {}", field, value))
+    }
+}
+
+/// The stretch `field` from `start` to `end` must be a real one.
+pub fn stretch(field: &str, start: f64, end: f64) -> Result<(), String> {
+    finite(&format!("{}.start", field), start)?;
+    finite(&format!("{}.end", field), end)?;
+    if start < end {
+        Ok(())
+    } else {
+        Err(format!("{} ends at {}, before it starts at {}", field, end, start))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn errors_should_name_the_field(){
+        assert_eq!(Ok(()), positive("vehicle.mass", 1.0));
+        assert_eq!(Err(String::from("vehicle.mass must be above 0, not 0")), positive("vehicle.mass", 0.0));
+        assert_eq!(Err(String::from("wind.force must be a finite number, not NaN")), finite("wind.force", f64::NAN));
+        assert_eq!(Err(String::from("track.slopes[1] ends at 2, before it starts at 3")), stretch("track.slopes[1]", 3.0, 2.0));
+    }
+}
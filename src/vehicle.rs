@@ -0,0 +1,1083 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::validate::{finite, non_negative, positive};
+use crate::{Engine, Gearbox, Powertrain, Suspension, Turbo, GRAVITY};
+
+/// Temperature brakes cool down to, in degrees Celsius.
+pub const AMBIENT_TEMPERATURE: f64 = 20.0;
+
+/// How hard cruise control pulls back towards its target speed: the speed
+/// error is corrected at this rate per second.
+pub const CRUISE_GAIN: f64 = 1.0;
+
+/// Share of the tyre grip traction control lets the engine use. It keeps a
+/// safety margin, so a careful driver without it can launch a bit harder.
+pub const TRACTION_CONTROL_GRIP: f64 = 0.9;
+
+/// Hard physical limits the car can never exceed, whatever the inputs.
+///
+/// Every limit defaults to `f64::MAX`, meaning unlimited, except
+/// `max_reverse_speed` which defaults to 0: the car can't reverse unless the
+/// level allows it.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VehicleLimits {
+    pub max_speed: f64,
+    pub max_acceleration: f64,
+    /// Largest deceleration, as a positive value.
+    pub max_deceleration: f64,
+    /// Fastest the car may go backwards, as a positive value.
+    pub max_reverse_speed: f64
+}
+
+#[wasm_bindgen]
+impl VehicleLimits {
+    #[wasm_bindgen(constructor)]
+    pub fn new(max_speed: f64, max_acceleration: f64, max_deceleration: f64) -> VehicleLimits {
+        VehicleLimits {
+            max_speed,
+            max_acceleration,
+            max_deceleration,
+            max_reverse_speed: 0.0
+        }
+    }
+
+    pub fn clamp_acceleration(&self, acceleration: f64) -> f64 {
+        acceleration.max(-self.max_deceleration).min(self.max_acceleration)
+    }
+
+    pub fn clamp_speed(&self, speed: f64) -> f64 {
+        speed.max(-self.max_reverse_speed).min(self.max_speed)
+    }
+
+    pub fn can_reverse(&self) -> bool {
+        self.max_reverse_speed > 0.0
+    }
+}
+
+impl Default for VehicleLimits {
+    fn default() -> VehicleLimits {
+        VehicleLimits {
+            max_speed: f64::MAX,
+            max_acceleration: f64::MAX,
+            max_deceleration: f64::MAX,
+            max_reverse_speed: 0.0
+        }
+    }
+}
+
+/// Physical configuration of the player's car.
+///
+/// The default is the arcade car the game started with: unit mass, so
+/// throttle maps straight to acceleration, and every optional effect is
+/// switched off.
+///
+/// Driving, braking and drag are forces, divided by `mass` to get the
+/// acceleration: the same throttle pulls a loaded truck much less than a
+/// light car, and the truck needs longer to stop.
+#[wasm_bindgen]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Vehicle {
+    pub mass: f64,
+    /// Braking force at full brake pedal.
+    pub brake_force: f64,
+    /// Temperature rise per second for each unit of braking power (braking
+    /// deceleration times speed). 0 disables brake heat and fade.
+    pub brake_heating: f64,
+    /// Share of the difference to ambient temperature lost per second.
+    pub brake_cooling: f64,
+    /// Temperature above which the brakes start fading.
+    pub brake_fade_temperature: f64,
+    /// Temperature at which the brakes are down to `faded_brake`.
+    pub brake_max_temperature: f64,
+    /// Share of the braking force left on fully faded brakes.
+    pub faded_brake: f64,
+    /// Limits braking to what the tyres can grip. Braking harder locks the
+    /// wheels, which then slide with less grip.
+    pub grip_limit: bool,
+    /// Tyre friction coefficient on dry asphalt.
+    pub tire_grip: f64,
+    /// Share of `tire_grip` left once the wheels are locked and sliding.
+    pub sliding_grip: f64,
+    /// Anti-lock brakes: braking beyond grip is modulated down to peak grip
+    /// instead of locking the wheels. Only matters with `grip_limit`.
+    pub abs: bool,
+    /// Cuts engine power before the driven wheels spin. Only matters with
+    /// `grip_limit`.
+    pub traction_control: bool,
+    /// Tyre wear from 0 (new) to 1 (bald). It belongs to the car, so it
+    /// carries over when the same vehicle drives the next level.
+    pub tire_wear: f64,
+    /// Wear added per unit of distance driven; braking and sliding wear
+    /// the tyres faster. 0 disables wear.
+    pub tire_wear_rate: f64,
+    /// Share of `tire_grip` left on fully worn tyres.
+    pub worn_grip: f64,
+    /// Enables rolling resistance and aerodynamic drag, so the car coasts
+    /// down on its own instead of keeping its speed forever.
+    pub resistance: bool,
+    /// Constant deceleration from the tyres rolling on the road.
+    pub rolling_resistance: f64,
+    /// Air drag force per unit of speed squared.
+    pub drag_coefficient: f64,
+    pub suspension: Suspension,
+    pub limits: VehicleLimits,
+    pub powertrain: Powertrain,
+    /// Used by the [`Powertrain::Combustion`] and [`Powertrain::Hybrid`]
+    /// powertrains.
+    pub engine: Engine,
+    /// Used by the [`Powertrain::Combustion`] and [`Powertrain::Hybrid`]
+    /// powertrains.
+    pub turbo: Turbo,
+    #[wasm_bindgen(getter_with_clone)]
+    pub gearbox: Gearbox,
+    /// Hands the clutch pedal to the player. First gear no longer slips on
+    /// its own, so pulling away takes clutch control and dumping the clutch
+    /// stalls the engine. Only matters for powertrains with an engine.
+    pub clutch: bool,
+    pub tank_capacity: f64,
+    /// Fuel burnt per second at full throttle, at the redline for engines.
+    /// 0 disables the fuel system.
+    pub fuel_consumption: f64,
+    /// Driving force of the [`Powertrain::Electric`] and
+    /// [`Powertrain::Hybrid`] motor at full throttle.
+    /// Electric motors give full torque from standstill.
+    pub motor_force: f64,
+    pub battery_capacity: f64,
+    /// Charge drawn per second at full throttle. 0 disables the battery.
+    pub energy_consumption: f64,
+    /// Speed up to which a [`Powertrain::Hybrid`] may run on its motor.
+    pub hybrid_switch_speed: f64,
+    /// Throttle above which a [`Powertrain::Hybrid`] starts its engine.
+    pub hybrid_engine_throttle: f64,
+    /// Share of `energy_consumption` given back per second at full brake.
+    pub regen_efficiency: f64
+}
+
+#[wasm_bindgen]
+impl Vehicle {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Vehicle {
+        Vehicle::default()
+    }
+
+    /// Deceleration for a brake pedal position between 0 (released) and 1
+    /// (floored). The curve is progressive, so light pressure gives fine
+    /// control and only a full press reaches `brake_force`.
+    pub fn brake_deceleration(&self, brake: f64) -> f64 {
+        let pedal = brake.clamp(0.0, 1.0);
+        self.brake_force * pedal * pedal / self.mass
+    }
+
+    /// Share of the braking force left at `temperature`: full below the
+    /// fade temperature, then fading linearly down to `faded_brake`, or at
+    /// once when the max temperature is no higher.
+    pub fn brake_fade(&self, temperature: f64) -> f64 {
+        let span = self.brake_max_temperature - self.brake_fade_temperature;
+        let heat = if span > 0.0 {
+            ((temperature - self.brake_fade_temperature) / span).clamp(0.0, 1.0)
+        } else if temperature > self.brake_fade_temperature {
+            1.0
+        } else {
+            0.0
+        };
+        1.0 - (1.0 - self.faded_brake) * heat
+    }
+
+    /// Brake temperature after `dt` seconds of braking with `braking`
+    /// deceleration at `speed`, cooling towards ambient all along.
+    pub fn brake_temperature(&self, temperature: f64, braking: f64, speed: f64, dt: f64) -> f64 {
+        let heating = self.brake_heating * braking * speed.abs();
+        let cooling = (temperature - AMBIENT_TEMPERATURE) * self.brake_cooling;
+        temperature + (heating - cooling) * dt
+    }
+
+    /// Deceleration the tyres can take on a road with the given `friction`
+    /// before locking up.
+    pub fn grip_deceleration(&self, friction: f64) -> f64 {
+        self.tire_grip * self.wear_grip_factor() * friction * GRAVITY
+    }
+
+    /// Share of the grip of new tyres left at the current wear.
+    pub fn wear_grip_factor(&self) -> f64 {
+        1.0 - (1.0 - self.worn_grip) * self.tire_wear.clamp(0.0, 1.0)
+    }
+
+    /// Wear added per second while moving at `speed` and braking with
+    /// `braking` deceleration, locked wheels wearing twice as fast.
+    pub fn tire_wear_per_second(&self, speed: f64, braking: f64, wheels_locked: bool) -> f64 {
+        let sliding = if wheels_locked { 2.0 } else { 1.0 };
+        self.tire_wear_rate * speed.abs() * (1.0 + braking / GRAVITY) * sliding
+    }
+
+    /// Fits a new set of tyres, for instance between campaign levels.
+    pub fn replace_tires(&mut self) {
+        self.tire_wear = 0.0;
+    }
+
+    /// Engine speed at `speed` in `gear`, or 0 for powertrains without an
+    /// engine. In neutral the engine idles. First gear and reverse slip below
+    /// idle so the car can pull away; in higher gears the RPM follows the wheels
+    /// down, which is what stalls a lugging engine.
+    pub fn rpm(&self, speed: f64, gear: i32) -> f64 {
+        match self.powertrain {
+            Powertrain::Direct | Powertrain::Electric => 0.0,
+            Powertrain::Combustion | Powertrain::Hybrid => {
+                let ratio = self.gearbox.ratio(gear);
+                if ratio == 0.0 {
+                    self.engine.idle_rpm
+                } else if (gear == 1 || gear == -1) && !self.uses_clutch() {
+                    self.engine.rpm(speed * ratio)
+                } else {
+                    speed * ratio * self.engine.rpm_per_speed
+                }
+            }
+        }
+    }
+
+    /// Gear engaged at `speed` after the automatic transmission, if any,
+    /// had its say.
+    pub fn select_gear(&self, speed: f64, gear: i32) -> i32 {
+        match self.powertrain {
+            Powertrain::Direct | Powertrain::Electric => gear,
+            Powertrain::Combustion | Powertrain::Hybrid => self.gearbox.automatic_gear(gear, self.rpm(speed, gear))
+        }
+    }
+
+    /// Whether the engine stalls when turning at `rpm` in `gear`.
+    pub fn stalls(&self, rpm: f64, gear: i32) -> bool {
+        self.has_engine() && gear != 0 && rpm < self.engine.stall_rpm
+    }
+
+    /// Acceleration produced by the powertrain for `throttle` at `rpm` in
+    /// `gear`, negative in reverse. The arcade powertrain uses the throttle
+    /// value as its force; a hybrid drives with its engine.
+    pub fn drive_acceleration(&self, throttle: f64, rpm: f64, gear: i32) -> f64 {
+        let direction = if gear < 0 { -1.0 } else { 1.0 };
+        let force = match self.powertrain {
+            Powertrain::Direct => throttle * direction,
+            Powertrain::Combustion | Powertrain::Hybrid => self.engine.force(throttle, rpm) * self.gearbox.ratio(gear),
+            Powertrain::Electric => throttle.clamp(0.0, 1.0) * self.motor_force * direction
+        };
+        force / self.mass
+    }
+
+    /// Acceleration produced by the electric motor alone for `throttle` in
+    /// `gear`, negative in reverse.
+    pub fn motor_acceleration(&self, throttle: f64, gear: i32) -> f64 {
+        let direction = if gear < 0 { -1.0 } else { 1.0 };
+        throttle.clamp(0.0, 1.0) * self.motor_force * direction / self.mass
+    }
+
+    /// Whether the electric motor alone drives the car at `throttle` and
+    /// `speed` with `battery` charge and `fuel` left. A hybrid with charge
+    /// left runs electric at low speed and light throttle, or whenever its
+    /// tank is dry.
+    pub fn electric_mode(&self, throttle: f64, speed: f64, battery: f64, fuel: f64) -> bool {
+        match self.powertrain {
+            Powertrain::Direct | Powertrain::Combustion => false,
+            Powertrain::Electric => true,
+            Powertrain::Hybrid => {
+                let tank_dry = self.uses_fuel() && fuel <= 0.0;
+                let light_load = speed.abs() < self.hybrid_switch_speed && throttle <= self.hybrid_engine_throttle;
+                battery > 0.0 && (tank_dry || light_load)
+            }
+        }
+    }
+
+    /// Whether the powertrain has a combustion engine, with its RPM, gears
+    /// and stalling.
+    pub fn has_engine(&self) -> bool {
+        matches!(self.powertrain, Powertrain::Combustion | Powertrain::Hybrid)
+    }
+
+    /// Deceleration from engine braking with `throttle` at `speed`, the
+    /// engine turning at `rpm` in `gear`. Only a closed throttle in gear
+    /// brakes; coasting in neutral leaves only drag and rolling resistance.
+    pub fn engine_braking(&self, throttle: f64, speed: f64, rpm: f64, gear: i32) -> f64 {
+        if !self.has_engine() || throttle > 0.0 || speed == 0.0 {
+            return 0.0;
+        }
+        self.engine.braking(rpm) * self.gearbox.ratio(gear).abs() / self.mass
+    }
+
+    pub fn uses_turbo(&self) -> bool {
+        self.has_engine() && self.turbo.max_gain > 0.0
+    }
+
+    pub fn uses_clutch(&self) -> bool {
+        self.has_engine() && self.clutch
+    }
+
+    /// Share of the engine's pull reaching the wheels with the clutch pedal
+    /// at `clutch`. Always 1 for cars without a clutch.
+    pub fn clutch_engagement(&self, clutch: f64) -> f64 {
+        if !self.uses_clutch() {
+            return 1.0;
+        }
+        1.0 - clutch.clamp(0.0, 1.0)
+    }
+
+    /// Engine speed with the clutch pedal at `clutch` when the wheels would
+    /// turn the engine at `rpm` in `gear`. A slipping clutch blends the
+    /// wheel speed with the revs the free engine reaches with `throttle`.
+    pub fn clutch_rpm(&self, rpm: f64, clutch: f64, throttle: f64, gear: i32) -> f64 {
+        if !self.uses_clutch() || gear == 0 {
+            return rpm;
+        }
+        let engagement = self.clutch_engagement(clutch);
+        rpm * engagement + self.engine.free_rpm(throttle) * (1.0 - engagement)
+    }
+
+    pub fn uses_fuel(&self) -> bool {
+        self.powertrain != Powertrain::Electric && self.fuel_consumption > 0.0
+    }
+
+    pub fn uses_battery(&self) -> bool {
+        matches!(self.powertrain, Powertrain::Electric | Powertrain::Hybrid) && self.energy_consumption > 0.0
+    }
+
+    /// Fuel burnt per second for `throttle` at `rpm`. Engines burn in
+    /// proportion to both; the arcade powertrain only to throttle.
+    pub fn fuel_rate(&self, throttle: f64, rpm: f64) -> f64 {
+        let throttle = throttle.clamp(0.0, 1.0);
+        match self.powertrain {
+            Powertrain::Direct => self.fuel_consumption * throttle,
+            Powertrain::Combustion | Powertrain::Hybrid => self.fuel_consumption * throttle * rpm / self.engine.redline_rpm,
+            Powertrain::Electric => 0.0
+        }
+    }
+
+    /// Net battery charge change per second: throttle drains it, braking a
+    /// moving car recharges it. Always 0 when the battery is not used.
+    pub fn battery_rate(&self, throttle: f64, brake: f64, speed: f64) -> f64 {
+        if !self.uses_battery() {
+            return 0.0;
+        }
+        let drain = self.energy_consumption * throttle.clamp(0.0, 1.0);
+        let regen = if speed != 0.0 {
+            self.energy_consumption * self.regen_efficiency * brake.clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        regen - drain
+    }
+
+    /// Deceleration the car undergoes while moving at `speed` with no pedal
+    /// pressed, on a road with the given `friction`, with air drag scaled by
+    /// `drag_factor` (below 1 in a slipstream). Friction only scales rolling
+    /// resistance; air drag doesn't care about the road. The value is a
+    /// magnitude against the direction of travel; a stopped car is not
+    /// pushed anywhere.
+    pub fn resistance_deceleration(&self, speed: f64, friction: f64, drag_factor: f64) -> f64 {
+        if !self.resistance || speed == 0.0 {
+            return 0.0;
+        }
+        self.rolling_resistance * friction + self.drag_coefficient * drag_factor * speed * speed / self.mass
+    }
+}
+
+impl Vehicle {
+    /// Checks that the car's physics make sense, a positive mass and no
+    /// negative forces or rates, naming the first field that doesn't.
+    pub fn check(&self) -> Result<(), String> {
+        positive("vehicle.mass", self.mass)?;
+        for (field, value) in [
+            ("brake_force", self.brake_force),
+            ("brake_heating", self.brake_heating),
+            ("brake_cooling", self.brake_cooling),
+            ("faded_brake", self.faded_brake),
+            ("tire_grip", self.tire_grip),
+            ("sliding_grip", self.sliding_grip),
+            ("tire_wear", self.tire_wear),
+            ("tire_wear_rate", self.tire_wear_rate),
+            ("worn_grip", self.worn_grip),
+            ("rolling_resistance", self.rolling_resistance),
+            ("drag_coefficient", self.drag_coefficient),
+            ("suspension.stiffness", self.suspension.stiffness),
+            ("suspension.damping", self.suspension.damping),
+            ("suspension.travel", self.suspension.travel),
+            ("limits.max_speed", self.limits.max_speed),
+            ("limits.max_acceleration", self.limits.max_acceleration),
+            ("limits.max_deceleration", self.limits.max_deceleration),
+            ("limits.max_reverse_speed", self.limits.max_reverse_speed),
+            ("engine.idle_rpm", self.engine.idle_rpm),
+            ("engine.stall_rpm", self.engine.stall_rpm),
+            ("engine.peak_torque_rpm", self.engine.peak_torque_rpm),
+            ("engine.redline_rpm", self.engine.redline_rpm),
+            ("engine.idle_torque", self.engine.idle_torque),
+            ("engine.redline_torque", self.engine.redline_torque),
+            ("engine.peak_force", self.engine.peak_force),
+            ("engine.braking_force", self.engine.braking_force),
+            ("engine.rpm_per_speed", self.engine.rpm_per_speed),
+            ("turbo.max_gain", self.turbo.max_gain),
+            ("turbo.spool_time", self.turbo.spool_time),
+            ("gearbox.reverse_ratio", self.gearbox.reverse_ratio),
+            ("gearbox.upshift_rpm", self.gearbox.upshift_rpm),
+            ("gearbox.downshift_rpm", self.gearbox.downshift_rpm),
+            ("tank_capacity", self.tank_capacity),
+            ("fuel_consumption", self.fuel_consumption),
+            ("motor_force", self.motor_force),
+            ("battery_capacity", self.battery_capacity),
+            ("energy_consumption", self.energy_consumption),
+            ("hybrid_switch_speed", self.hybrid_switch_speed),
+            ("hybrid_engine_throttle", self.hybrid_engine_throttle),
+            ("regen_efficiency", self.regen_efficiency)
+        ] {
+            non_negative(&format!("vehicle.{}", field), value)?;
+        }
+        finite("vehicle.brake_fade_temperature", self.brake_fade_temperature)?;
+        finite("vehicle.brake_max_temperature", self.brake_max_temperature)?;
+        if self.brake_max_temperature <= self.brake_fade_temperature {
+            return Err(format!(
+                "vehicle.brake_max_temperature must be above vehicle.brake_fade_temperature ({}), not {}",
+                self.brake_fade_temperature, self.brake_max_temperature
+            ));
+        }
+        if self.gearbox.ratios.is_empty() {
+            return Err(String::from("vehicle.gearbox.ratios must have at least one gear"));
+        }
+        for (index, &ratio) in self.gearbox.ratios.iter().enumerate() {
+            positive(&format!("vehicle.gearbox.ratios[{}]", index), ratio)?;
+        }
+        positive("vehicle.gearbox.final_drive", self.gearbox.final_drive)
+    }
+
+    /// Effective braking deceleration on a road with the given `friction`
+    /// with brakes at `brake_temperature`, and whether the wheels locked.
+    /// Without the grip limit, friction simply scales the brakes; with it,
+    /// the road only limits how much of the brakes the tyres can use.
+    pub fn braking(&self, brake: f64, friction: f64, speed: f64, brake_temperature: f64) -> (f64, bool) {
+        let requested = self.brake_deceleration(brake) * self.brake_fade(brake_temperature);
+        if !self.grip_limit {
+            return (requested * friction, false);
+        }
+        let grip = self.grip_deceleration(friction);
+        if speed != 0.0 && requested > grip && !self.abs {
+            (grip * self.sliding_grip, true)
+        } else {
+            (requested.min(grip), false)
+        }
+    }
+
+    /// Throttle that makes the powertrain produce `acceleration` at `rpm`
+    /// in `gear`. Pedal powertrains are limited to full throttle.
+    pub fn throttle_for(&self, acceleration: f64, rpm: f64, gear: i32) -> f64 {
+        let full = self.drive_acceleration(1.0, rpm, gear);
+        if full <= 0.0 || acceleration <= 0.0 {
+            return 0.0;
+        }
+        let throttle = acceleration / full;
+        match self.powertrain {
+            Powertrain::Direct => throttle,
+            Powertrain::Combustion | Powertrain::Electric | Powertrain::Hybrid => throttle.min(1.0)
+        }
+    }
+
+    /// Throttle cruise control applies to get from `speed` to `target`
+    /// while compensating `resistance`, the deceleration the road, air and
+    /// wind currently put on the car.
+    pub fn cruise_throttle(&self, target: f64, speed: f64, resistance: f64, rpm: f64, gear: i32) -> f64 {
+        self.throttle_for(CRUISE_GAIN * (target - speed) + resistance, rpm, gear)
+    }
+
+    /// Effective `drive` acceleration on a road with the given `friction`,
+    /// and whether the wheels spin. Asking for more than the tyres grip
+    /// spins them, leaving only the sliding grip; traction control trims
+    /// the drive to [`TRACTION_CONTROL_GRIP`] of the grip so they never do.
+    pub fn traction(&self, drive: f64, friction: f64) -> (f64, bool) {
+        if !self.grip_limit {
+            return (drive, false);
+        }
+        let grip = self.grip_deceleration(friction);
+        if self.traction_control {
+            (drive.min(grip * TRACTION_CONTROL_GRIP), false)
+        } else if drive > grip {
+            (grip * self.sliding_grip, true)
+        } else {
+            (drive, false)
+        }
+    }
+
+    /// Largest `drive` launch control lets through on a road with the
+    /// given `friction`: right up to the grip, so the wheels don't spin.
+    pub fn launch_drive(&self, drive: f64, friction: f64) -> f64 {
+        if !self.grip_limit {
+            return drive;
+        }
+        drive.min(self.grip_deceleration(friction))
+    }
+}
+
+impl Default for Vehicle {
+    fn default() -> Vehicle {
+        Vehicle {
+            mass: 1.0,
+            brake_force: 1.0,
+            brake_heating: 0.0,
+            brake_cooling: 0.05,
+            brake_fade_temperature: 300.0,
+            brake_max_temperature: 700.0,
+            faded_brake: 0.3,
+            grip_limit: false,
+            tire_grip: 1.0,
+            sliding_grip: 0.7,
+            abs: false,
+            traction_control: false,
+            tire_wear: 0.0,
+            tire_wear_rate: 0.0,
+            worn_grip: 0.5,
+            resistance: false,
+            rolling_resistance: 0.1,
+            drag_coefficient: 0.0005,
+            suspension: Suspension::default(),
+            limits: VehicleLimits::default(),
+            powertrain: Powertrain::Direct,
+            engine: Engine::default(),
+            turbo: Turbo::default(),
+            gearbox: Gearbox::default(),
+            clutch: false,
+            tank_capacity: 50.0,
+            fuel_consumption: 0.0,
+            motor_force: 4.0,
+            battery_capacity: 50.0,
+            energy_consumption: 0.0,
+            hybrid_switch_speed: 10.0,
+            hybrid_engine_throttle: 0.5,
+            regen_efficiency: 0.3
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn released_brake_should_not_decelerate(){
+        let vehicle = Vehicle { brake_force: 8.0, ..Default::default() };
+
+        assert_eq!(0.0, vehicle.brake_deceleration(0.0));
+    }
+
+    #[test]
+    fn full_brake_should_decelerate_with_brake_force(){
+        let vehicle = Vehicle { brake_force: 8.0, ..Default::default() };
+
+        assert_eq!(8.0, vehicle.brake_deceleration(1.0));
+    }
+
+    #[test]
+    fn half_brake_should_decelerate_less_than_half_brake_force(){
+        let vehicle = Vehicle { brake_force: 8.0, ..Default::default() };
+
+        assert_eq!(2.0, vehicle.brake_deceleration(0.5));
+    }
+
+    #[test]
+    fn brake_pedal_should_be_clamped_between_released_and_floored(){
+        let vehicle = Vehicle { brake_force: 8.0, ..Default::default() };
+
+        assert_eq!(8.0, vehicle.brake_deceleration(3.0));
+        assert_eq!(0.0, vehicle.brake_deceleration(-1.0));
+    }
+
+    #[test]
+    fn resistance_should_be_disabled_by_default(){
+        let vehicle = Vehicle::default();
+
+        assert_eq!(0.0, vehicle.resistance_deceleration(10.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn resistance_should_add_rolling_resistance_and_drag_on_speed_squared(){
+        let vehicle = Vehicle {
+            resistance: true,
+            rolling_resistance: 0.5,
+            drag_coefficient: 0.01,
+            ..Default::default()
+        };
+
+        assert_eq!(1.5, vehicle.resistance_deceleration(10.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn drag_factor_should_only_scale_air_drag(){
+        let vehicle = Vehicle {
+            resistance: true,
+            rolling_resistance: 0.5,
+            drag_coefficient: 0.01,
+            ..Default::default()
+        };
+
+        assert_eq!(1.0, vehicle.resistance_deceleration(10.0, 1.0, 0.5));
+    }
+
+    #[test]
+    fn resistance_should_not_act_on_a_stopped_car(){
+        let vehicle = Vehicle {
+            resistance: true,
+            ..Default::default()
+        };
+
+        assert_eq!(0.0, vehicle.resistance_deceleration(0.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn default_limits_should_not_clamp_anything(){
+        let limits = VehicleLimits::default();
+
+        assert_eq!(1e9, limits.clamp_speed(1e9));
+        assert_eq!(-1e9, limits.clamp_acceleration(-1e9));
+    }
+
+    #[test]
+    fn limits_should_clamp_acceleration_between_max_deceleration_and_max_acceleration(){
+        let limits = VehicleLimits::new(f64::MAX, 3.0, 8.0);
+
+        assert_eq!(3.0, limits.clamp_acceleration(5.0));
+        assert_eq!(-8.0, limits.clamp_acceleration(-10.0));
+    }
+
+    #[test]
+    fn limits_should_clamp_speed_between_zero_and_max_speed(){
+        let limits = VehicleLimits::new(30.0, f64::MAX, f64::MAX);
+
+        assert_eq!(30.0, limits.clamp_speed(31.0));
+        assert_eq!(0.0, limits.clamp_speed(-1.0));
+    }
+
+    #[test]
+    fn direct_powertrain_should_use_throttle_as_acceleration(){
+        let vehicle = Vehicle::default();
+
+        assert_eq!(0.0, vehicle.rpm(20.0, 1));
+        assert_eq!(3.0, vehicle.drive_acceleration(3.0, 0.0, 1));
+    }
+
+    #[test]
+    fn combustion_powertrain_should_use_engine_torque_curve(){
+        let vehicle = Vehicle { powertrain: Powertrain::Combustion, ..Default::default() };
+        let rpm = vehicle.rpm(20.0, 1);
+
+        assert_eq!(vehicle.engine.rpm(20.0), rpm);
+        assert_eq!(vehicle.engine.force(1.0, rpm), vehicle.drive_acceleration(1.0, rpm, 1));
+    }
+
+    #[test]
+    fn lower_gears_should_rev_higher_and_pull_harder(){
+        let vehicle = Vehicle {
+            powertrain: Powertrain::Combustion,
+            gearbox: Gearbox::new(vec![2.0, 1.0], 1.0),
+            ..Default::default()
+        };
+
+        assert_eq!(2.0 * vehicle.rpm(10.0, 2), vehicle.rpm(10.0, 1));
+        assert_eq!(2.0 * vehicle.engine.force(1.0, 3000.0), vehicle.drive_acceleration(1.0, 3000.0, 1));
+    }
+
+    #[test]
+    fn engine_braking_should_only_act_at_closed_throttle_in_gear(){
+        let vehicle = Vehicle {
+            powertrain: Powertrain::Combustion,
+            engine: Engine { braking_force: 1.0, redline_rpm: 6000.0, ..Default::default() },
+            gearbox: Gearbox::new(vec![2.0, 1.0], 1.0),
+            ..Default::default()
+        };
+
+        assert_eq!(1.0, vehicle.engine_braking(0.0, 10.0, 3000.0, 1));
+        assert_eq!(0.5, vehicle.engine_braking(0.0, 10.0, 3000.0, 2));
+        assert_eq!(0.0, vehicle.engine_braking(0.0, 10.0, 3000.0, 0));
+        assert_eq!(0.0, vehicle.engine_braking(0.5, 10.0, 3000.0, 1));
+        assert_eq!(0.0, vehicle.engine_braking(0.0, 0.0, 3000.0, 1));
+    }
+
+    #[test]
+    fn launch_drive_should_stop_at_the_grip(){
+        let vehicle = Vehicle { grip_limit: true, ..Default::default() };
+
+        assert_eq!(GRAVITY, vehicle.launch_drive(20.0, 1.0));
+        assert_eq!(5.0, vehicle.launch_drive(5.0, 1.0));
+        assert_eq!(20.0, Vehicle::default().launch_drive(20.0, 1.0));
+    }
+
+    #[test]
+    fn clutch_should_blend_wheel_and_free_engine_speed(){
+        let vehicle = Vehicle { powertrain: Powertrain::Combustion, clutch: true, ..Default::default() };
+        let free = vehicle.engine.free_rpm(0.5);
+
+        assert_eq!(0.0, vehicle.rpm(0.0, 1));
+        assert_eq!(3000.0, vehicle.clutch_rpm(3000.0, 0.0, 0.5, 1));
+        assert_eq!(free, vehicle.clutch_rpm(3000.0, 1.0, 0.5, 1));
+        assert_eq!(0.5, vehicle.clutch_engagement(0.5));
+    }
+
+    #[test]
+    fn cars_without_a_clutch_should_ignore_the_pedal(){
+        let vehicle = Vehicle { powertrain: Powertrain::Combustion, ..Default::default() };
+
+        assert_eq!(1.0, vehicle.clutch_engagement(1.0));
+        assert_eq!(3000.0, vehicle.clutch_rpm(3000.0, 1.0, 0.5, 1));
+    }
+
+    #[test]
+    fn hybrid_should_only_run_electric_with_charge_at_light_load_or_dry_tank(){
+        let hybrid = Vehicle { powertrain: Powertrain::Hybrid, fuel_consumption: 1.0, ..Default::default() };
+
+        assert!(hybrid.electric_mode(0.3, 5.0, 1.0, 1.0));
+        assert!(!hybrid.electric_mode(0.8, 5.0, 1.0, 1.0));
+        assert!(!hybrid.electric_mode(0.3, 20.0, 1.0, 1.0));
+        assert!(!hybrid.electric_mode(0.3, 5.0, 0.0, 1.0));
+        assert!(hybrid.electric_mode(0.8, 20.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn neutral_should_idle_and_not_drive(){
+        let vehicle = Vehicle {
+            powertrain: Powertrain::Combustion,
+            gearbox: Gearbox::five_speed(),
+            ..Default::default()
+        };
+
+        assert_eq!(vehicle.engine.idle_rpm, vehicle.rpm(20.0, 0));
+        assert_eq!(0.0, vehicle.drive_acceleration(1.0, vehicle.engine.idle_rpm, 0));
+        assert!(!vehicle.stalls(0.0, 0));
+    }
+
+    #[test]
+    fn engine_should_stall_below_stall_rpm_in_gear(){
+        let vehicle = Vehicle {
+            powertrain: Powertrain::Combustion,
+            gearbox: Gearbox::five_speed(),
+            ..Default::default()
+        };
+
+        assert!(vehicle.stalls(vehicle.rpm(1.0, 3), 3));
+        assert!(!vehicle.stalls(vehicle.rpm(0.0, 1), 1));
+    }
+
+    #[test]
+    fn fuel_system_should_be_disabled_by_default(){
+        let vehicle = Vehicle::default();
+
+        assert!(!vehicle.uses_fuel());
+        assert_eq!(0.0, vehicle.fuel_rate(1.0, 0.0));
+    }
+
+    #[test]
+    fn fuel_rate_should_be_proportional_to_throttle(){
+        let vehicle = Vehicle { fuel_consumption: 2.0, ..Default::default() };
+
+        assert_eq!(1.0, vehicle.fuel_rate(0.5, 0.0));
+    }
+
+    #[test]
+    fn engine_fuel_rate_should_be_proportional_to_throttle_and_rpm(){
+        let vehicle = Vehicle {
+            powertrain: Powertrain::Combustion,
+            fuel_consumption: 2.0,
+            ..Default::default()
+        };
+        let half_redline = vehicle.engine.redline_rpm / 2.0;
+
+        assert_eq!(2.0, vehicle.fuel_rate(1.0, vehicle.engine.redline_rpm));
+        assert_eq!(0.5, vehicle.fuel_rate(0.5, half_redline));
+    }
+
+    fn electric_car() -> Vehicle {
+        Vehicle {
+            powertrain: Powertrain::Electric,
+            energy_consumption: 2.0,
+            regen_efficiency: 0.5,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn electric_motor_should_give_flat_acceleration(){
+        let vehicle = electric_car();
+
+        assert_eq!(2.0, vehicle.drive_acceleration(0.5, 0.0, 1));
+        assert_eq!(0.0, vehicle.rpm(30.0, 1));
+    }
+
+    #[test]
+    fn electric_car_should_drain_battery_and_not_burn_fuel(){
+        let vehicle = Vehicle { fuel_consumption: 2.0, ..electric_car() };
+
+        assert!(!vehicle.uses_fuel());
+        assert_eq!(0.0, vehicle.fuel_rate(1.0, 0.0));
+        assert_eq!(-2.0, vehicle.battery_rate(1.0, 0.0, 10.0));
+    }
+
+    #[test]
+    fn braking_a_moving_electric_car_should_recharge_the_battery(){
+        let vehicle = electric_car();
+
+        assert_eq!(1.0, vehicle.battery_rate(0.0, 1.0, 10.0));
+        assert_eq!(0.0, vehicle.battery_rate(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn combustion_car_should_not_use_the_battery(){
+        let vehicle = Vehicle { powertrain: Powertrain::Combustion, energy_consumption: 2.0, ..Default::default() };
+
+        assert!(!vehicle.uses_battery());
+        assert_eq!(0.0, vehicle.battery_rate(1.0, 0.0, 10.0));
+    }
+
+    #[test]
+    fn heavier_vehicle_should_accelerate_less_for_the_same_throttle(){
+        let car = Vehicle { mass: 1.0, ..Default::default() };
+        let truck = Vehicle { mass: 4.0, ..Default::default() };
+
+        assert_eq!(1.0, car.drive_acceleration(1.0, 0.0, 1));
+        assert_eq!(0.25, truck.drive_acceleration(1.0, 0.0, 1));
+    }
+
+    #[test]
+    fn heavier_vehicle_should_brake_less_for_the_same_brake_force(){
+        let truck = Vehicle { mass: 4.0, brake_force: 8.0, ..Default::default() };
+
+        assert_eq!(2.0, truck.brake_deceleration(1.0));
+    }
+
+    #[test]
+    fn mass_should_scale_drag_but_not_rolling_resistance(){
+        let truck = Vehicle {
+            mass: 2.0,
+            resistance: true,
+            rolling_resistance: 0.5,
+            drag_coefficient: 0.02,
+            ..Default::default()
+        };
+
+        assert_eq!(1.5, truck.resistance_deceleration(10.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn friction_should_scale_rolling_resistance_but_not_drag(){
+        let vehicle = Vehicle {
+            resistance: true,
+            rolling_resistance: 0.5,
+            drag_coefficient: 0.01,
+            ..Default::default()
+        };
+
+        assert_eq!(1.25, vehicle.resistance_deceleration(10.0, 0.5, 1.0));
+    }
+
+    fn gripping_car() -> Vehicle {
+        Vehicle {
+            grip_limit: true,
+            tire_grip: 1.0,
+            sliding_grip: 0.5,
+            brake_force: 2.0 * GRAVITY,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn braking_without_grip_limit_should_scale_with_friction(){
+        let vehicle = Vehicle { brake_force: 8.0, ..Default::default() };
+
+        assert_eq!((4.0, false), vehicle.braking(1.0, 0.5, 10.0, AMBIENT_TEMPERATURE));
+    }
+
+    #[test]
+    fn braking_within_grip_should_not_lock_the_wheels(){
+        let vehicle = gripping_car();
+
+        assert_eq!((GRAVITY / 2.0, false), vehicle.braking(0.5, 1.0, 10.0, AMBIENT_TEMPERATURE));
+    }
+
+    #[test]
+    fn braking_beyond_grip_should_lock_the_wheels_and_slide(){
+        let vehicle = gripping_car();
+
+        assert_eq!((GRAVITY / 2.0, true), vehicle.braking(1.0, 1.0, 10.0, AMBIENT_TEMPERATURE));
+    }
+
+    #[test]
+    fn low_friction_should_lock_the_wheels_sooner(){
+        let vehicle = gripping_car();
+
+        assert!(!vehicle.braking(0.5, 1.0, 10.0, AMBIENT_TEMPERATURE).1);
+        assert!(vehicle.braking(0.5, 0.1, 10.0, AMBIENT_TEMPERATURE).1);
+    }
+
+    #[test]
+    fn wheels_should_not_lock_when_stopped(){
+        let vehicle = gripping_car();
+
+        assert_eq!((GRAVITY, false), vehicle.braking(1.0, 1.0, 0.0, AMBIENT_TEMPERATURE));
+    }
+
+    #[test]
+    fn worn_tires_should_have_less_grip(){
+        let new_tires = Vehicle { worn_grip: 0.5, ..Default::default() };
+        let half_worn = Vehicle { tire_wear: 0.5, ..new_tires.clone() };
+        let bald = Vehicle { tire_wear: 1.0, ..new_tires.clone() };
+
+        assert_eq!(GRAVITY, new_tires.grip_deceleration(1.0));
+        assert_eq!(0.75 * GRAVITY, half_worn.grip_deceleration(1.0));
+        assert_eq!(0.5 * GRAVITY, bald.grip_deceleration(1.0));
+    }
+
+    #[test]
+    fn tire_wear_should_grow_with_speed_and_braking(){
+        let vehicle = Vehicle { tire_wear_rate: 0.001, ..Default::default() };
+
+        assert_eq!(0.0, vehicle.tire_wear_per_second(0.0, 0.0, false));
+        assert_eq!(0.01, vehicle.tire_wear_per_second(10.0, 0.0, false));
+        assert_eq!(0.02, vehicle.tire_wear_per_second(10.0, GRAVITY, false));
+        assert_eq!(0.04, vehicle.tire_wear_per_second(10.0, GRAVITY, true));
+    }
+
+    #[test]
+    fn replacing_tires_should_reset_wear(){
+        let mut vehicle = Vehicle { tire_wear: 0.8, ..Default::default() };
+        vehicle.replace_tires();
+
+        assert_eq!(0.0, vehicle.tire_wear);
+    }
+
+    #[test]
+    fn brakes_should_not_fade_below_fade_temperature(){
+        let vehicle = Vehicle::default();
+
+        assert_eq!(1.0, vehicle.brake_fade(AMBIENT_TEMPERATURE));
+        assert_eq!(1.0, vehicle.brake_fade(vehicle.brake_fade_temperature));
+    }
+
+    #[test]
+    fn brakes_should_fade_linearly_up_to_max_temperature(){
+        let vehicle = Vehicle {
+            brake_fade_temperature: 300.0,
+            brake_max_temperature: 500.0,
+            faded_brake: 0.5,
+            ..Default::default()
+        };
+
+        assert_eq!(0.75, vehicle.brake_fade(400.0));
+        assert_eq!(0.5, vehicle.brake_fade(500.0));
+        assert_eq!(0.5, vehicle.brake_fade(900.0));
+    }
+
+    #[test]
+    fn brakes_should_fade_at_once_when_max_temperature_is_fade_temperature(){
+        let vehicle = Vehicle { brake_fade_temperature: 300.0, brake_max_temperature: 300.0, faded_brake: 0.5, ..Default::default() };
+
+        assert_eq!(1.0, vehicle.brake_fade(300.0));
+        assert_eq!(0.5, vehicle.brake_fade(300.1));
+    }
+
+    #[test]
+    fn hot_brakes_should_brake_less(){
+        let vehicle = Vehicle { brake_force: 8.0, faded_brake: 0.5, ..Default::default() };
+
+        assert_eq!((4.0, false), vehicle.braking(1.0, 1.0, 10.0, vehicle.brake_max_temperature));
+    }
+
+    #[test]
+    fn braking_should_heat_the_brakes(){
+        let vehicle = Vehicle { brake_heating: 0.5, brake_cooling: 0.0, ..Default::default() };
+
+        assert_eq!(AMBIENT_TEMPERATURE + 40.0, vehicle.brake_temperature(AMBIENT_TEMPERATURE, 4.0, 20.0, 1.0));
+    }
+
+    #[test]
+    fn brakes_should_cool_towards_ambient(){
+        let vehicle = Vehicle { brake_cooling: 0.5, ..Default::default() };
+
+        assert_eq!(AMBIENT_TEMPERATURE + 50.0, vehicle.brake_temperature(AMBIENT_TEMPERATURE + 100.0, 0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn abs_should_hold_braking_at_peak_grip_instead_of_locking(){
+        let vehicle = Vehicle { abs: true, ..gripping_car() };
+
+        assert_eq!((GRAVITY, false), vehicle.braking(1.0, 1.0, 10.0, AMBIENT_TEMPERATURE));
+    }
+
+    #[test]
+    fn abs_should_not_change_braking_within_grip(){
+        let vehicle = Vehicle { abs: true, ..gripping_car() };
+
+        assert_eq!(gripping_car().braking(0.5, 1.0, 10.0, AMBIENT_TEMPERATURE), vehicle.braking(0.5, 1.0, 10.0, AMBIENT_TEMPERATURE));
+    }
+
+    #[test]
+    fn drive_within_grip_should_not_spin_the_wheels(){
+        let vehicle = gripping_car();
+
+        assert_eq!((GRAVITY / 2.0, false), vehicle.traction(GRAVITY / 2.0, 1.0));
+    }
+
+    #[test]
+    fn drive_beyond_grip_should_spin_the_wheels(){
+        let vehicle = gripping_car();
+
+        assert_eq!((GRAVITY / 2.0, true), vehicle.traction(2.0 * GRAVITY, 1.0));
+    }
+
+    #[test]
+    fn traction_control_should_trim_drive_below_grip(){
+        let vehicle = Vehicle { traction_control: true, ..gripping_car() };
+
+        assert_eq!((GRAVITY * TRACTION_CONTROL_GRIP, false), vehicle.traction(2.0 * GRAVITY, 1.0));
+        assert_eq!((1.0, false), vehicle.traction(1.0, 1.0));
+    }
+
+    #[test]
+    fn drive_should_not_be_limited_without_grip_limit(){
+        let vehicle = Vehicle { traction_control: true, ..Default::default() };
+
+        assert_eq!((100.0, false), vehicle.traction(100.0, 1.0));
+    }
+
+    #[test]
+    fn throttle_for_should_invert_drive_acceleration(){
+        let truck = Vehicle { mass: 4.0, ..Default::default() };
+        let electric = Vehicle { powertrain: Powertrain::Electric, motor_force: 4.0, ..Default::default() };
+
+        assert_eq!(8.0, truck.throttle_for(2.0, 0.0, 1));
+        assert_eq!(0.5, electric.throttle_for(2.0, 0.0, 1));
+        assert_eq!(1.0, electric.throttle_for(20.0, 0.0, 1));
+        assert_eq!(0.0, electric.throttle_for(-1.0, 0.0, 1));
+    }
+
+    #[test]
+    fn cruise_throttle_should_correct_speed_error_and_resistance(){
+        let vehicle = Vehicle::default();
+
+        assert_eq!(CRUISE_GAIN * 2.0 + 0.5, vehicle.cruise_throttle(12.0, 10.0, 0.5, 0.0, 1));
+        assert_eq!(0.0, vehicle.cruise_throttle(10.0, 12.0, 0.5, 0.0, 1));
+    }
+
+    #[test]
+    fn limits_should_cap_reverse_speed_when_reversing_is_allowed(){
+        let limits = VehicleLimits { max_reverse_speed: 5.0, ..Default::default() };
+
+        assert!(limits.can_reverse());
+        assert!(!VehicleLimits::default().can_reverse());
+        assert_eq!(-3.0, limits.clamp_speed(-3.0));
+        assert_eq!(-5.0, limits.clamp_speed(-8.0));
+    }
+
+    #[test]
+    fn reverse_gear_should_drive_backwards_for_every_powertrain(){
+        let electric = Vehicle { powertrain: Powertrain::Electric, motor_force: 2.0, ..Default::default() };
+        let combustion = Vehicle { powertrain: Powertrain::Combustion, gearbox: Gearbox::five_speed(), ..Default::default() };
+
+        assert_eq!(-1.0, Vehicle::default().drive_acceleration(1.0, 0.0, -1));
+        assert_eq!(-2.0, electric.drive_acceleration(1.0, 0.0, -1));
+        let rpm = combustion.rpm(-5.0, -1);
+        assert!(rpm > 0.0);
+        assert!(combustion.drive_acceleration(1.0, rpm, -1) < 0.0);
+    }
+}
@@ -0,0 +1,142 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::rng::Rng;
+
+/// Weather over the whole track, for the front end to render.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Weather {
+    Dry,
+    Rain,
+    Snow
+}
+
+impl Weather {
+    /// Scales the road friction.
+    pub fn friction(self) -> f64 {
+        match self {
+            Weather::Dry => 1.0,
+            Weather::Rain => 0.7,
+            Weather::Snow => 0.3
+        }
+    }
+
+    /// Scales the air drag: rain and snow thicken the air the car pushes
+    /// through.
+    pub fn drag(self) -> f64 {
+        match self {
+            Weather::Dry => 1.0,
+            Weather::Rain => 1.1,
+            Weather::Snow => 1.2
+        }
+    }
+}
+
+/// Weather schedule of a level: it starts with `initial` and, when
+/// `change_period` is positive, may change every `change_period` seconds.
+///
+/// Changes are seeded: a given seed always brings the same weather at the
+/// same time, so runs stay reproducible.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Forecast {
+    pub initial: Weather,
+    /// Seconds between two possible weather changes. 0 keeps the initial
+    /// weather for the whole run.
+    pub change_period: f64,
+    pub seed: u64
+}
+
+#[wasm_bindgen]
+impl Forecast {
+    #[wasm_bindgen(constructor)]
+    pub fn new(initial: Weather, change_period: f64, seed: u64) -> Forecast {
+        Forecast {
+            initial,
+            change_period,
+            seed
+        }
+    }
+
+    /// Weather after `time` seconds of the run.
+    pub fn weather_at(&self, time: f64) -> Weather {
+        if self.change_period <= 0.0 {
+            return self.initial;
+        }
+        let period = (time / self.change_period).floor().max(0.0) as u64;
+        if period == 0 {
+            return self.initial;
+        }
+        let mut rng = Rng::keyed(self.seed, period);
+        match rng.next_u64() % 3 {
+            0 => Weather::Dry,
+            1 => Weather::Rain,
+            _ => Weather::Snow
+        }
+    }
+}
+
+impl Default for Forecast {
+    fn default() -> Forecast {
+        Forecast {
+            initial: Weather::Dry,
+            change_period: 0.0,
+            seed: 0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn default_forecast_should_stay_dry(){
+        assert_eq!(Weather::Dry, Forecast::default().weather_at(1000.0));
+    }
+
+    #[test]
+    fn forecast_should_start_with_the_initial_weather(){
+        let forecast = Forecast::new(Weather::Snow, 30.0, 5);
+
+        assert_eq!(Weather::Snow, forecast.weather_at(0.0));
+        assert_eq!(Weather::Snow, forecast.weather_at(29.0));
+    }
+
+    #[test]
+    fn weather_should_only_change_at_period_boundaries(){
+        let forecast = Forecast::new(Weather::Dry, 30.0, 5);
+
+        for period in 1..20 {
+            let start = period as f64 * 30.0;
+            assert_eq!(forecast.weather_at(start), forecast.weather_at(start + 29.0));
+        }
+    }
+
+    #[test]
+    fn weather_should_change_over_a_long_run(){
+        let forecast = Forecast::new(Weather::Dry, 30.0, 5);
+
+        assert!((1..20).any(|period| forecast.weather_at(period as f64 * 30.0) != Weather::Dry));
+    }
+
+    #[test]
+    fn same_seed_should_bring_the_same_weather(){
+        let a = Forecast::new(Weather::Dry, 10.0, 11);
+        let b = Forecast::new(Weather::Dry, 10.0, 11);
+
+        for period in 0..20 {
+            assert_eq!(a.weather_at(period as f64 * 10.0), b.weather_at(period as f64 * 10.0));
+        }
+    }
+
+    #[test]
+    fn wet_weather_should_lower_friction_and_raise_drag(){
+        assert!(Weather::Rain.friction() < Weather::Dry.friction());
+        assert!(Weather::Snow.friction() < Weather::Rain.friction());
+        assert!(Weather::Snow.drag() > Weather::Dry.drag());
+    }
+}
@@ -0,0 +1,112 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::rng::Rng;
+
+/// Head or tail wind blowing along the track, optionally gusty.
+///
+/// Gusts are seeded noise over time: a given seed always gusts the same
+/// way, so runs stay reproducible.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Wind {
+    /// Steady force; positive is a tailwind pushing the car forward,
+    /// negative a headwind.
+    pub force: f64,
+    /// Largest force gusts add or remove on top of the steady force.
+    pub gust_strength: f64,
+    /// Seconds between two gust peaks.
+    pub gust_period: f64,
+    pub seed: u64
+}
+
+#[wasm_bindgen]
+impl Wind {
+    #[wasm_bindgen(constructor)]
+    pub fn new(force: f64, gust_strength: f64, seed: u64) -> Wind {
+        Wind {
+            force,
+            gust_strength,
+            seed,
+            ..Wind::default()
+        }
+    }
+
+    /// Wind force after `time` seconds of the run.
+    pub fn force_at(&self, time: f64) -> f64 {
+        if self.gust_strength == 0.0 || self.gust_period <= 0.0 {
+            return self.force;
+        }
+        let phase = time / self.gust_period;
+        let key = phase.floor();
+        let t = phase - key;
+        let eased = t * t * (3.0 - 2.0 * t);
+        let from = self.gust(key as i64);
+        let to = self.gust(key as i64 + 1);
+        self.force + self.gust_strength * (from + (to - from) * eased)
+    }
+
+    /// Gust peak number `key`, between -1 and 1.
+    fn gust(&self, key: i64) -> f64 {
+        let mut rng = Rng::keyed(self.seed, key as u64);
+        rng.next_f64() * 2.0 - 1.0
+    }
+}
+
+impl Default for Wind {
+    fn default() -> Wind {
+        Wind {
+            force: 0.0,
+            gust_strength: 0.0,
+            gust_period: 2.0,
+            seed: 0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn calm_wind_should_have_no_force(){
+        assert_eq!(0.0, Wind::default().force_at(3.0));
+    }
+
+    #[test]
+    fn steady_wind_should_have_constant_force(){
+        let wind = Wind::new(-2.0, 0.0, 0);
+
+        assert_eq!(-2.0, wind.force_at(0.0));
+        assert_eq!(-2.0, wind.force_at(10.0));
+    }
+
+    #[test]
+    fn gusts_should_stay_within_gust_strength(){
+        let wind = Wind::new(1.0, 0.5, 3);
+
+        for step in 0..200 {
+            let force = wind.force_at(step as f64 * 0.1);
+            assert!((0.5..=1.5).contains(&force));
+        }
+    }
+
+    #[test]
+    fn gusts_should_vary_over_time(){
+        let wind = Wind::new(0.0, 1.0, 3);
+
+        assert_ne!(wind.force_at(0.0), wind.force_at(wind.gust_period));
+    }
+
+    #[test]
+    fn same_seed_should_gust_the_same_way(){
+        let a = Wind::new(0.0, 1.0, 11);
+        let b = Wind::new(0.0, 1.0, 11);
+        let c = Wind::new(0.0, 1.0, 12);
+
+        assert_eq!(a.force_at(4.3), b.force_at(4.3));
+        assert_ne!(a.force_at(4.3), c.force_at(4.3));
+    }
+}
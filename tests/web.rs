@@ -5,9 +5,49 @@
 extern crate wasm_bindgen_test;
 use wasm_bindgen_test::*;
 
+use js_sys::{Array, Function, Reflect};
+use wasm_bindgen::JsValue;
+use wasm_car_simulator::{Event, Level, Simulation, State};
+
 wasm_bindgen_test_configure!(run_in_browser);
 
 #[wasm_bindgen_test]
 fn pass() {
     assert_eq!(1 + 1, 2);
 }
+
+#[wasm_bindgen_test]
+fn listeners_should_be_called_on_their_event_only() {
+    let calls = Array::new();
+    let global = js_sys::global();
+    Reflect::set(&global, &JsValue::from_str("calls"), &calls).unwrap();
+    let callback = Function::new_with_args("event", "calls.push(event)");
+    let state = State { position: 9500.0, ..State::new() };
+    let mut simulation = Simulation::new(state, 16.0);
+    simulation.add_listener(Event::Won, callback.clone());
+    simulation.add_listener(Event::Lost, callback);
+
+    simulation.step();
+    simulation.step();
+    assert_eq!(1, calls.length());
+    assert_eq!(JsValue::from(Event::Won), calls.get(0));
+}
+
+#[wasm_bindgen_test]
+fn state_should_round_trip_through_a_plain_object() {
+    let mut level = Level::default();
+    level.set_seed(u64::MAX - 1);
+    let state = State { position: 1234.5, speed: 6.0, ..State::from_level(&level) };
+    let object = state.to_js().unwrap();
+
+    assert_eq!(JsValue::from(1234.5), Reflect::get(&object, &JsValue::from_str("position")).unwrap());
+    let restored = State::from_js(&object).unwrap();
+    assert_eq!(state.position, restored.position);
+    assert_eq!(state.speed, restored.speed);
+    assert_eq!(state.seed, restored.seed);
+    assert_eq!(state.wind.seed, restored.wind.seed);
+    assert_eq!(state.forecast.seed, restored.forecast.seed);
+    assert_eq!(state.endless.seed, restored.endless.seed);
+    assert_eq!(state.target_time, restored.target_time);
+    assert!(State::from_js(&JsValue::from(3)).is_err());
+}